@@ -3,20 +3,20 @@ use openbabel;
 use chiral_db_sources;
 use openbabel::fingerprint::Kind;
 
-fn get_ecfp(smiles: &String, fpg: &openbabel::fingerprint::FingerprintGenerator) -> cxx::UniquePtr<cxx::CxxVector<u32>> {
-    let mol = openbabel::molecule::Molecule::new_from_smiles(smiles);
+fn get_ecfp(smiles: &String, fpg: &openbabel::fingerprint::FingerprintGenerator) -> Vec<u32> {
+    let mol = openbabel::molecule::Molecule::from_smiles(smiles).unwrap();
     fpg.get_fingerprint(&mol)
 }
 
 fn get_ecfp_for_mols(smiles_vec: &Vec<String>, fpg: &openbabel::fingerprint::FingerprintGenerator) {
     for smiles in smiles_vec.iter() {
-        let mol = openbabel::molecule::Molecule::new_from_smiles(smiles);
+        let mol = openbabel::molecule::Molecule::from_smiles(smiles).unwrap();
         fpg.get_fingerprint(&mol);
     }
 }
 
 fn criterion_benchmark(c: &mut criterion::Criterion) {
-    let fpg = openbabel::fingerprint::FingerprintGenerator::new(Kind::ECFP4 { nbits: 2048 });
+    let fpg = openbabel::fingerprint::FingerprintGenerator::new(Kind::ECFP4 { nbits: 2048 }).unwrap();
     
     let sc = chiral_db_sources::chembl::SourceChembl::new_default();
     c.bench_function("ECFP4 fingerprint generation - 1 mol", |b| b.iter(|| get_ecfp(criterion::black_box(&String::from("c1ccccc1N")), &fpg))); 
@@ -12,20 +12,34 @@ use openbabel;
 
 fn query_substructure(smiles_vec: &Vec<String>, sps: &Vec<openbabel::smartspattern::SmartsPattern>) {
     for smiles in smiles_vec.iter() {
-        let mol = openbabel::molecule::Molecule::new_from_smiles(smiles);
+        let mol = openbabel::molecule::Molecule::from_smiles(smiles).unwrap();
         for sp in sps.iter() {
             sp.find_match(&mol);
         }
     }
 }
 
+fn screen_substructure(smiles_vec: &Vec<String>, sps: &Vec<openbabel::smartspattern::SmartsPattern>) {
+    for smiles in smiles_vec.iter() {
+        let mol = openbabel::molecule::Molecule::from_smiles(smiles).unwrap();
+        for sp in sps.iter() {
+            sp.has_match(&mol);
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+fn query_substructure_par(smiles_vec: &Vec<String>, sps: &Vec<openbabel::smartspattern::SmartsPattern>) {
+    openbabel::parallel::find_matches_for_smiles(sps, smiles_vec);
+}
+
 fn criterion_benchmark(c: &mut criterion::Criterion) {
     let sps: Vec<openbabel::smartspattern::SmartsPattern> = vec![
         "CC1=C(C=C(C=C1)C(N)=O)C#CC1=CN=CC=C1",
         "CC1=CC2=C(S1)C(=O)NC(C)=N2",
         "CN1C(=O)N(C)C2=C1C=NC(N)=N2"
     ].iter()
-    .map(|s| openbabel::smartspattern::SmartsPattern::new_from_smarts(s))
+    .map(|s| openbabel::smartspattern::SmartsPattern::from_smarts(s).unwrap())
     .collect();
 
     let sc = chiral_db_sources::chembl::SourceChembl::new_default();
@@ -34,6 +48,9 @@ fn criterion_benchmark(c: &mut criterion::Criterion) {
             .map(|ec| ec.smiles.clone())
             .collect();
         c.bench_function(format!("Substructure search - {} mols", count).as_str(), |b| b.iter(|| query_substructure(criterion::black_box(&smiles_vec), &sps))); 
+        c.bench_function(format!("Substructure screen - {} mols", count).as_str(), |b| b.iter(|| screen_substructure(criterion::black_box(&smiles_vec), &sps)));
+        #[cfg(feature = "rayon")]
+        c.bench_function(format!("Parallel substructure search - {} mols", count).as_str(), |b| b.iter(|| query_substructure_par(criterion::black_box(&smiles_vec), &sps)));
     }
 }
 
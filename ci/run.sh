@@ -5,5 +5,7 @@ set -ex
 cargo test --target $TARGET --no-run
  
 if [ -z "$NO_RUN" ]; then
-    cargo test --lib -- --test-threads=1
+    cargo test --lib
+    cargo test --lib --features rayon
+    cargo test -p openbabel-sys --lib -- --test-threads=1
 fi
\ No newline at end of file
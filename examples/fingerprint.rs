@@ -3,8 +3,8 @@ use openbabel::fingerprint::Kind;
 
 fn main() {
     let fpk = Kind::ECFP4 { nbits: 4096 };
-    let mol = openbabel::molecule::Molecule::new_from_smiles("c1ccccc1");
-    let fpg = openbabel::fingerprint::FingerprintGenerator::new(fpk);
+    let mol = openbabel::molecule::Molecule::from_smiles("c1ccccc1").unwrap();
+    let fpg = openbabel::fingerprint::FingerprintGenerator::new(fpk).unwrap();
     let fpd = fpg.get_fingerprint(&mol);
     println!("ECFP for mole c1ccccc1: {:?}", fpd);
 }
\ No newline at end of file
@@ -22,6 +22,13 @@ fn main() {
         std::fs::create_dir_all(&data_ob).unwrap();
         fs_extra::dir::copy("src/data", data_ob.to_str().unwrap(), &fs_extra::dir::CopyOptions::new()).unwrap();
     }
+    // pattern files read at runtime by PatternFP (FP3, FP4)
+    for data_file in ["patterns.txt", "SMARTS_InteLigand.txt"] {
+        let data_file_ob = data_ob.join(data_file);
+        if !data_file_ob.exists() {
+            std::fs::copy(std::path::Path::new("openbabel/data").join(data_file), data_file_ob).unwrap();
+        }
+    }
 
     // babelconfig.h
     let include_ob = include.join("openbabel");
@@ -45,7 +52,8 @@ fn main() {
     ).unwrap();
 
     // Compiling
-    cxx_build::bridge("src/lib.rs")
+    let mut build = cxx_build::bridge("src/lib.rs");
+    build
         .file("openbabel/src/base.cpp")
         .file("openbabel/src/atom.cpp")
         .file("openbabel/src/bond.cpp")
@@ -97,12 +105,16 @@ fn main() {
         .file("openbabel/src/fingerprints/finger3.cpp")
         .file("openbabel/src/fingerprints/fingerecfp.cpp")
         .file("openbabel/src/fingerprint.cpp")
+        .file("openbabel/src/query.cpp")
+        .file("openbabel/src/isomorphism.cpp")
         .file("openbabel/src/forcefields/forcefielduff.cpp")
         .file("openbabel/src/forcefields/forcefieldgaff.cpp")
         .file("openbabel/src/forcefields/forcefieldmmff94.cpp")
         .file("openbabel/src/forcefields/forcefieldghemical.cpp")
         .file("openbabel/src/forcefield.cpp")
         .file("openbabel/src/molchrg.cpp")
+        .file("openbabel/src/rotor.cpp")  // rotor searches, torsion rules from src/data/torlib.h
+        .file("openbabel/src/rotamer.cpp")
         // .file("openbabel/src/forcefields/forcefieldmm2.cpp")  // compilation error when added
         .file("openbabel/src/formats/smilesformat.cpp")
         .file("openbabel/src/formats/xyzformat.cpp")
@@ -116,6 +128,12 @@ fn main() {
         .file("openbabel/src/formats/siestaformat.cpp")
         .file("openbabel/src/formats/mdlformat.cpp")
         .file("openbabel/src/formats/pdbformat.cpp")
+        .file("openbabel/src/formats/mol2format.cpp")
+        .file("openbabel/src/formats/mmcifformat.cpp")
+        .file("openbabel/src/formats/cifformat.cpp")
+        .file("openbabel/src/formats/moldenformat.cpp")
+        .file("openbabel/src/formats/vaspformat.cpp")
+        .file("openbabel/src/formats/castepformat.cpp")
         .file("openbabel/src/alias.cpp")
         .file("openbabel/src/mcdlutil.cpp")
         .file("src/wrapper.cpp")
@@ -140,8 +158,60 @@ fn main() {
         .flag_if_supported("-Wno-misleading-indentation")
         .flag_if_supported("-Wno-parentheses")
         // .flag("-Wno-c++11-extensions")
-        .flag_if_supported("-Wno-unused-private-field")
-        .compile("openbabel");
+        .flag_if_supported("-Wno-unused-private-field");
+
+    // CML is read and written through libxml2
+    #[cfg(feature = "cml")]
+    {
+        build
+            .file("openbabel/src/formats/xml/xml.cpp")
+            .file("openbabel/src/formats/xml/xmlformat.cpp")
+            .file("openbabel/src/formats/xml/cmlformat.cpp")
+            .define("OPENBABEL_SYS_CML", None);
+        for path in pkg_config::probe_library("libxml-2.0").unwrap().include_paths {
+            build.include(path);
+        }
+    }
+
+    // OBConformerSearch scores conformers by RMSD through OBAlign, which needs Eigen3
+    #[cfg(feature = "conformersearch")]
+    {
+        build
+            .file("openbabel/src/conformersearch.cpp")
+            .file("openbabel/src/math/align.cpp")
+            .define("OPENBABEL_SYS_CONFORMERSEARCH", None);
+        for path in pkg_config::probe_library("eigen3").unwrap().include_paths {
+            build.include(path);
+        }
+    }
+
+    #[cfg(feature = "inchi")]
+    {
+        build
+            .file("openbabel/src/formats/inchiformat.cpp")
+            .file("openbabel/src/formats/getinchi.cpp");
+    }
+
+    build.compile("openbabel");
+
+    // the InChI library is C, compiled after openbabel so that it comes later on the link line
+    #[cfg(feature = "inchi")]
+    {
+        let mut inchi = cc::Build::new();
+        for entry in std::fs::read_dir("openbabel/src/formats/libinchi").unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().map_or(false, |ext| ext == "c") {
+                inchi.file(path);
+            }
+        }
+        inchi
+            .include("openbabel/include")
+            .include("openbabel/src/formats/libinchi")
+            .define("TARGET_API_LIB", None)
+            .define("COMPILE_ANSI_ONLY", None)
+            .warnings(false)
+            .compile("inchi");
+    }
 
         println!("cargo:rerun-if-changed=src/lib.rs");
         println!("cargo:rerun-if-changed=src/wrapper.h");
@@ -1,29 +1,109 @@
 //!  OpenBabel Rust Bindings
 //!
+//!  Data files
+//!  ----------
+//! OBDatafile_locate <-> OpenDatafile
+//!
+//!  OBMessageHandler
+//!  ----------------
+//! OBMessageHandler_get_messages <-> obErrorLog message list (level, method, text)
+//! OBMessageHandler_clear_log <-> OBMessageHandler::ClearLog
+//! OBMessageHandler_set_output_level <-> OBMessageHandler::SetOutputLevel, negative level silences output
+//! OBMessageHandler_set_max_log_entries <-> OBMessageHandler::SetMaxLogEntries
+//! OBMessageHandler_get_error_message_count <-> OBMessageHandler::GetErrorMessageCount
+//! OBMessageHandler_get_warning_message_count <-> OBMessageHandler::GetWarningMessageCount
+//!
 //!  OBConversion
 //!  ------------
 //! OBConversion_new <-> new OBConversion()
 //! OBConversion_set_in_format <-> OBConversion::SetInFormat
 //! OBConversion_set_out_format <-> OBConversion::SetOutFormat
 //! OBConversion_set_in_and_out_formats <-> OBConversion::SetInAndOutFormats
-//! OBConversion_read_string <-> OBConversion::ReadString
-//! OBConversion_write_string <-> OBConversion::WriteString
-//! OBConversion_read_file <-> OBConversion::ReadFile
-//! OBConversion_write_file <-> OBConversion::WriteFile
+//! OBConversion_read_string <-> OBConversion::ReadString + OBMol::DoTransformations
+//! OBConversion_write_string <-> OBMol::DoTransformations (on a copy) + OBConversion::WriteString
+//! OBConversion_read_file <-> OBConversion::ReadFile + OBMol::DoTransformations
+//! OBConversion_write_file <-> OBMol::DoTransformations (on a copy) + OBConversion::WriteFile
 //! OBConversion_get_supported_input_format <-> OBConversion::GetSupportedInputFormat
 //! OBConversion_get_supported_output_format <-> OBConversion::GetSupportedOutputFormat
+//! OBConversion_add_option <-> OBConversion::AddOption, option type 0 = INOPTIONS, 1 = OUTOPTIONS, 2 = GENOPTIONS, empty value for none
+//! OBConversion_remove_option <-> OBConversion::RemoveOption
+//! OBConversion_is_option <-> OBConversion::IsOption
 //!
 //! OBMol
 //! -----
 //! OBMol_new <-> new OBMol()
+//! OBMol_clone <-> new OBMol(const OBMol &)
 //! OBMol_num_atoms <-> OBMol::NumAtoms
 //! OBMol_num_bonds <-> OBMol::NumBonds
 //! OBMol_num_hvy_atoms <-> OBMol::NumHvyAtoms
 //! OBMol_get_mol_wt <-> OBMol::GetMolWt
+//! OBMol_get_coordinates <-> OBAtom::GetX/GetY/GetZ of all atoms, flattened
+//! OBMol_set_coordinates <-> OBAtom::SetVector of all atoms, flattened
+//! OBMol_get_cell_vectors <-> OBUnitCell::GetCellVectors, flattened, empty without a unit cell
+//! OBMol_get_dimension <-> OBMol::GetDimension
+//! OBMol_set_dimension <-> OBMol::SetDimension
+//! OBMol_center <-> OBMol::Center
+//! OBMol_translate <-> OBMol::Translate
+//! OBMol_rotate <-> OBMol::Rotate, row-major 3x3 matrix
+//! OBMol_num_conformers <-> OBMol::NumConformers
+//! OBMol_get_conformer <-> OBMol::GetConformer, flattened, empty if out of range
+//! OBMol_set_conformers <-> OBMol::SetConformers + OBMol::SetConformer(0), flattened conformers one after the other
+//! OBMol_set_conformer <-> OBMol::SetConformer, ignored if out of range
+//! OBMol_get_energies <-> OBMol::GetEnergies, one per conformer if set
+//! OBMol_set_energies <-> OBMol::SetEnergies
+//! OBMol_add_atom <-> OBMol::NewAtom + OBAtom::SetAtomicNum, returns the 1-based atom index
+//! OBMol_add_bond <-> OBMol::AddBond, fails if the atoms are identical or already bonded
+//! OBMol_delete_atom <-> OBMol::DeleteAtom
+//! OBMol_delete_bond <-> OBMol::DeleteBond
+//! (editing calls are wrapped in OBMol::BeginModify/EndModify, the conformers are carried over)
+//! OBMol_get_symmetry_classes <-> OBGraphSym::GetSymmetry, one class per atom
+//! OBMol_get_canonical_labels <-> CanonicalLabels on the OBGraphSym classes, one 1-based label per atom
+//! OBMol_is_perceived <-> OBMol::HasSSSRPerceived, HasAromaticPerceived, HasHybridizationPerceived and HasChiralityPerceived
+//! OBMol_perceive <-> OBMol::GetSSSR, OBAtom::GetHyb and OBStereoFacade, runs the lazy perceptions
+//! OBMol_has_substructure <-> CompileMoleculeQuery + OBIsomorphismMapper::MapFirst, atoms by element, aromaticity and ring membership
+//!
+//! OBAtom (addressed by the 1-based atom index in its OBMol)
+//! ------
+//! OBAtom_get_atomic_num <-> OBAtom::GetAtomicNum
+//! OBAtom_get_isotope <-> OBAtom::GetIsotope
+//! OBAtom_get_formal_charge <-> OBAtom::GetFormalCharge
+//! OBAtom_get_implicit_h_count <-> OBAtom::GetImplicitHCount
+//! OBAtom_get_hyb <-> OBAtom::GetHyb
+//! OBAtom_is_aromatic <-> OBAtom::IsAromatic
+//! OBAtom_get_vector <-> OBAtom::GetVector
+//! OBAtom_is_in_ring <-> OBAtom::IsInRing
+//! OBAtom_get_explicit_degree <-> OBAtom::GetExplicitDegree
+//! OBAtom_get_neighbors <-> FOR_NBORS_OF_ATOM, returns 1-based atom indices
+//! OBAtom_get_bonds <-> FOR_BONDS_OF_ATOM, returns 0-based bond indices
+//! OBAtom_set_formal_charge <-> OBAtom::SetFormalCharge
+//! OBAtom_set_isotope <-> OBAtom::SetIsotope
+//! OBAtom_set_implicit_h_count <-> OBAtom::SetImplicitHCount
+//!
+//! OBBond (addressed by the 0-based bond index in its OBMol)
+//! ------
+//! OBBond_get_bond_order <-> OBBond::GetBondOrder
+//! OBBond_get_begin_atom_idx <-> OBBond::GetBeginAtomIdx
+//! OBBond_get_end_atom_idx <-> OBBond::GetEndAtomIdx
+//! OBBond_is_aromatic <-> OBBond::IsAromatic
+//!
+//! OBStreamReader (OBConversion reading a RustReader through a std::streambuf)
+//! --------------
+//! OBStreamReader_new <-> OBConversion::SetInStream + OBConversion::SetInFormat
+//! OBStreamReader_read <-> OBConversion::Read + OBMol::DoTransformations, OBFormat::SkipObjects(0) after a failed record
+//! OBStreamReader_tell <-> std::istream::tellg
+//! OBStreamReader_add_option <-> OBConversion::AddOption
+//!
+//! OBStreamWriter (OBConversion writing to a RustWriter through a std::streambuf)
+//! --------------
+//! OBStreamWriter_new <-> OBConversion::SetOutStream + OBConversion::SetOutFormat
+//! OBStreamWriter_write <-> OBMol::DoTransformations (on a copy) + OBConversion::Write, with OBConversion::SetLast(false)
+//! OBStreamWriter_finish <-> OBConversion::SetLast(true) + the trailer of the format (END for PDB, end of the CML document), then flush
+//! OBStreamWriter_flush <-> std::ostream::flush
+//! OBStreamWriter_add_option <-> OBConversion::AddOption
 //!
 //! OBForceField
 //! ------------
-//! OBForceField_find_forcefield <-> OBForceField::FindForceField
+//! OBForceField_find_forcefield <-> OBForceField::FindForceField, null if the force field is unknown
 //! OBForceField_setup <-> OBForceField::Setup
 //! OBForceField_conjugate_gradients <-> OBForceField::ConjugateGradients
 //! OBForceField_conjugate_gradients_initialize <-> OBForceField::ConjugateGradientsInitialize
@@ -33,11 +113,68 @@
 //! OBForceField_steepest_descent_take_n_steps <-> OBForceField::SteepestDescentTakeNSteps
 //! OBForceField_energy <-> OBForceField::Energy
 //! OBForceField_is_setup_needed <-> OBForceField::IsSetupNeeded
+//! OBForceField_get_coordinates <-> OBForceField::GetCoordinates
+//! OBForceField_energy_terms <-> OBForceField::E_Bond, E_Angle, E_StrBnd, E_Torsion, E_OOP, E_VDW, E_Electrostatic
+//! OBForceField_gradients <-> OBForceField::GetGradient of all atoms after Energy(true), flattened; OpenBabel stores -dE/dx
+//! OBForceField_validate_gradients <-> OBForceField::ValidateGradients
+//! OBForceField_get_unit <-> OBForceField::GetUnit
+//! OBForceField_setup_with_constraints <-> OBForceField::Setup(mol, constraints)
+//! OBForceField_is_valid_setup <-> OBForceField::_validSetup, whether the last OBForceField::Setup succeeded
+//! OBForceField_atom_types <-> OBForceField::GetAtomTypes on a copy of the molecule, types of the last OBForceField::Setup, also if it failed; empty for another molecule
+//! OBForceField_set_coordinates <-> OBForceField::SetCoordinates
+//! OBForceField_get_conformers <-> OBForceField::GetConformers
+//! OBForceField_systematic_rotor_search_initialize <-> OBForceField::SystematicRotorSearchInitialize, false without rotors
+//! OBForceField_systematic_rotor_search_next_conformer <-> OBForceField::SystematicRotorSearchNextConformer
+//! OBForceField_random_rotor_search_initialize <-> OBForceField::RandomRotorSearchInitialize
+//! OBForceField_random_rotor_search_next_conformer <-> OBForceField::RandomRotorSearchNextConformer
+//! (the rotor searches leave the conformers in the force field, see OBForceField_get_conformers;
+//! the rotor list is built by the initialize calls, the next_conformer calls only turn and optimize)
+//! 
+//! 
+//! 
+//! OBFFConstraints
+//! ---------------
+//! 
+//! Atom indices are 1-based as in OpenBabel
+//! 
+//! OBFFConstraints_new <-> OBFFConstraints::OBFFConstraints
+//! OBFFConstraints_set_factor <-> OBFFConstraints::SetFactor, the force constant of all restraints
+//! OBFFConstraints_add_ignore <-> OBFFConstraints::AddIgnore
+//! OBFFConstraints_add_atom_constraint <-> OBFFConstraints::AddAtomConstraint
+//! OBFFConstraints_add_atom_x_constraint <-> OBFFConstraints::AddAtomXConstraint
+//! OBFFConstraints_add_atom_y_constraint <-> OBFFConstraints::AddAtomYConstraint
+//! OBFFConstraints_add_atom_z_constraint <-> OBFFConstraints::AddAtomZConstraint
+//! OBFFConstraints_add_distance_constraint <-> OBFFConstraints::AddDistanceConstraint
+//! OBFFConstraints_add_angle_constraint <-> OBFFConstraints::AddAngleConstraint
+//! OBFFConstraints_add_torsion_constraint <-> OBFFConstraints::AddTorsionConstraint
+//! OBFFConstraints_size <-> OBFFConstraints::Size
+//!
+//! OBConformerSearch
+//! -----------------
+//! OBConformerSearch_setup <-> OBConformerSearch::Setup, null with an error logged if the setup fails or
+//! without the `conformersearch` feature
+//! OBConformerSearch_search <-> OBConformerSearch::Search + GetConformers, the conformers replace those of the molecule
 //!
 //!  
 //! OBFingerprint
 //! -------------
-//! OBFingerprint_get_fingerprint <-> OBFingerprint::GetFingerprint
+//! OBFingerprint_find_fingerprint <-> OBFingerprint::FindFingerprint
+//! OBFingerprint_get_fingerprint <-> OBFingerprint::GetFingerprint, all bits clear on failure
+//! OBFingerprint_tanimoto <-> OBFingerprint::Tanimoto
+//!
+//! FastSearch (.fs index files, as written by obabel -ofs)
+//! ----------
+//! OBFastSearchWriter_new <-> FastSearchIndexer::FastSearchIndexer on a std::ofstream
+//! OBFastSearchWriter_add <-> FastSearchIndexer::Add
+//! OBFastSearchWriter_finish <-> FastSearchIndexer::~FastSearchIndexer, writes the index
+//! OBFastSearchIndex_open <-> FastSearch::ReadIndexFile
+//! OBFastSearchIndex_datafile_name <-> FastSearch::ReadIndexFile, the data file named in the index
+//! OBFastSearchIndex_fp_name <-> FastSearch::GetIndexHeader, fpid
+//! OBFastSearchIndex_nbits <-> FastSearch::GetIndexHeader, words * 32
+//! OBFastSearchIndex_seek_positions <-> FastSearch::Find for the empty molecule, one per indexed record
+//! OBFastSearchIndex_find <-> FastSearch::Find
+//! OBFastSearchIndex_find_similar <-> FastSearch::FindSimilar(MinTani), best first
+//! OBFastSearchIndex_find_most_similar <-> FastSearch::FindSimilar(nCandidates), best first
 //! 
 //! 
 //! 
@@ -45,23 +182,72 @@
 //! ---------------
 //! 
 //! OBSmartsPattern_from_smarts <-> OBSmartsPattern::Init
+//! OBSmartsPattern_is_valid <-> OBSmartsPattern::IsValid
 //! OBSmartsPattern_num_atoms <-> OBSmartsPattern::NumAtoms
 //! OBSmartsPattern_num_bonds <-> OBSmartsPattern::NumBonds
-//! OBSmartsPattern_num_matches <-> OBSmartsPattern::NumMatches
-//! OBSmartsPattern_match <-> OBSmartsPattern::Match
+//! OBSmartsPattern_has_match <-> OBSmartsPattern::HasMatch
+//! OBSmartsPattern_match <-> OBSmartsPattern::Match(mol, mlist, mtype), const: the pattern keeps no match list;
+//!                          the mappings come back flat in OBSmartsMatches, with 0-based atom indices
 
 #[cxx::bridge(namespace = "OpenBabel")]
 pub mod ob {
+    /// One entry of obErrorLog, level follows obMessageLevel (0 = obError ... 4 = obDebug)
+    #[derive(Debug, Clone)]
+    struct OBLogMessage {
+        level: u32,
+        method: String,
+        text: String,
+    }
+
+    /// Record of a FastSearch index, `position` is its seek position in the data file
+    #[derive(Debug, Clone)]
+    struct OBFastSearchHit {
+        position: u64,
+        tanimoto: f64,
+    }
+
+    /// Energy terms of a force field, terms it does not have are 0
+    #[derive(Debug, Clone)]
+    struct OBFFEnergyTerms {
+        bond: f64,
+        angle: f64,
+        str_bnd: f64,
+        torsion: f64,
+        oop: f64,
+        vdw: f64,
+        electrostatic: f64,
+    }
+
+    /// Mappings of a SMARTS pattern, flattened: mapping `i` is `atoms[offsets[i]..offsets[i + 1]]`,
+    /// atom indices are 0-based (OBAtom::GetIdx() - 1)
+    #[derive(Debug, Clone)]
+    struct OBSmartsMatches {
+        atoms: Vec<u32>,
+        offsets: Vec<u32>,
+    }
+
     unsafe extern "C++" {
         include!("openbabel-sys/src/wrapper.h");
         type OBMol;
         type OBSmartsPattern;
         type OBConversion;
         type OBForceField;
+        type OBFFConstraints;
 
         // Debug
         fn print_global_instances();
 
+        // Data files
+        fn OBDatafile_locate(file_name: &CxxString) -> String;
+
+        // OBMessageHandler
+        fn OBMessageHandler_get_messages() -> Vec<OBLogMessage>;
+        fn OBMessageHandler_clear_log();
+        fn OBMessageHandler_set_output_level(level: i32);
+        fn OBMessageHandler_set_max_log_entries(max: u32);
+        fn OBMessageHandler_get_error_message_count() -> u32;
+        fn OBMessageHandler_get_warning_message_count() -> u32;
+
         // OBConversion
         // fn OBConversion_smi_to_mol(smiles: &CxxString) -> UniquePtr<OBMol>;
         fn OBConversion_new() -> UniquePtr<OBConversion>;
@@ -99,6 +285,37 @@ pub mod ob {
         ) -> bool;
         fn OBConversion_get_supported_input_format() -> Vec<String>;
         fn OBConversion_get_supported_output_format() -> Vec<String>;
+        fn OBConversion_add_option(
+            conv: &UniquePtr<OBConversion>,
+            name: &CxxString,
+            opt_type: u32,
+            value: &CxxString,
+        );
+        fn OBConversion_remove_option(
+            conv: &UniquePtr<OBConversion>,
+            name: &CxxString,
+            opt_type: u32,
+        ) -> bool;
+        fn OBConversion_is_option(
+            conv: &UniquePtr<OBConversion>,
+            name: &CxxString,
+            opt_type: u32,
+        ) -> bool;
+
+        // OBStreamReader
+        type OBStreamReader;
+        fn OBStreamReader_new(source: Box<RustReader>, input_format: &CxxString) -> UniquePtr<OBStreamReader>;
+        fn OBStreamReader_read(reader: &UniquePtr<OBStreamReader>, mol: &UniquePtr<OBMol>) -> i32;
+        fn OBStreamReader_tell(reader: &UniquePtr<OBStreamReader>) -> u64;
+        fn OBStreamReader_add_option(reader: &UniquePtr<OBStreamReader>, name: &CxxString, opt_type: u32, value: &CxxString);
+
+        // OBStreamWriter
+        type OBStreamWriter;
+        fn OBStreamWriter_new(sink: Box<RustWriter>, output_format: &CxxString) -> UniquePtr<OBStreamWriter>;
+        fn OBStreamWriter_write(writer: &UniquePtr<OBStreamWriter>, mol: &UniquePtr<OBMol>) -> i32;
+        fn OBStreamWriter_finish(writer: &UniquePtr<OBStreamWriter>) -> bool;
+        fn OBStreamWriter_flush(writer: &UniquePtr<OBStreamWriter>) -> bool;
+        fn OBStreamWriter_add_option(writer: &UniquePtr<OBStreamWriter>, name: &CxxString, opt_type: u32, value: &CxxString);
 
         // OBForceField
         fn OBForceField_find_forcefield(ff_name: &CxxString) -> UniquePtr<OBForceField>;
@@ -128,26 +345,213 @@ pub mod ob {
             pFF: &UniquePtr<OBForceField>,
             mol: &UniquePtr<OBMol>,
         ) -> bool;
+        fn OBForceField_energy_terms(pFF: &UniquePtr<OBForceField>) -> OBFFEnergyTerms;
+        fn OBForceField_gradients(pFF: &UniquePtr<OBForceField>) -> Vec<f64>;
+        fn OBForceField_validate_gradients(pFF: &UniquePtr<OBForceField>) -> bool;
+        fn OBForceField_get_unit(pFF: &UniquePtr<OBForceField>) -> String;
+        fn OBForceField_is_valid_setup(pFF: &UniquePtr<OBForceField>) -> bool;
+        fn OBForceField_atom_types(pFF: &UniquePtr<OBForceField>, mol: &UniquePtr<OBMol>) -> Vec<String>;
+        fn OBForceField_get_coordinates(
+            pFF: &UniquePtr<OBForceField>,
+            mol: &UniquePtr<OBMol>,
+        ) -> bool;
+        fn OBForceField_setup_with_constraints(
+            mol: &UniquePtr<OBMol>,
+            pFF: &UniquePtr<OBForceField>,
+            constraints: &UniquePtr<OBFFConstraints>,
+        ) -> u32;
+        fn OBForceField_set_coordinates(pFF: &UniquePtr<OBForceField>, mol: &UniquePtr<OBMol>) -> bool;
+        fn OBForceField_get_conformers(pFF: &UniquePtr<OBForceField>, mol: &UniquePtr<OBMol>) -> bool;
+        fn OBForceField_systematic_rotor_search_initialize(pFF: &UniquePtr<OBForceField>, geom_steps: u32) -> bool;
+        fn OBForceField_systematic_rotor_search_next_conformer(pFF: &UniquePtr<OBForceField>, geom_steps: u32) -> bool;
+        fn OBForceField_random_rotor_search_initialize(pFF: &UniquePtr<OBForceField>, conformers: u32, geom_steps: u32);
+        fn OBForceField_random_rotor_search_next_conformer(pFF: &UniquePtr<OBForceField>, geom_steps: u32) -> bool;
+
+        // OBFFConstraints
+        fn OBFFConstraints_new() -> UniquePtr<OBFFConstraints>;
+        fn OBFFConstraints_set_factor(constraints: &UniquePtr<OBFFConstraints>, factor: f64);
+        fn OBFFConstraints_add_ignore(constraints: &UniquePtr<OBFFConstraints>, a: u32);
+        fn OBFFConstraints_add_atom_constraint(constraints: &UniquePtr<OBFFConstraints>, a: u32);
+        fn OBFFConstraints_add_atom_x_constraint(constraints: &UniquePtr<OBFFConstraints>, a: u32);
+        fn OBFFConstraints_add_atom_y_constraint(constraints: &UniquePtr<OBFFConstraints>, a: u32);
+        fn OBFFConstraints_add_atom_z_constraint(constraints: &UniquePtr<OBFFConstraints>, a: u32);
+        fn OBFFConstraints_add_distance_constraint(constraints: &UniquePtr<OBFFConstraints>, a: u32, b: u32, length: f64);
+        fn OBFFConstraints_add_angle_constraint(constraints: &UniquePtr<OBFFConstraints>, a: u32, b: u32, c: u32, angle: f64);
+        fn OBFFConstraints_add_torsion_constraint(constraints: &UniquePtr<OBFFConstraints>, a: u32, b: u32, c: u32, d: u32, torsion: f64);
+        fn OBFFConstraints_size(constraints: &UniquePtr<OBFFConstraints>) -> u32;
+
+        // OBConformerSearch
+        type OBConformerSearchRun;
+        fn OBConformerSearch_setup(
+            mol: &UniquePtr<OBMol>,
+            num_conformers: u32,
+            num_children: u32,
+            mutability: u32,
+            convergence: u32,
+        ) -> UniquePtr<OBConformerSearchRun>;
+        fn OBConformerSearch_search(search: &UniquePtr<OBConformerSearchRun>, mol: &UniquePtr<OBMol>);
 
         // OBMol
         fn OBMol_new() -> UniquePtr<OBMol>;
         fn OBMol_from_smiles(smiles: &CxxString) -> UniquePtr<OBMol>;
+        fn OBMol_clone(mol: &UniquePtr<OBMol>) -> UniquePtr<OBMol>;
         fn OBMol_num_atoms(mol: &UniquePtr<OBMol>) -> u32;
         fn OBMol_num_bonds(mol: &UniquePtr<OBMol>) -> u32;
         fn OBMol_num_hvy_atoms(mol: &UniquePtr<OBMol>) -> u32;
         fn OBMol_get_mol_wt(mol: &UniquePtr<OBMol>) -> f64;
+        fn OBMol_get_coordinates(mol: &UniquePtr<OBMol>) -> Vec<f64>;
+        fn OBMol_set_coordinates(mol: &UniquePtr<OBMol>, coords: &[f64]);
+        fn OBMol_get_cell_vectors(mol: &UniquePtr<OBMol>) -> Vec<f64>;
+        fn OBMol_get_dimension(mol: &UniquePtr<OBMol>) -> u32;
+        fn OBMol_set_dimension(mol: &UniquePtr<OBMol>, dim: u32);
+        fn OBMol_center(mol: &UniquePtr<OBMol>);
+        fn OBMol_translate(mol: &UniquePtr<OBMol>, x: f64, y: f64, z: f64);
+        fn OBMol_rotate(mol: &UniquePtr<OBMol>, matrix: &[f64]);
+        fn OBMol_num_conformers(mol: &UniquePtr<OBMol>) -> u32;
+        fn OBMol_get_conformer(mol: &UniquePtr<OBMol>, idx: u32) -> Vec<f64>;
+        fn OBMol_set_conformers(mol: &UniquePtr<OBMol>, coords: &[f64]);
+        fn OBMol_set_conformer(mol: &UniquePtr<OBMol>, idx: u32);
+        fn OBMol_get_energies(mol: &UniquePtr<OBMol>) -> Vec<f64>;
+        fn OBMol_set_energies(mol: &UniquePtr<OBMol>, energies: &[f64]);
+        fn OBMol_add_atom(mol: &UniquePtr<OBMol>, atomic_num: u32) -> u32;
+        fn OBMol_add_bond(mol: &UniquePtr<OBMol>, begin_idx: u32, end_idx: u32, order: u32) -> bool;
+        fn OBMol_delete_atom(mol: &UniquePtr<OBMol>, idx: u32) -> bool;
+        fn OBMol_delete_bond(mol: &UniquePtr<OBMol>, idx: u32) -> bool;
+        fn OBMol_get_symmetry_classes(mol: &UniquePtr<OBMol>) -> Vec<u32>;
+        fn OBMol_get_canonical_labels(mol: &UniquePtr<OBMol>) -> Vec<u32>;
+        fn OBMol_is_perceived(mol: &UniquePtr<OBMol>) -> bool;
+        fn OBMol_perceive(mol: &UniquePtr<OBMol>);
+        fn OBMol_has_substructure(mol: &UniquePtr<OBMol>, query: &UniquePtr<OBMol>) -> bool;
+
+        // OBAtom
+        fn OBAtom_get_atomic_num(mol: &UniquePtr<OBMol>, idx: u32) -> u32;
+        fn OBAtom_get_isotope(mol: &UniquePtr<OBMol>, idx: u32) -> u32;
+        fn OBAtom_get_formal_charge(mol: &UniquePtr<OBMol>, idx: u32) -> i32;
+        fn OBAtom_get_implicit_h_count(mol: &UniquePtr<OBMol>, idx: u32) -> u32;
+        fn OBAtom_get_hyb(mol: &UniquePtr<OBMol>, idx: u32) -> u32;
+        fn OBAtom_is_aromatic(mol: &UniquePtr<OBMol>, idx: u32) -> bool;
+        fn OBAtom_get_vector(mol: &UniquePtr<OBMol>, idx: u32) -> Vec<f64>;
+        fn OBAtom_is_in_ring(mol: &UniquePtr<OBMol>, idx: u32) -> bool;
+        fn OBAtom_get_explicit_degree(mol: &UniquePtr<OBMol>, idx: u32) -> u32;
+        fn OBAtom_get_neighbors(mol: &UniquePtr<OBMol>, idx: u32) -> Vec<u32>;
+        fn OBAtom_get_bonds(mol: &UniquePtr<OBMol>, idx: u32) -> Vec<u32>;
+        fn OBAtom_set_formal_charge(mol: &UniquePtr<OBMol>, idx: u32, charge: i32);
+        fn OBAtom_set_isotope(mol: &UniquePtr<OBMol>, idx: u32, isotope: u32);
+        fn OBAtom_set_implicit_h_count(mol: &UniquePtr<OBMol>, idx: u32, count: u32);
+
+        // OBBond
+        fn OBBond_get_bond_order(mol: &UniquePtr<OBMol>, idx: u32) -> u32;
+        fn OBBond_get_begin_atom_idx(mol: &UniquePtr<OBMol>, idx: u32) -> u32;
+        fn OBBond_get_end_atom_idx(mol: &UniquePtr<OBMol>, idx: u32) -> u32;
+        fn OBBond_is_aromatic(mol: &UniquePtr<OBMol>, idx: u32) -> bool;
 
         // OBFingerprint
+        fn OBFingerprint_find_fingerprint(fp_name: &CxxString) -> bool;
         fn OBFingerprint_get_fingerprint(fp_name: &CxxString, mol: &UniquePtr<OBMol>, nbits: u32) -> UniquePtr<CxxVector<u32>>;
+        fn OBFingerprint_tanimoto(fp1: &Vec<u32>, fp2: &Vec<u32>) -> f64;
+
+        // FastSearch
+        type OBFastSearchWriter;
+        type OBFastSearchIndex;
+        fn OBFastSearchWriter_new(index_path: &CxxString, datafile_name: &CxxString, fp_name: &CxxString, nbits: u32) -> UniquePtr<OBFastSearchWriter>;
+        fn OBFastSearchWriter_add(writer: &UniquePtr<OBFastSearchWriter>, mol: &UniquePtr<OBMol>, seekpos: u64) -> bool;
+        fn OBFastSearchWriter_finish(writer: &UniquePtr<OBFastSearchWriter>) -> bool;
+        fn OBFastSearchIndex_open(index_path: &CxxString) -> UniquePtr<OBFastSearchIndex>;
+        fn OBFastSearchIndex_datafile_name(index: &UniquePtr<OBFastSearchIndex>) -> String;
+        fn OBFastSearchIndex_fp_name(index: &UniquePtr<OBFastSearchIndex>) -> String;
+        fn OBFastSearchIndex_nbits(index: &UniquePtr<OBFastSearchIndex>) -> u32;
+        fn OBFastSearchIndex_seek_positions(index: &UniquePtr<OBFastSearchIndex>) -> Vec<u64>;
+        fn OBFastSearchIndex_find(index: &UniquePtr<OBFastSearchIndex>, mol: &UniquePtr<OBMol>, max_candidates: u32) -> Vec<u64>;
+        fn OBFastSearchIndex_find_similar(index: &UniquePtr<OBFastSearchIndex>, mol: &UniquePtr<OBMol>, min_tanimoto: f64) -> Vec<OBFastSearchHit>;
+        fn OBFastSearchIndex_find_most_similar(index: &UniquePtr<OBFastSearchIndex>, mol: &UniquePtr<OBMol>, count: u32) -> Vec<OBFastSearchHit>;
 
         // OBSmartsPattern
         fn OBSmartsPattern_from_smarts(smarts: &CxxString) -> UniquePtr<OBSmartsPattern>;
+        fn OBSmartsPattern_is_valid(pattern: &UniquePtr<OBSmartsPattern>) -> bool;
         fn OBSmartsPattern_num_atoms(pattern: &UniquePtr<OBSmartsPattern>) -> u32;
         fn OBSmartsPattern_num_bonds(pattern: &UniquePtr<OBSmartsPattern>) -> u32;
-        fn OBSmartsPattern_num_matches(pattern: &UniquePtr<OBSmartsPattern>) -> u32;
-        fn OBSmartsPattern_match(pattern: &UniquePtr<OBSmartsPattern>, mol: &UniquePtr<OBMol>) -> UniquePtr<CxxVector<i32>>;
+        fn OBSmartsPattern_has_match(pattern: &UniquePtr<OBSmartsPattern>, mol: &UniquePtr<OBMol>) -> bool;
+        fn OBSmartsPattern_match(pattern: &UniquePtr<OBSmartsPattern>, mol: &UniquePtr<OBMol>, match_type: u32, max_matches: u32) -> OBSmartsMatches;
+    }
+
+    extern "Rust" {
+        type RustReader;
+        fn read_chunk(self: &mut RustReader, buf: &mut [u8]) -> usize;
+
+        type RustWriter;
+        fn write_chunk(self: &mut RustWriter, buf: &[u8]) -> bool;
+        fn flush(self: &mut RustWriter) -> bool;
+    }
+}
+
+/// Source of an OBStreamReader, the C++ stream pulls its bytes from `inner`.
+///
+/// Read errors end the stream; the error is kept in [error_handle](RustReader::error_handle).
+pub struct RustReader {
+    inner: Box<dyn std::io::Read>,
+    error: std::sync::Arc<std::sync::Mutex<Option<std::io::Error>>>,
+}
+
+impl RustReader {
+    pub fn new(inner: Box<dyn std::io::Read>) -> Self {
+        Self { inner, error: Default::default() }
+    }
+
+    pub fn error_handle(&self) -> std::sync::Arc<std::sync::Mutex<Option<std::io::Error>>> {
+        self.error.clone()
+    }
+
+    fn read_chunk(&mut self, buf: &mut [u8]) -> usize {
+        loop {
+            match self.inner.read(buf) {
+                Ok(n) => return n,
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(err) => {
+                    *self.error.lock().unwrap() = Some(err);
+                    return 0;
+                }
+            }
+        }
     }
 }
 
 
+/// Sink of an OBStreamWriter, the C++ stream pushes its bytes into `inner`.
+///
+/// Write errors are kept in [error_handle](RustWriter::error_handle).
+pub struct RustWriter {
+    inner: Box<dyn std::io::Write>,
+    error: std::sync::Arc<std::sync::Mutex<Option<std::io::Error>>>,
+}
+
+impl RustWriter {
+    pub fn new(inner: Box<dyn std::io::Write>) -> Self {
+        Self { inner, error: Default::default() }
+    }
+
+    pub fn error_handle(&self) -> std::sync::Arc<std::sync::Mutex<Option<std::io::Error>>> {
+        self.error.clone()
+    }
+
+    fn keep_error(&self, result: std::io::Result<()>) -> bool {
+        match result {
+            Ok(()) => true,
+            Err(err) => {
+                *self.error.lock().unwrap() = Some(err);
+                false
+            }
+        }
+    }
+
+    fn write_chunk(&mut self, buf: &[u8]) -> bool {
+        let result = self.inner.write_all(buf);
+        self.keep_error(result)
+    }
+
+    fn flush(&mut self) -> bool {
+        let result = self.inner.flush();
+        self.keep_error(result)
+    }
+}
+
 mod tests;
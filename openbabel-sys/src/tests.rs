@@ -1,8 +1,9 @@
 //!
 //! Test cases for OpenBabel Wrapper
 //!
-//! Only support single-thread
-//! cargo test -- --test-threads=1
+//! The raw bindings do not guard the global state of OpenBabel (the openbabel crate does),
+//! so these tests run on a single thread:
+//! cargo test -p openbabel-sys -- --test-threads=1
 
 #[cfg(test)]
 mod test {
@@ -37,6 +38,70 @@ Au      1.442498    2.498480    0.000000";
         }
     }
 
+    #[test]
+    fn test_atoms_and_bonds() {
+        cxx::let_cxx_string!(smiles = "c1ccccc1[15NH3+]");
+        let mol = ob::OBMol_from_smiles(&smiles);
+        assert_eq!(ob::OBAtom_get_atomic_num(&mol, 1), 6);
+        assert!(ob::OBAtom_is_aromatic(&mol, 1));
+        assert!(ob::OBAtom_is_in_ring(&mol, 1));
+        assert_eq!(ob::OBAtom_get_atomic_num(&mol, 7), 7);
+        assert_eq!(ob::OBAtom_get_isotope(&mol, 7), 15);
+        assert_eq!(ob::OBAtom_get_formal_charge(&mol, 7), 1);
+        assert_eq!(ob::OBAtom_get_implicit_h_count(&mol, 7), 3);
+        assert_eq!(ob::OBAtom_get_explicit_degree(&mol, 6), 3);
+        assert_eq!(ob::OBAtom_get_neighbors(&mol, 7).as_slice(), &[6]);
+        let bonds = ob::OBAtom_get_bonds(&mol, 7);
+        assert_eq!(bonds.len(), 1);
+        assert_eq!(ob::OBBond_get_begin_atom_idx(&mol, bonds[0]), 6);
+        assert_eq!(ob::OBBond_get_end_atom_idx(&mol, bonds[0]), 7);
+        assert_eq!(ob::OBBond_get_bond_order(&mol, bonds[0]), 1);
+        assert!(!ob::OBBond_is_aromatic(&mol, bonds[0]));
+        assert!(ob::OBBond_is_aromatic(&mol, 0));
+    }
+
+    #[test]
+    fn test_edit_mol() {
+        let mol = ob::OBMol_new();
+        assert_eq!(ob::OBMol_add_atom(&mol, 6), 1);
+        assert_eq!(ob::OBMol_add_atom(&mol, 8), 2);
+        assert!(ob::OBMol_add_bond(&mol, 1, 2, 2));
+        assert!(!ob::OBMol_add_bond(&mol, 1, 2, 1));
+        ob::OBAtom_set_implicit_h_count(&mol, 1, 2);
+        assert_eq!(ob::OBMol_num_atoms(&mol), 2);
+        assert_eq!(ob::OBMol_num_bonds(&mol), 1);
+        assert_delta!(ob::OBMol_get_mol_wt(&mol), 30.026, 1e-3);
+        assert!(ob::OBMol_delete_bond(&mol, 0));
+        assert!(ob::OBMol_delete_atom(&mol, 1));
+        assert_eq!(ob::OBMol_num_atoms(&mol), 1);
+        assert_eq!(ob::OBAtom_get_atomic_num(&mol, 1), 8);
+    }
+
+    #[test]
+    fn test_canonical_labels() {
+        cxx::let_cxx_string!(smiles = "OCC");
+        let mol = ob::OBMol_from_smiles(&smiles);
+        let classes = ob::OBMol_get_symmetry_classes(&mol);
+        assert_eq!(classes.len(), 3);
+        assert!(classes.iter().all(|c| *c > 0));
+        let mut labels = ob::OBMol_get_canonical_labels(&mol).to_vec();
+        labels.sort();
+        assert_eq!(labels, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_perceive() {
+        cxx::let_cxx_string!(smiles = "c1ccccc1C");
+        let mol = ob::OBMol_from_smiles(&smiles);
+        assert!(!ob::OBMol_is_perceived(&mol));
+        ob::OBMol_perceive(&mol);
+        assert!(ob::OBMol_is_perceived(&mol));
+        assert!(ob::OBAtom_is_aromatic(&mol, 1));
+        assert!(!ob::OBAtom_is_in_ring(&mol, 7));
+        ob::OBMol_perceive(&ob::OBMol_new());  // no atoms
+        assert!(ob::OBMol_is_perceived(&ob::OBMol_new()));
+    }
+
     #[test]
     fn test_fingerprint() {
         cxx::let_cxx_string!(smiles = "c1ccccc1");
@@ -51,24 +116,118 @@ Au      1.442498    2.498480    0.000000";
         }
     }
 
+    #[test]
+    fn test_fast_search() {
+        let index_path = std::env::temp_dir().join("openbabel_sys_test_fast_search.fs");
+        cxx::let_cxx_string!(index_path_cxx = index_path.to_str().unwrap());
+        cxx::let_cxx_string!(datafile_name = "mols.smi");
+        cxx::let_cxx_string!(fp_name = "FP2");
+        let writer = ob::OBFastSearchWriter_new(&index_path_cxx, &datafile_name, &fp_name, 1024);
+        assert!(!writer.is_null());
+        let mut seekpos = 0;
+        for smiles_str in vec!["c1ccccc1O", "CCCC", "c1ccccc1N"] {
+            cxx::let_cxx_string!(smiles = smiles_str);
+            let mol = ob::OBMol_from_smiles(&smiles);
+            assert!(ob::OBFastSearchWriter_add(&writer, &mol, seekpos));
+            seekpos += smiles_str.len() as u64 + 1;
+        }
+        assert!(ob::OBFastSearchWriter_finish(&writer));
+
+        let index = ob::OBFastSearchIndex_open(&index_path_cxx);
+        assert!(!index.is_null());
+        assert_eq!(ob::OBFastSearchIndex_datafile_name(&index), "mols.smi");
+        assert_eq!(ob::OBFastSearchIndex_fp_name(&index), "FP2");
+        assert_eq!(ob::OBFastSearchIndex_nbits(&index), 1024);
+        assert_eq!(ob::OBFastSearchIndex_seek_positions(&index), vec![0, 10, 15]);
+        cxx::let_cxx_string!(query_smiles = "c1ccccc1");
+        let query = ob::OBMol_from_smiles(&query_smiles);
+        let mut candidates = ob::OBFastSearchIndex_find(&index, &query, 100);
+        candidates.sort();
+        assert_eq!(candidates, vec![0, 15]);
+        let hits = ob::OBFastSearchIndex_find_most_similar(&index, &query, 1);
+        assert_eq!(hits.len(), 1);
+        assert!(hits[0].position == 0 || hits[0].position == 15);
+        std::fs::remove_file(&index_path).unwrap();
+    }
+
+    #[test]
+    fn test_tanimoto() {
+        assert_eq!(ob::OBFingerprint_tanimoto(&vec![0b0111, 0], &vec![0b0110, 1]), 0.5);
+        assert_eq!(ob::OBFingerprint_tanimoto(&vec![1], &vec![1, 0]), -1.0);
+    }
+
+    #[test]
+    fn test_message_handler() {
+        ob::OBMessageHandler_set_output_level(-1);
+        ob::OBMessageHandler_clear_log();
+        let error_count = ob::OBMessageHandler_get_error_message_count();
+        cxx::let_cxx_string!(smiles = "C1CC(");
+        assert!(ob::OBMol_from_smiles(&smiles).is_null());
+        assert!(ob::OBMessageHandler_get_error_message_count() > error_count);
+        let messages = ob::OBMessageHandler_get_messages();
+        assert!(messages.iter().any(|m| m.level == 0 && m.method == "OBMol_from_smiles"));
+        ob::OBMessageHandler_clear_log();
+        assert!(ob::OBMessageHandler_get_messages().is_empty());
+        ob::OBMessageHandler_set_output_level(1);
+    }
+
+    #[test]
+    fn test_find_fingerprint() {
+        cxx::let_cxx_string!(name = "ECFP4");
+        assert!(ob::OBFingerprint_find_fingerprint(&name));
+        cxx::let_cxx_string!(name = "FP9");
+        assert!(!ob::OBFingerprint_find_fingerprint(&name));
+    }
+
+    #[test]
+    fn test_locate_datafile() {
+        cxx::let_cxx_string!(file_name = "patterns.txt");
+        assert!(ob::OBDatafile_locate(&file_name).ends_with("patterns.txt"));
+        cxx::let_cxx_string!(file_name = "no_such_file.txt");
+        assert!(ob::OBDatafile_locate(&file_name).is_empty());
+    }
+
+    #[test]
+    fn test_smarts_pattern_is_valid() {
+        cxx::let_cxx_string!(smarts = "O=CN*");
+        assert!(ob::OBSmartsPattern_is_valid(&ob::OBSmartsPattern_from_smarts(&smarts)));
+        cxx::let_cxx_string!(smarts = "O=C(N");
+        assert!(!ob::OBSmartsPattern_is_valid(&ob::OBSmartsPattern_from_smarts(&smarts)));
+    }
+
     #[test]
     fn test_smarts_pattern() {
         cxx::let_cxx_string!(smiles = "NCC(=O)NCC");
         let mol = ob::OBMol_from_smiles(&smiles);
-        let test_data: Vec<(String, (u32, u32, u32, Vec<i32>))> = vec![
-            (String::from("O=CN"), (3, 2, 1, vec![4, 3, 5, -1])),
-            (String::from("CN"), (2, 1, 3, vec![2, 1, -1, 3, 5, -1, 6, 5, -1])),
+        let test_data: Vec<(String, (u32, u32, Vec<u32>, Vec<u32>))> = vec![
+            (String::from("O=CN"), (3, 2, vec![3, 2, 4], vec![0, 3])),
+            (String::from("CN"), (2, 1, vec![1, 0, 2, 4, 5, 4], vec![0, 2, 4, 6])),
         ];
 
-        for (s, (num_atoms, num_bonds, num_match, match_indexes)) in test_data.iter() {
+        for (s, (num_atoms, num_bonds, match_atoms, match_offsets)) in test_data.iter() {
             cxx::let_cxx_string!(smarts = s);
             let sp = ob::OBSmartsPattern_from_smarts(&smarts);
             assert_eq!(ob::OBSmartsPattern_num_atoms(&sp), *num_atoms);
             assert_eq!(ob::OBSmartsPattern_num_bonds(&sp), *num_bonds);
-            let match_cxx_vec = ob::OBSmartsPattern_match(&sp, &mol);
-            assert_eq!(ob::OBSmartsPattern_num_matches(&sp), *num_match);
-            assert_eq!(match_cxx_vec.as_slice(), match_indexes);
+            assert!(ob::OBSmartsPattern_has_match(&sp, &mol));
+            let matches = ob::OBSmartsPattern_match(&sp, &mol, 0, 0);
+            assert_eq!(&matches.atoms, match_atoms);
+            assert_eq!(&matches.offsets, match_offsets);
+            let first = ob::OBSmartsPattern_match(&sp, &mol, 1, 0);
+            assert_eq!(first.atoms.len(), *num_atoms as usize);
+            assert_eq!(first.offsets, vec![0, *num_atoms]);
+            let limited = ob::OBSmartsPattern_match(&sp, &mol, 0, 1);
+            assert_eq!(limited.atoms.as_slice(), &match_atoms[..*num_atoms as usize]);
         }
+
+        cxx::let_cxx_string!(smarts = "CC");  // symmetric: each bond matched in both directions
+        let sp = ob::OBSmartsPattern_from_smarts(&smarts);
+        assert_eq!(ob::OBSmartsPattern_match(&sp, &mol, 0, 0).offsets.len(), 5);
+        assert_eq!(ob::OBSmartsPattern_match(&sp, &mol, 2, 0).offsets.len(), 3);
+        cxx::let_cxx_string!(smarts = "c");
+        let sp = ob::OBSmartsPattern_from_smarts(&smarts);
+        assert!(!ob::OBSmartsPattern_has_match(&sp, &mol));
+        assert_eq!(ob::OBSmartsPattern_match(&sp, &mol, 0, 0).offsets, vec![0]);
     }
 
     #[test]
@@ -76,14 +235,14 @@ Au      1.442498    2.498480    0.000000";
         let supported_formats = ob::OBConversion_get_supported_input_format();
         assert_eq!(
             supported_formats.len(),
-            26,
+            39 + cfg!(feature = "cml") as usize + cfg!(feature = "inchi") as usize,
             "wrong amount of supported input formats"
         );
 
         let supported_formats = ob::OBConversion_get_supported_output_format();
         assert_eq!(
             supported_formats.len(),
-            19,
+            31 + cfg!(feature = "cml") as usize + 3 * cfg!(feature = "inchi") as usize,
             "wrong amount of supported output formats"
         );
     }
@@ -103,6 +262,28 @@ Au      1.442498    2.498480    0.000000";
         assert_eq!(ob::OBMol_num_atoms(&obmol), 2, "wrong number of atoms");
     }
 
+    #[test]
+    fn test_coordinates() {
+        let obmol = ob::OBMol_new();
+        let obconv = ob::OBConversion_new();
+        cxx::let_cxx_string!(input = TEST_XYZ);
+        cxx::let_cxx_string!(input_format = "xyz");
+        assert!(ob::OBConversion_set_in_format(&obconv, &input_format), "unable to set input format");
+        assert!(ob::OBConversion_read_string(&obconv, &obmol, &input), "unable to read molecule");
+        assert_eq!(ob::OBMol_get_dimension(&obmol), 3);
+
+        let coords = ob::OBMol_get_coordinates(&obmol);
+        assert_eq!(coords.len(), 6);
+        assert_delta!(coords[3], 1.442498, 1e-6);
+        ob::OBMol_translate(&obmol, 1.0, 0.0, 0.0);
+        assert_delta!(ob::OBAtom_get_vector(&obmol, 1)[0], 1.02, 1e-6);
+        ob::OBMol_rotate(&obmol, &[0.0, -1.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0]);
+        assert_delta!(ob::OBAtom_get_vector(&obmol, 1)[1], 1.02, 1e-6);
+        ob::OBMol_set_coordinates(&obmol, &[0.0, 0.0, 0.0, 2.0, 0.0, 0.0]);
+        ob::OBMol_center(&obmol);
+        assert_delta!(ob::OBAtom_get_vector(&obmol, 1)[0], -1.0, 1e-6);
+    }
+
     #[test]
     fn test_write_to_str() {
         let obmol = ob::OBMol_new();
@@ -128,6 +309,70 @@ Au      1.442498    2.498480    0.000000";
         );
     }
 
+    #[test]
+    fn test_conversion_options() {
+        let obmol = ob::OBMol_new();
+        let obconv = ob::OBConversion_new();
+        cxx::let_cxx_string!(input = TEST_XYZ);
+        cxx::let_cxx_string!(input_format = "xyz");
+        cxx::let_cxx_string!(output_format = "smi");
+        assert!(ob::OBConversion_set_in_and_out_formats(&obconv, &input_format, &output_format));
+        assert!(ob::OBConversion_read_string(&obconv, &obmol, &input));
+
+        cxx::let_cxx_string!(no_name = "n");
+        cxx::let_cxx_string!(no_value = "");
+        ob::OBConversion_add_option(&obconv, &no_name, 1, &no_value);
+        assert!(ob::OBConversion_is_option(&obconv, &no_name, 1));
+        assert!(!ob::OBConversion_is_option(&obconv, &no_name, 0));
+        assert_eq!(ob::OBConversion_write_string(&obconv, &obmol), "[Au][Au]\n");
+
+        assert!(ob::OBConversion_remove_option(&obconv, &no_name, 1));
+        assert!(!ob::OBConversion_is_option(&obconv, &no_name, 1));
+        assert_eq!(ob::OBConversion_write_string(&obconv, &obmol), "[Au][Au]\t\n");
+    }
+
+    #[test]
+    fn test_stream_reader() {
+        let input = "CCO ethanol\nC1CC( broken\nc1ccccc1 benzene\n";
+        let source = crate::RustReader::new(Box::new(std::io::Cursor::new(input)));
+        cxx::let_cxx_string!(input_format = "smi");
+        let reader = ob::OBStreamReader_new(Box::new(source), &input_format);
+        assert!(!reader.is_null());
+
+        let mut results = vec![];
+        loop {
+            let offset = ob::OBStreamReader_tell(&reader);
+            let mol = ob::OBMol_new();
+            match ob::OBStreamReader_read(&reader, &mol) {
+                0 => break,
+                status => results.push((status, offset, ob::OBMol_num_atoms(&mol))),
+            }
+        }
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0], (1, 0, 3));
+        assert_eq!((results[1].0, results[1].1), (-1, 12));
+        assert_eq!(results[2], (1, 25, 6));
+    }
+
+    #[test]
+    fn test_stream_writer() {
+        let file_handle = NamedTempFile::new().expect("unable to create temp file");
+        let sink = crate::RustWriter::new(Box::new(file_handle.reopen().unwrap()));
+        cxx::let_cxx_string!(output_format = "smi");
+        let writer = ob::OBStreamWriter_new(Box::new(sink), &output_format);
+        assert!(!writer.is_null());
+        for s in ["CCO", "c1ccccc1"].iter() {
+            cxx::let_cxx_string!(smiles = s);
+            let mol = ob::OBMol_from_smiles(&smiles);
+            assert_eq!(ob::OBStreamWriter_write(&writer, &ob::OBMol_clone(&mol)), 1);
+        }
+        assert!(ob::OBStreamWriter_flush(&writer));
+        let output = std::fs::read_to_string(file_handle.path()).unwrap();
+        assert_eq!(output.lines().count(), 2);
+        assert!(ob::OBStreamWriter_finish(&writer));
+        assert_eq!(std::fs::read_to_string(file_handle.path()).unwrap(), output);
+    }
+
     #[test]
     fn test_read_write_from_file() {
         let file_handle = NamedTempFile::new().expect("unable to create temp file");
@@ -202,6 +447,107 @@ Au      1.442498    2.498480    0.000000";
         ob::OBForceField_conjugate_gradients_initialize(&ff, 100, 1e-5);
         assert!(ob::OBForceField_conjugate_gradients_take_n_steps(&ff, 10) == false);
         assert!(ob::OBForceField_energy(&ff) < 0.01);
+
+        // the optimized coordinates stay in the force field until copied back
+        assert!(ob::OBMol_get_coordinates(&mol).iter().all(|&x| x == 0.0));
+        assert!(ob::OBForceField_get_coordinates(&ff, &mol));
+        assert!(ob::OBMol_get_coordinates(&mol).iter().any(|&x| x != 0.0));
+    }
+
+    #[test]
+    fn test_energy_terms() {
+        for (ff_name, unit) in [("mmff94", "kcal/mol"), ("uff", "kJ/mol"), ("gaff", "kJ/mol")] {
+            cxx::let_cxx_string!(ff_name_cxx = ff_name);
+            let ff = ob::OBForceField_find_forcefield(&ff_name_cxx);
+            cxx::let_cxx_string!(smiles = "CC(=O)O");
+            let mol = ob::OBMol_from_smiles(&smiles);
+            ob::OBForceField_setup(&mol, &ff);
+            ob::OBForceField_steepest_descent(&ff, 20, 1e-5);
+
+            assert_eq!(ob::OBForceField_get_unit(&ff), unit);
+            let terms = ob::OBForceField_energy_terms(&ff);
+            let total = terms.bond + terms.angle + terms.str_bnd + terms.torsion + terms.oop + terms.vdw + terms.electrostatic;
+            let energy = ob::OBForceField_energy(&ff);
+            assert!((total - energy).abs() < 1e-6 * energy.abs().max(1.0));
+            assert_eq!(ob::OBForceField_gradients(&ff).len(), 3 * ob::OBMol_num_atoms(&mol) as usize);
+        }
+    }
+
+    #[test]
+    fn test_constraints() {
+        let constraints = ob::OBFFConstraints_new();
+        ob::OBFFConstraints_add_atom_constraint(&constraints, 1);
+        ob::OBFFConstraints_add_distance_constraint(&constraints, 1, 4, 2.9);
+        ob::OBFFConstraints_set_factor(&constraints, 10000.0);
+        assert_eq!(ob::OBFFConstraints_size(&constraints), 2);
+
+        cxx::let_cxx_string!(ff_name = "uff");
+        let ff = ob::OBForceField_find_forcefield(&ff_name);
+        cxx::let_cxx_string!(smiles = "CC(=O)O");
+        let mol = ob::OBMol_from_smiles(&smiles);
+        ob::OBMol_set_coordinates(&mol, &[0.0, 0.0, 0.0, 1.5, 0.0, 0.0, 2.0, 1.2, 0.0, 2.3, -0.9, 0.4]);
+        assert_eq!(ob::OBForceField_setup_with_constraints(&mol, &ff, &constraints), 0);
+        ob::OBForceField_conjugate_gradients(&ff, 200, 1e-6);
+        assert!(ob::OBForceField_get_coordinates(&ff, &mol));
+        let coords = ob::OBMol_get_coordinates(&mol);
+        assert_eq!(&coords[..3], &[0.0, 0.0, 0.0]);
+        let distance = (coords[9].powi(2) + coords[10].powi(2) + coords[11].powi(2)).sqrt();
+        assert!((distance - 2.9).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_conformers() {
+        cxx::let_cxx_string!(smiles = "CO");
+        let mol = ob::OBMol_from_smiles(&smiles);
+        assert_eq!(ob::OBMol_num_conformers(&mol), 1);
+        ob::OBMol_set_conformers(&mol, &[0.0, 0.0, 0.0, 1.4, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.4, 0.0]);
+        ob::OBMol_set_energies(&mol, &[2.0, 1.0]);
+        assert_eq!(ob::OBMol_num_conformers(&mol), 2);
+        assert_eq!(ob::OBMol_get_coordinates(&mol), vec![0.0, 0.0, 0.0, 1.4, 0.0, 0.0]);
+        ob::OBMol_set_conformer(&mol, 1);
+        assert_eq!(ob::OBMol_get_coordinates(&mol), vec![0.0, 0.0, 0.0, 0.0, 1.4, 0.0]);
+        assert_eq!(ob::OBMol_get_conformer(&mol, 0), vec![0.0, 0.0, 0.0, 1.4, 0.0, 0.0]);
+        assert!(ob::OBMol_get_conformer(&mol, 2).is_empty());
+        assert_eq!(ob::OBMol_get_energies(&mol), vec![2.0, 1.0]);
+    }
+
+    #[test]
+    fn test_rotor_search() {
+        cxx::let_cxx_string!(ff_name = "uff");
+        let ff = ob::OBForceField_find_forcefield(&ff_name);
+        cxx::let_cxx_string!(smiles = "CCCC");
+        let mol = ob::OBMol_from_smiles(&smiles);
+        ob::OBMol_set_coordinates(&mol, &[0.0, 0.0, 0.0, 1.53, 0.0, 0.0, 2.041, 1.442, 0.0, 1.637, 2.127, 1.307]);
+        assert_eq!(ob::OBForceField_setup(&mol, &ff), 0);
+        assert!(ob::OBForceField_systematic_rotor_search_initialize(&ff, 100));
+        while ob::OBForceField_systematic_rotor_search_next_conformer(&ff, 100) {}
+        assert!(ob::OBForceField_get_conformers(&ff, &mol));
+        let num_conformers = ob::OBMol_num_conformers(&mol);
+        assert!(num_conformers > 1);
+        assert_eq!(ob::OBMol_get_energies(&mol).len(), num_conformers as usize);
+        assert!(ob::OBForceField_set_coordinates(&ff, &mol));
+    }
+
+    #[test]
+    fn test_setup_failure() {
+        ob::OBMessageHandler_set_output_level(-1);
+        cxx::let_cxx_string!(ff_name = "no_such_forcefield");
+        assert!(ob::OBForceField_find_forcefield(&ff_name).is_null());
+
+        cxx::let_cxx_string!(smiles = "C[Sn](C)(C)C");
+        let mol = ob::OBMol_from_smiles(&smiles);
+        cxx::let_cxx_string!(ff_name = "mmff94");
+        let ff = ob::OBForceField_find_forcefield(&ff_name);
+        assert_eq!(ob::OBForceField_setup(&mol, &ff), 1);
+        assert!(!ob::OBForceField_is_valid_setup(&ff));
+        let atom_types = ob::OBForceField_atom_types(&ff, &mol);
+        assert_eq!(atom_types.len(), ob::OBMol_num_atoms(&mol) as usize);
+        assert_eq!(atom_types[1], "0");
+        cxx::let_cxx_string!(ff_name = "uff");
+        let ff = ob::OBForceField_find_forcefield(&ff_name);
+        assert_eq!(ob::OBForceField_setup(&mol, &ff), 0);
+        assert!(ob::OBForceField_is_valid_setup(&ff));
+        ob::OBMessageHandler_set_output_level(1);
     }
 
     #[test]
@@ -1,13 +1,71 @@
 #include <sstream>
+#include <fstream>
 #include <vector>
+#include <map>
+#include <cstring>
 #include <openbabel/fingerprint.h>
 #include <openbabel/oberror.h>
+#include <openbabel/obiter.h>
+#include <openbabel/generic.h>
 #include <openbabel/obconversion.h>
 #include <openbabel/forcefield.h>
+#include <openbabel/graphsym.h>
+#include <openbabel/canon.h>
+#include <openbabel/query.h>
+#include <openbabel/isomorphism.h>
+#include <openbabel/stereo/stereo.h>
+#ifdef OPENBABEL_SYS_CML
+#include <openbabel/xml.h>
+#endif
 #include "wrapper.h"
+#include "openbabel-sys/src/lib.rs.h"
 
 namespace OpenBabel {
 
+// Data files
+
+rust::String OBDatafile_locate(const std::string &file_name) {
+    std::ifstream ifs;
+    return OpenDatafile(ifs, file_name);  // empty if not found
+}
+
+// Data files - End
+
+// OBMessageHandler
+
+namespace {
+    // OBMessageHandler keeps its message list protected, reach it through a derived class
+    struct OBMessageHandlerAccess : public OBMessageHandler {
+        static rust::Vec<OBLogMessage> messages(OBMessageHandler & handler) {
+            rust::Vec<OBLogMessage> result {};
+            for (const OBError & err : handler.*(&OBMessageHandlerAccess::_messageList)) {
+                result.push_back(OBLogMessage { static_cast<uint32_t>(err.GetLevel()), err.GetMethod(), err.GetError() });
+            }
+            return result;
+        }
+    };
+
+    std::ostream null_stream(nullptr);  // discards everything written to it
+}
+
+rust::Vec<OBLogMessage> OBMessageHandler_get_messages() { return OBMessageHandlerAccess::messages(obErrorLog); }
+void OBMessageHandler_clear_log() { obErrorLog.ClearLog(); }
+
+void OBMessageHandler_set_output_level(int32_t level) {
+    if (level < 0) {
+        obErrorLog.SetOutputStream(&null_stream);
+    } else {
+        obErrorLog.SetOutputStream(&std::clog);
+        obErrorLog.SetOutputLevel(static_cast<obMessageLevel>(level));
+    }
+}
+
+void OBMessageHandler_set_max_log_entries(u_int32_t max) { obErrorLog.SetMaxLogEntries(max); }
+unsigned int OBMessageHandler_get_error_message_count() { return obErrorLog.GetErrorMessageCount(); }
+unsigned int OBMessageHandler_get_warning_message_count() { return obErrorLog.GetWarningMessageCount(); }
+
+// OBMessageHandler - End
+
 // OBConversion 
 
 // std::unique_ptr<OBMol> OBConversion_smi_to_mol(const std::string &smiles) {
@@ -65,6 +123,30 @@ bool OBConversion_set_in_and_out_formats(const std::unique_ptr<OBConversion> & p
     return result;
 }
 
+namespace {
+    bool has_general_options(OBConversion & conv) {
+        return !conv.GetOptions(OBConversion::GENOPTIONS)->empty();
+    }
+
+    // OBConversion::Read and Write leave the general options (obabel -h, -d, -p, --title, -s, ...)
+    // to OBMoleculeFormat::ReadChemObject, run them here as obabel does.
+    // Returns false if an option filtered the molecule out.
+    bool do_transformations(OBConversion & conv, OBMol & mol) {
+        if (!has_general_options(conv)) return true;
+        OBMol* pCopy = new OBMol(mol);
+        // a molecule that is filtered out is deleted by DoTransformations
+        OBBase* pResult = pCopy->DoTransformations(conv.GetOptions(OBConversion::GENOPTIONS), &conv);
+        if (pResult == nullptr) return false;
+        mol = *static_cast<OBMol*>(pResult);
+        delete pResult;
+        return true;
+    }
+
+    void throw_filtered_out(const char* method) {
+        obErrorLog.ThrowError(method, "The molecule was filtered out by the general options", obError);
+    }
+}
+
 bool OBConversion_read_string(const std::unique_ptr<OBConversion> & pConv, const std::unique_ptr<OBMol> & pMol, const std::string &input) {
     bool result = pConv.get()->ReadString(pMol.get(), input.c_str());
     if (!result) {
@@ -73,11 +155,19 @@ bool OBConversion_read_string(const std::unique_ptr<OBConversion> & pConv, const
         obErrorLog.ThrowError(__FUNCTION__, errorMsg.str(), obError);
         return result;
     }
+    if (!do_transformations(*pConv, *pMol)) {
+        throw_filtered_out(__FUNCTION__);
+        return false;
+    }
     return result;
 }
 
+// General options are run on a copy, pMol is left as it is
 rust::String OBConversion_write_string(const std::unique_ptr<OBConversion> & pConv, const std::unique_ptr<OBMol> & pMol) {
-    return pConv.get()->WriteString(pMol.get());
+    if (!has_general_options(*pConv)) return pConv.get()->WriteString(pMol.get());
+    OBMol mol(*pMol);
+    if (!do_transformations(*pConv, mol)) return rust::String();
+    return pConv.get()->WriteString(&mol);
 }
 
 bool OBConversion_read_file(const std::unique_ptr<OBConversion> & pConv, const std::unique_ptr<OBMol> & pMol, const std::string &input_path) {
@@ -88,11 +178,20 @@ bool OBConversion_read_file(const std::unique_ptr<OBConversion> & pConv, const s
         obErrorLog.ThrowError(__FUNCTION__, errorMsg.str(), obError);
 	return result;
     }
+    if (!do_transformations(*pConv, *pMol)) {
+        throw_filtered_out(__FUNCTION__);
+        return false;
+    }
     return result;
 }
 
 bool OBConversion_write_file(const std::unique_ptr<OBConversion> & pConv, const std::unique_ptr<OBMol> & pMol, const std::string &output_path) {
-    bool result = pConv.get()->WriteFile(pMol.get(), output_path);
+    OBMol mol(*pMol);
+    if (!do_transformations(*pConv, mol)) {
+        throw_filtered_out(__FUNCTION__);
+        return false;
+    }
+    bool result = pConv.get()->WriteFile(&mol, output_path);
     if (!result) {
         std::stringstream errorMsg;
         errorMsg << "OBConversion::WriteFile error" << std::endl;
@@ -120,22 +219,214 @@ rust::Vec<rust::String> OBConversion_get_supported_output_format() {
     return result;
 }
 
+// An empty value adds the option without a parameter
+void OBConversion_add_option(const std::unique_ptr<OBConversion> & pConv, const std::string &name, u_int32_t opt_type, const std::string &value) {
+    pConv.get()->AddOption(name.c_str(), static_cast<OBConversion::Option_type>(opt_type), value.empty() ? nullptr : value.c_str());
+}
+
+bool OBConversion_remove_option(const std::unique_ptr<OBConversion> & pConv, const std::string &name, u_int32_t opt_type) {
+    return pConv.get()->RemoveOption(name.c_str(), static_cast<OBConversion::Option_type>(opt_type));
+}
+
+bool OBConversion_is_option(const std::unique_ptr<OBConversion> & pConv, const std::string &name, u_int32_t opt_type) {
+    return pConv.get()->IsOption(name.c_str(), static_cast<OBConversion::Option_type>(opt_type)) != nullptr;
+}
+
 // OBConversion - End
 
+// OBStreamReader
+
+RustInStreamBuf::RustInStreamBuf(rust::Box<RustReader> source) : source(std::move(source)), consumed(0) {}
+
+RustInStreamBuf::int_type RustInStreamBuf::underflow() {
+    if (gptr() < egptr()) return traits_type::to_int_type(*gptr());
+    consumed += egptr() - eback();
+    size_t n = source->read_chunk(rust::Slice<uint8_t>(reinterpret_cast<uint8_t*>(buffer), sizeof(buffer)));
+    if (n == 0) return traits_type::eof();
+    setg(buffer, buffer, buffer + n);
+    return traits_type::to_int_type(*gptr());
+}
+
+RustInStreamBuf::pos_type RustInStreamBuf::seekoff(off_type off, std::ios_base::seekdir dir, std::ios_base::openmode which) {
+    // only tellg() is supported, the source cannot seek
+    if (off == 0 && dir == std::ios_base::cur && (which & std::ios_base::in)) {
+        return pos_type(consumed + (gptr() - eback()));
+    }
+    return pos_type(off_type(-1));
+}
+
+OBStreamReader::OBStreamReader(rust::Box<RustReader> source) : buf(std::move(source)), stream(&buf) {
+    conv.SetInStream(&stream, false);
+}
+
+std::unique_ptr<OBStreamReader> OBStreamReader_new(rust::Box<RustReader> source, const std::string &input_format) {
+    std::unique_ptr<OBStreamReader> pReader(new OBStreamReader(std::move(source)));
+    if (!pReader->conv.SetInFormat(OBConversion::FindFormat(input_format))) {
+        std::stringstream errorMsg;
+        errorMsg << "OBConversion::SetInFormat(" << input_format << ")" << std::endl;
+        obErrorLog.ThrowError(__FUNCTION__, errorMsg.str(), obError);
+        return std::unique_ptr<OBStreamReader>(nullptr);
+    }
+    return pReader;
+}
+
+// Returns 1 if a record was read, 0 at the end of the input,
+// -1 if the record failed and the stream was moved to the next record (as with the -e option of obabel),
+// -2 if the record failed and the stream cannot be resynchronized.
+// Records filtered out by the general options are skipped.
+int OBStreamReader_read(const std::unique_ptr<OBStreamReader> & pReader, const std::unique_ptr<OBMol> & pMol) {
+    std::istream & in = pReader->stream;
+    if (in.peek() == EOF) return 0;
+    while (pReader->conv.Read(pMol.get())) {
+        if (do_transformations(pReader->conv, *pMol)) return 1;
+        pMol->Clear();
+        if (in.peek() == EOF) return 0;
+    }
+
+    in.clear();
+    if (in.peek() == EOF) {
+        return pMol->NumAtoms() == 0 ? 0 : -1;  // nothing but trailing blank lines
+    }
+    if (pReader->conv.GetInFormat()->SkipObjects(0, &pReader->conv) != 1) return -2;
+    in.clear();
+    return -1;
+}
+
+u_int64_t OBStreamReader_tell(const std::unique_ptr<OBStreamReader> & pReader) {
+    std::streamoff pos = pReader->stream.rdbuf()->pubseekoff(0, std::ios_base::cur, std::ios_base::in);
+    return pos < 0 ? 0 : static_cast<u_int64_t>(pos);
+}
+
+void OBStreamReader_add_option(const std::unique_ptr<OBStreamReader> & pReader, const std::string &name, u_int32_t opt_type, const std::string &value) {
+    pReader->conv.AddOption(name.c_str(), static_cast<OBConversion::Option_type>(opt_type), value.empty() ? nullptr : value.c_str());
+}
+
+// OBStreamReader - End
+
+// OBStreamWriter
+
+RustOutStreamBuf::RustOutStreamBuf(rust::Box<RustWriter> sink) : sink(std::move(sink)) {
+    setp(buffer, buffer + sizeof(buffer) - 1);  // keep one byte for overflow()
+}
+
+RustOutStreamBuf::~RustOutStreamBuf() { sync(); }
+
+bool RustOutStreamBuf::write_buffer() {
+    std::ptrdiff_t n = pptr() - pbase();
+    if (n == 0) return true;
+    bool result = sink->write_chunk(rust::Slice<const uint8_t>(reinterpret_cast<const uint8_t*>(pbase()), n));
+    pbump(-static_cast<int>(n));
+    return result;
+}
+
+RustOutStreamBuf::int_type RustOutStreamBuf::overflow(int_type ch) {
+    if (!traits_type::eq_int_type(ch, traits_type::eof())) {
+        *pptr() = traits_type::to_char_type(ch);
+        pbump(1);
+    }
+    return write_buffer() ? traits_type::not_eof(ch) : traits_type::eof();
+}
+
+int RustOutStreamBuf::sync() {
+    return write_buffer() && sink->flush() ? 0 : -1;
+}
+
+OBStreamWriter::OBStreamWriter(rust::Box<RustWriter> sink, const std::string &format) : buf(std::move(sink)), stream(&buf), format(format) {
+    conv.SetOutStream(&stream, false);
+}
+
+std::unique_ptr<OBStreamWriter> OBStreamWriter_new(rust::Box<RustWriter> sink, const std::string &output_format) {
+    std::unique_ptr<OBStreamWriter> pWriter(new OBStreamWriter(std::move(sink), output_format));
+    if (!pWriter->conv.SetOutFormat(OBConversion::FindFormat(output_format))) {
+        std::stringstream errorMsg;
+        errorMsg << "OBConversion::SetOutFormat(" << output_format << ")" << std::endl;
+        obErrorLog.ThrowError(__FUNCTION__, errorMsg.str(), obError);
+        return std::unique_ptr<OBStreamWriter>(nullptr);
+    }
+    return pWriter;
+}
+
+// Records go out as they come, none of them flagged as the last one (OBConversion::SetLast).
+// Returns 1 if the record was written, 0 if the general options filtered it out, -1 on error.
+int OBStreamWriter_write(const std::unique_ptr<OBStreamWriter> & pWriter, const std::unique_ptr<OBMol> & pMol) {
+    OBConversion & conv = pWriter->conv;
+    OBMol* pOut = pMol.get();
+    OBMol mol;
+    if (has_general_options(conv)) {
+        mol = *pMol;
+        if (!do_transformations(conv, mol)) return 0;
+        pOut = &mol;
+    }
+    conv.SetLast(false);
+    bool result = conv.Write(pOut);
+    if (!result) {
+        std::stringstream errorMsg;
+        errorMsg << "OBConversion::Write error" << std::endl;
+        obErrorLog.ThrowError(__FUNCTION__, errorMsg.str(), obError);
+    }
+    return result && pWriter->stream.good() ? 1 : -1;
+}
+
+// Writes what the formats put after their last record: END of a multi-model PDB, the end of
+// the CML document
+bool OBStreamWriter_finish(const std::unique_ptr<OBStreamWriter> & pWriter) {
+    OBConversion & conv = pWriter->conv;
+    conv.SetLast(true);
+    if (conv.GetOutputIndex() > 0) {
+        if (pWriter->format == "pdb" || pWriter->format == "ent") {
+            pWriter->stream << "END\n";
+        }
+#ifdef OPENBABEL_SYS_CML
+        if (dynamic_cast<XMLBaseFormat*>(conv.GetOutFormat()) != nullptr) {
+            XMLConversion* pxmlConv = XMLConversion::GetDerived(&conv, false);
+            xmlTextWriterEndDocument(pxmlConv->GetWriter());
+            pxmlConv->OutputToStream();
+        }
+#endif
+    }
+    pWriter->stream.flush();
+    return pWriter->stream.good();
+}
+
+bool OBStreamWriter_flush(const std::unique_ptr<OBStreamWriter> & pWriter) {
+    pWriter->stream.flush();
+    return pWriter->stream.good();
+}
+
+void OBStreamWriter_add_option(const std::unique_ptr<OBStreamWriter> & pWriter, const std::string &name, u_int32_t opt_type, const std::string &value) {
+    pWriter->conv.AddOption(name.c_str(), static_cast<OBConversion::Option_type>(opt_type), value.empty() ? nullptr : value.c_str());
+}
+
+// OBStreamWriter - End
+
 // OBForceField
 std::unique_ptr<OBForceField> OBForceField_find_forcefield(const std::string &ff_name) {
     OBForceField* raw_ff = OBForceField::FindForceField(ff_name.c_str());
+    if (!raw_ff) {
+        std::stringstream errorMsg;
+        errorMsg << "OBForceField::FindForceField(" << ff_name << ") error" << std::endl;
+        obErrorLog.ThrowError(__FUNCTION__, errorMsg.str(), obError);
+        return std::unique_ptr<OBForceField>();
+    }
     std::unique_ptr<OBForceField> p_ff(raw_ff->MakeNewInstance());
 
     if (!p_ff) {
         std::stringstream errorMsg;
-        errorMsg << "OBForceField::FindForceField error" << std::endl;
+        errorMsg << "OBForceField::MakeNewInstance error" << std::endl;
         obErrorLog.ThrowError(__FUNCTION__, errorMsg.str(), obError);
     }
 
     return p_ff;
 }
 
+namespace {
+    // OBForceField keeps its copy of the molecule protected, reach it through a derived class
+    struct OBForceFieldAccess : public OBForceField {
+        static const OBMol & mol(const OBForceField & ff) { return ff.*(&OBForceFieldAccess::_mol); }
+        static bool valid_setup(const OBForceField & ff) { return ff.*(&OBForceFieldAccess::_validSetup); }
+    };
+}
+
 unsigned int OBForceField_setup(const std::unique_ptr<OBMol> & pMol, const std::unique_ptr<OBForceField> & pFF) {
     pFF.get()->SetLogLevel(0);
     if (!pFF.get()->Setup(*pMol)) {
@@ -173,9 +464,131 @@ bool OBForceField_steepest_descent_take_n_steps(const std::unique_ptr<OBForceFie
 
 double OBForceField_energy(const std::unique_ptr<OBForceField> & pFF) { return pFF.get()->Energy(); }
 bool OBForceField_is_setup_needed(const std::unique_ptr<OBForceField> & pFF, const std::unique_ptr<OBMol> & pMol) { return pFF.get()->IsSetupNeeded(*pMol); }
+bool OBForceField_is_valid_setup(const std::unique_ptr<OBForceField> & pFF) { return OBForceFieldAccess::valid_setup(*pFF); }
+
+rust::Vec<rust::String> OBForceField_atom_types(const std::unique_ptr<OBForceField> & pFF, const std::unique_ptr<OBMol> & pMol) {
+    rust::Vec<rust::String> result {};
+    OBMol mol(*pMol);  // GetAtomTypes stores the types as "FFAtomType" data on the atoms
+    if (!pFF->GetAtomTypes(mol)) return result;
+    FOR_ATOMS_OF_MOL (atom, mol) {
+        OBPairData * atom_type = dynamic_cast<OBPairData *>(atom->GetData("FFAtomType"));
+        result.push_back(rust::String(atom_type ? atom_type->GetValue() : std::string()));
+    }
+    return result;
+}
+
+OBFFEnergyTerms OBForceField_energy_terms(const std::unique_ptr<OBForceField> & pFF) {
+    OBForceField & ff = *pFF;
+    return OBFFEnergyTerms {
+        ff.E_Bond(false), ff.E_Angle(false), ff.E_StrBnd(false), ff.E_Torsion(false),
+        ff.E_OOP(false), ff.E_VDW(false), ff.E_Electrostatic(false)
+    };
+}
+
+rust::Vec<double> OBForceField_gradients(const std::unique_ptr<OBForceField> & pFF) {
+    pFF->Energy(true);  // fills the gradients of the current coordinates
+    rust::Vec<double> result {};
+    const OBMol & mol = OBForceFieldAccess::mol(*pFF);
+    for (unsigned int i = 1; i <= mol.NumAtoms(); ++i) {
+        vector3 grad = pFF->GetGradient(mol.GetAtom(i));
+        result.push_back(grad.x());
+        result.push_back(grad.y());
+        result.push_back(grad.z());
+    }
+    return result;
+}
+
+bool OBForceField_validate_gradients(const std::unique_ptr<OBForceField> & pFF) { return pFF->ValidateGradients(); }
+rust::String OBForceField_get_unit(const std::unique_ptr<OBForceField> & pFF) { return rust::String(pFF->GetUnit()); }
+
+bool OBForceField_get_coordinates(const std::unique_ptr<OBForceField> & pFF, const std::unique_ptr<OBMol> & pMol) { return pFF.get()->GetCoordinates(*pMol); }
+
+unsigned int OBForceField_setup_with_constraints(const std::unique_ptr<OBMol> & pMol, const std::unique_ptr<OBForceField> & pFF, const std::unique_ptr<OBFFConstraints> & pConstraints) {
+    pFF.get()->SetLogLevel(0);
+    if (!pFF.get()->Setup(*pMol, *pConstraints)) {
+        std::stringstream errorMsg;
+        errorMsg << "OBForceField->Setup() error" << std::endl;
+        obErrorLog.ThrowError(__FUNCTION__, errorMsg.str(), obError);
+        return 1;
+    }
+    return 0;
+}
+
+bool OBForceField_set_coordinates(const std::unique_ptr<OBForceField> & pFF, const std::unique_ptr<OBMol> & pMol) { return pFF->SetCoordinates(*pMol); }
+bool OBForceField_get_conformers(const std::unique_ptr<OBForceField> & pFF, const std::unique_ptr<OBMol> & pMol) { return pFF->GetConformers(*pMol); }
+bool OBForceField_systematic_rotor_search_initialize(const std::unique_ptr<OBForceField> & pFF, u_int32_t geom_steps) {
+    return pFF->SystematicRotorSearchInitialize(geom_steps) > 0;
+}
+
+bool OBForceField_systematic_rotor_search_next_conformer(const std::unique_ptr<OBForceField> & pFF, u_int32_t geom_steps) {
+    return pFF->SystematicRotorSearchNextConformer(geom_steps);
+}
+
+void OBForceField_random_rotor_search_initialize(const std::unique_ptr<OBForceField> & pFF, u_int32_t conformers, u_int32_t geom_steps) {
+    pFF->RandomRotorSearchInitialize(conformers, geom_steps);
+}
+
+bool OBForceField_random_rotor_search_next_conformer(const std::unique_ptr<OBForceField> & pFF, u_int32_t geom_steps) {
+    return pFF->RandomRotorSearchNextConformer(geom_steps);
+}
 
 // OBForceField End
 
+// OBFFConstraints, atom indices are 1-based as in OpenBabel
+
+std::unique_ptr<OBFFConstraints> OBFFConstraints_new() { return std::unique_ptr<OBFFConstraints>(new OBFFConstraints()); }
+void OBFFConstraints_set_factor(const std::unique_ptr<OBFFConstraints> & pConstraints, double factor) { pConstraints->SetFactor(factor); }
+void OBFFConstraints_add_ignore(const std::unique_ptr<OBFFConstraints> & pConstraints, u_int32_t a) { pConstraints->AddIgnore(a); }
+void OBFFConstraints_add_atom_constraint(const std::unique_ptr<OBFFConstraints> & pConstraints, u_int32_t a) { pConstraints->AddAtomConstraint(a); }
+void OBFFConstraints_add_atom_x_constraint(const std::unique_ptr<OBFFConstraints> & pConstraints, u_int32_t a) { pConstraints->AddAtomXConstraint(a); }
+void OBFFConstraints_add_atom_y_constraint(const std::unique_ptr<OBFFConstraints> & pConstraints, u_int32_t a) { pConstraints->AddAtomYConstraint(a); }
+void OBFFConstraints_add_atom_z_constraint(const std::unique_ptr<OBFFConstraints> & pConstraints, u_int32_t a) { pConstraints->AddAtomZConstraint(a); }
+
+void OBFFConstraints_add_distance_constraint(const std::unique_ptr<OBFFConstraints> & pConstraints, u_int32_t a, u_int32_t b, double length) {
+    pConstraints->AddDistanceConstraint(a, b, length);
+}
+
+void OBFFConstraints_add_angle_constraint(const std::unique_ptr<OBFFConstraints> & pConstraints, u_int32_t a, u_int32_t b, u_int32_t c, double angle) {
+    pConstraints->AddAngleConstraint(a, b, c, angle);
+}
+
+void OBFFConstraints_add_torsion_constraint(const std::unique_ptr<OBFFConstraints> & pConstraints, u_int32_t a, u_int32_t b, u_int32_t c, u_int32_t d, double torsion) {
+    pConstraints->AddTorsionConstraint(a, b, c, d, torsion);
+}
+
+unsigned int OBFFConstraints_size(const std::unique_ptr<OBFFConstraints> & pConstraints) { return pConstraints->Size(); }
+
+// OBFFConstraints End
+
+// OBConformerSearch
+
+std::unique_ptr<OBConformerSearchRun> OBConformerSearch_setup(const std::unique_ptr<OBMol> & pMol, u_int32_t num_conformers, u_int32_t num_children, u_int32_t mutability, u_int32_t convergence) {
+#ifdef OPENBABEL_SYS_CONFORMERSEARCH
+    std::unique_ptr<OBConformerSearchRun> pRun(new OBConformerSearchRun());
+    if (!pRun->search.Setup(*pMol, num_conformers, num_children, mutability, convergence)) {
+        std::stringstream errorMsg;
+        errorMsg << "OBConformerSearch::Setup error" << std::endl;
+        obErrorLog.ThrowError(__FUNCTION__, errorMsg.str(), obError);
+        return std::unique_ptr<OBConformerSearchRun>(nullptr);
+    }
+    return pRun;
+#else
+    std::stringstream errorMsg;
+    errorMsg << "OBConformerSearch needs Eigen3, build with the conformersearch feature" << std::endl;
+    obErrorLog.ThrowError(__FUNCTION__, errorMsg.str(), obError);
+    return std::unique_ptr<OBConformerSearchRun>(nullptr);
+#endif
+}
+
+void OBConformerSearch_search(const std::unique_ptr<OBConformerSearchRun> & pRun, const std::unique_ptr<OBMol> & pMol) {
+#ifdef OPENBABEL_SYS_CONFORMERSEARCH
+    pRun->search.Search();
+    pRun->search.GetConformers(*pMol);
+#endif
+}
+
+// OBConformerSearch End
+
 
 // OBMol
 std::unique_ptr<OBMol> OBMol_new() { return std::unique_ptr<OBMol>(new OBMol()); }
@@ -200,61 +613,424 @@ std::unique_ptr<OBMol> OBMol_from_smiles(const std::string &smiles) {
     return pMol;
 }
 
+std::unique_ptr<OBMol> OBMol_clone(const std::unique_ptr<OBMol> & pMol) { return std::unique_ptr<OBMol>(new OBMol(*pMol)); }
+
 unsigned int OBMol_num_atoms(const std::unique_ptr<OBMol> & pMol) { return pMol->NumAtoms(); }
 unsigned int OBMol_num_bonds(const std::unique_ptr<OBMol> & pMol) { return pMol->NumBonds(); }
 unsigned int OBMol_num_hvy_atoms(const std::unique_ptr<OBMol> & pMol) { return pMol->NumHvyAtoms(); }
 double OBMol_get_mol_wt(const std::unique_ptr<OBMol> & pMol) { return pMol->GetMolWt(); }
 
+rust::Vec<double> OBMol_get_coordinates(const std::unique_ptr<OBMol> & pMol) {
+    rust::Vec<double> result {};
+    FOR_ATOMS_OF_MOL(atom, pMol.get()) {
+        result.push_back(atom->GetX());
+        result.push_back(atom->GetY());
+        result.push_back(atom->GetZ());
+    }
+    return result;
+}
+
+void OBMol_set_coordinates(const std::unique_ptr<OBMol> & pMol, rust::Slice<const double> coords) {
+    size_t i = 0;
+    FOR_ATOMS_OF_MOL(atom, pMol.get()) {
+        atom->SetVector(coords[i], coords[i + 1], coords[i + 2]);
+        i += 3;
+    }
+}
+
+rust::Vec<double> OBMol_get_cell_vectors(const std::unique_ptr<OBMol> & pMol) {
+    rust::Vec<double> result {};
+    OBUnitCell* pCell = static_cast<OBUnitCell*>(pMol->GetData(OBGenericDataType::UnitCell));
+    if (pCell == nullptr) return result;
+    for (const vector3 & v : pCell->GetCellVectors()) {
+        result.push_back(v.x());
+        result.push_back(v.y());
+        result.push_back(v.z());
+    }
+    return result;
+}
+
+unsigned int OBMol_get_dimension(const std::unique_ptr<OBMol> & pMol) { return pMol->GetDimension(); }
+void OBMol_set_dimension(const std::unique_ptr<OBMol> & pMol, u_int32_t dim) { pMol->SetDimension(dim); }
+void OBMol_center(const std::unique_ptr<OBMol> & pMol) { pMol->Center(); }
+void OBMol_translate(const std::unique_ptr<OBMol> & pMol, double x, double y, double z) { pMol->Translate(vector3(x, y, z)); }
+
+void OBMol_rotate(const std::unique_ptr<OBMol> & pMol, rust::Slice<const double> matrix) {
+    double m[9];
+    std::copy(matrix.begin(), matrix.end(), m);
+    pMol->Rotate(m);
+}
+
+unsigned int OBMol_num_conformers(const std::unique_ptr<OBMol> & pMol) { return pMol->NumConformers(); }
+
+rust::Vec<double> OBMol_get_conformer(const std::unique_ptr<OBMol> & pMol, u_int32_t idx) {
+    rust::Vec<double> result {};
+    if (idx >= static_cast<u_int32_t>(pMol->NumConformers())) {
+        return result;
+    }
+    const double* xyz = pMol->GetConformer(idx);
+    for (unsigned int i = 0; i < 3 * pMol->NumAtoms(); ++i) {
+        result.push_back(xyz[i]);
+    }
+    return result;
+}
+
+void OBMol_set_conformers(const std::unique_ptr<OBMol> & pMol, rust::Slice<const double> coords) {
+    const size_t size = 3 * pMol->NumAtoms();
+    if (size == 0 || coords.size() < size) {
+        return;
+    }
+    std::vector<double*> conformers;
+    for (size_t offset = 0; offset + size <= coords.size(); offset += size) {
+        double* xyz = new double[size];  // owned by the OBMol
+        std::copy(coords.begin() + offset, coords.begin() + offset + size, xyz);
+        conformers.push_back(xyz);
+    }
+    pMol->SetConformers(conformers);
+    pMol->SetConformer(0);
+}
+
+void OBMol_set_conformer(const std::unique_ptr<OBMol> & pMol, u_int32_t idx) {
+    if (idx < static_cast<u_int32_t>(pMol->NumConformers())) {
+        pMol->SetConformer(idx);
+    }
+}
+
+rust::Vec<double> OBMol_get_energies(const std::unique_ptr<OBMol> & pMol) {
+    rust::Vec<double> result {};
+    for (double energy : pMol->GetEnergies()) {
+        result.push_back(energy);
+    }
+    return result;
+}
+
+void OBMol_set_energies(const std::unique_ptr<OBMol> & pMol, rust::Slice<const double> energies) {
+    std::vector<double> values(energies.begin(), energies.end());
+    pMol->SetEnergies(values);
+}
+
+namespace {
+    // OBMol::BeginModify/EndModify keep only the current conformer, carry the others over the
+    // edit; `adapt` fixes each saved conformer up for the atoms added or removed
+    template <typename Edit, typename Adapt>
+    bool modify_keeping_conformers(OBMol & mol, Edit edit, Adapt adapt) {
+        std::vector<std::vector<double>> conformers;
+        std::vector<double> energies = mol.GetEnergies();
+        int current = 0;
+        if (mol.NumConformers() > 1) {
+            for (int i = 0; i < mol.NumConformers(); ++i) {
+                const double* xyz = mol.GetConformer(i);
+                conformers.emplace_back(xyz, xyz + 3 * mol.NumAtoms());
+                if (xyz == mol.GetCoordinates()) current = i;
+            }
+        }
+        mol.BeginModify();
+        bool result = edit();
+        mol.EndModify();
+        if (!conformers.empty()) {
+            std::vector<double*> restored;
+            for (auto & coords : conformers) {
+                if (result) adapt(coords);
+                double* xyz = new double[coords.size()];  // owned by the OBMol
+                std::copy(coords.begin(), coords.end(), xyz);
+                restored.push_back(xyz);
+            }
+            mol.SetConformers(restored);
+            mol.SetConformer(current);
+            mol.SetEnergies(energies);
+        }
+        return result;
+    }
+
+    void same_atoms(std::vector<double> &) {}
+}
+
+unsigned int OBMol_add_atom(const std::unique_ptr<OBMol> & pMol, u_int32_t atomic_num) {
+    OBAtom* pAtom = nullptr;
+    modify_keeping_conformers(*pMol, [&] {
+        pAtom = pMol->NewAtom();
+        pAtom->SetAtomicNum(atomic_num);
+        return true;
+    }, [](std::vector<double> & coords) { coords.insert(coords.end(), 3, 0.0); });
+    return pAtom->GetIdx();
+}
+
+bool OBMol_add_bond(const std::unique_ptr<OBMol> & pMol, u_int32_t begin_idx, u_int32_t end_idx, u_int32_t order) {
+    if (begin_idx == end_idx || pMol->GetBond(begin_idx, end_idx) != nullptr) return false;
+    return modify_keeping_conformers(*pMol, [&] { return pMol->AddBond(begin_idx, end_idx, order); }, same_atoms);
+}
+
+bool OBMol_delete_atom(const std::unique_ptr<OBMol> & pMol, u_int32_t idx) {
+    return modify_keeping_conformers(*pMol, [&] { return pMol->DeleteAtom(pMol->GetAtom(idx)); }, [&](std::vector<double> & coords) {
+        coords.erase(coords.begin() + 3 * (idx - 1), coords.begin() + 3 * idx);
+    });
+}
+
+bool OBMol_delete_bond(const std::unique_ptr<OBMol> & pMol, u_int32_t idx) {
+    return modify_keeping_conformers(*pMol, [&] { return pMol->DeleteBond(pMol->GetBond(idx)); }, same_atoms);
+}
+
+rust::Vec<u_int32_t> OBMol_get_symmetry_classes(const std::unique_ptr<OBMol> & pMol) {
+    std::vector<unsigned int> symmetry_classes;
+    OBGraphSym graph_sym(pMol.get());
+    graph_sym.GetSymmetry(symmetry_classes);
+    rust::Vec<u_int32_t> result {};
+    for (auto symmetry_class : symmetry_classes) result.push_back(symmetry_class);
+    return result;
+}
+
+rust::Vec<u_int32_t> OBMol_get_canonical_labels(const std::unique_ptr<OBMol> & pMol) {
+    std::vector<unsigned int> symmetry_classes;
+    OBGraphSym graph_sym(pMol.get());
+    graph_sym.GetSymmetry(symmetry_classes);
+    std::vector<unsigned int> canonical_labels;
+    CanonicalLabels(pMol.get(), symmetry_classes, canonical_labels);
+    rust::Vec<u_int32_t> result {};
+    for (auto label : canonical_labels) result.push_back(label);
+    return result;
+}
+
+bool OBMol_is_perceived(const std::unique_ptr<OBMol> & pMol) {
+    return pMol->NumAtoms() == 0 || (pMol->HasSSSRPerceived() && pMol->HasAromaticPerceived()
+        && pMol->HasHybridizationPerceived() && pMol->HasChiralityPerceived());
+}
+
+void OBMol_perceive(const std::unique_ptr<OBMol> & pMol) {
+    if (pMol->NumAtoms() == 0) return;
+    pMol->GetSSSR();
+    pMol->GetAtom(1)->GetHyb();  // runs the aromatic typer as well
+    OBStereoFacade facade(pMol.get());
+    facade.NumTetrahedralStereo();
+}
+
+bool OBMol_has_substructure(const std::unique_ptr<OBMol> & pMol, const std::unique_ptr<OBMol> & pQuery) {
+    std::unique_ptr<OBQuery> query(CompileMoleculeQuery(pQuery.get()));
+    std::unique_ptr<OBIsomorphismMapper> mapper(OBIsomorphismMapper::GetInstance(query.get()));
+    OBIsomorphismMapper::Mapping mapping;
+    mapper->MapFirst(pMol.get(), mapping);
+    return !mapping.empty();
+}
+
 // OBMol End
 
+// OBAtom
+
+unsigned int OBAtom_get_atomic_num(const std::unique_ptr<OBMol> & pMol, u_int32_t idx) { return pMol->GetAtom(idx)->GetAtomicNum(); }
+unsigned int OBAtom_get_isotope(const std::unique_ptr<OBMol> & pMol, u_int32_t idx) { return pMol->GetAtom(idx)->GetIsotope(); }
+int OBAtom_get_formal_charge(const std::unique_ptr<OBMol> & pMol, u_int32_t idx) { return pMol->GetAtom(idx)->GetFormalCharge(); }
+unsigned int OBAtom_get_implicit_h_count(const std::unique_ptr<OBMol> & pMol, u_int32_t idx) { return pMol->GetAtom(idx)->GetImplicitHCount(); }
+unsigned int OBAtom_get_hyb(const std::unique_ptr<OBMol> & pMol, u_int32_t idx) { return pMol->GetAtom(idx)->GetHyb(); }
+bool OBAtom_is_aromatic(const std::unique_ptr<OBMol> & pMol, u_int32_t idx) { return pMol->GetAtom(idx)->IsAromatic(); }
+rust::Vec<double> OBAtom_get_vector(const std::unique_ptr<OBMol> & pMol, u_int32_t idx) {
+    OBAtom* pAtom = pMol->GetAtom(idx);
+    rust::Vec<double> result {};
+    result.push_back(pAtom->GetX());
+    result.push_back(pAtom->GetY());
+    result.push_back(pAtom->GetZ());
+    return result;
+}
+
+bool OBAtom_is_in_ring(const std::unique_ptr<OBMol> & pMol, u_int32_t idx) { return pMol->GetAtom(idx)->IsInRing(); }
+unsigned int OBAtom_get_explicit_degree(const std::unique_ptr<OBMol> & pMol, u_int32_t idx) { return pMol->GetAtom(idx)->GetExplicitDegree(); }
+
+rust::Vec<u_int32_t> OBAtom_get_neighbors(const std::unique_ptr<OBMol> & pMol, u_int32_t idx) {
+    rust::Vec<u_int32_t> result {};
+    FOR_NBORS_OF_ATOM(nbr, pMol->GetAtom(idx)) {
+        result.push_back(nbr->GetIdx());
+    }
+    return result;
+}
+
+rust::Vec<u_int32_t> OBAtom_get_bonds(const std::unique_ptr<OBMol> & pMol, u_int32_t idx) {
+    rust::Vec<u_int32_t> result {};
+    FOR_BONDS_OF_ATOM(bond, pMol->GetAtom(idx)) {
+        result.push_back(bond->GetIdx());
+    }
+    return result;
+}
+
+void OBAtom_set_formal_charge(const std::unique_ptr<OBMol> & pMol, u_int32_t idx, int charge) {
+    modify_keeping_conformers(*pMol, [&] { pMol->GetAtom(idx)->SetFormalCharge(charge); return true; }, same_atoms);
+}
+
+void OBAtom_set_isotope(const std::unique_ptr<OBMol> & pMol, u_int32_t idx, u_int32_t isotope) {
+    modify_keeping_conformers(*pMol, [&] { pMol->GetAtom(idx)->SetIsotope(isotope); return true; }, same_atoms);
+}
+
+void OBAtom_set_implicit_h_count(const std::unique_ptr<OBMol> & pMol, u_int32_t idx, u_int32_t count) {
+    modify_keeping_conformers(*pMol, [&] { pMol->GetAtom(idx)->SetImplicitHCount(count); return true; }, same_atoms);
+}
+
+// OBAtom End
+
+// OBBond
+
+unsigned int OBBond_get_bond_order(const std::unique_ptr<OBMol> & pMol, u_int32_t idx) { return pMol->GetBond(idx)->GetBondOrder(); }
+unsigned int OBBond_get_begin_atom_idx(const std::unique_ptr<OBMol> & pMol, u_int32_t idx) { return pMol->GetBond(idx)->GetBeginAtomIdx(); }
+unsigned int OBBond_get_end_atom_idx(const std::unique_ptr<OBMol> & pMol, u_int32_t idx) { return pMol->GetBond(idx)->GetEndAtomIdx(); }
+bool OBBond_is_aromatic(const std::unique_ptr<OBMol> & pMol, u_int32_t idx) { return pMol->GetBond(idx)->IsAromatic(); }
+
+// OBBond End
+
 // OBFingerprint
 
+bool OBFingerprint_find_fingerprint(const std::string &fp_name) {
+    return OBFingerprint::FindFingerprint(fp_name.c_str()) != nullptr;
+}
+
 std::unique_ptr<FPData> OBFingerprint_get_fingerprint(const std::string &fp_name, const std::unique_ptr<OBMol> & pMol, u_int32_t nbits) {
     FPData fps;
     fps.resize(nbits / 32);
     OBFingerprint* pFP = OBFingerprint::FindFingerprint(fp_name.c_str());
 
-    if (!pFP) {
-        std::stringstream errorMsg;
-        errorMsg << "Cannot find fingerprint " << fp_name << std::endl;
-        obErrorLog.ThrowError(__FUNCTION__, errorMsg.str(), obError);
+    // No error is logged: callers hold only the lock of the fingerprint type, not the one of obErrorLog
+    if (!pFP || !pFP->GetFingerprint(pMol.get(), fps, nbits)) {
         std::fill(fps.begin(), fps.end(), 0);
-    } else {
-        if(!pFP->GetFingerprint(pMol.get(), fps, nbits)) {
-            std::stringstream errorMsg;
-            errorMsg << "Error on generating fingerprint " << fp_name << std::endl;
-            obErrorLog.ThrowError(__FUNCTION__, errorMsg.str(), obError);
-            std::fill(fps.begin(), fps.end(), 0);
-        }
     }
 
     return std::make_unique<FPData>(std::move(fps));
 }
 
+double OBFingerprint_tanimoto(const rust::Vec<u_int32_t> & fp1, const rust::Vec<u_int32_t> & fp2) {
+    std::vector<unsigned int> vec1(fp1.begin(), fp1.end());
+    std::vector<unsigned int> vec2(fp2.begin(), fp2.end());
+    return OBFingerprint::Tanimoto(vec1, vec2);
+}
+
 // OBFingerprint - End
 
+// FastSearch
+
+std::unique_ptr<OBFastSearchWriter> OBFastSearchWriter_new(const std::string &index_path, const std::string &datafile_name, const std::string &fp_name, u_int32_t nbits) {
+    std::unique_ptr<OBFastSearchWriter> pWriter(new OBFastSearchWriter());
+    pWriter->ofs.open(index_path.c_str(), std::ios::out | std::ios::binary);
+    if (!pWriter->ofs) {
+        std::stringstream errorMsg;
+        errorMsg << "Cannot open " << index_path << " for writing" << std::endl;
+        obErrorLog.ThrowError(__FUNCTION__, errorMsg.str(), obError);
+        return std::unique_ptr<OBFastSearchWriter>(nullptr);
+    }
+    std::string datafile(datafile_name);
+    std::string fpid(fp_name);
+    pWriter->indexer.reset(new FastSearchIndexer(datafile, &pWriter->ofs, fpid, nbits));
+    return pWriter;
+}
+
+bool OBFastSearchWriter_add(const std::unique_ptr<OBFastSearchWriter> & pWriter, const std::unique_ptr<OBMol> & pMol, u_int64_t seekpos) {
+    return pWriter->indexer->Add(pMol.get(), std::streampos(static_cast<std::streamoff>(seekpos)));
+}
+
+bool OBFastSearchWriter_finish(const std::unique_ptr<OBFastSearchWriter> & pWriter) {
+    pWriter->indexer.reset();  // ~FastSearchIndexer writes the header, fingerprints and seek positions
+    pWriter->ofs.close();
+    return !pWriter->ofs.fail();
+}
+
+std::unique_ptr<OBFastSearchIndex> OBFastSearchIndex_open(const std::string &index_path) {
+    std::unique_ptr<OBFastSearchIndex> pIndex(new OBFastSearchIndex());
+    pIndex->datafile_name = pIndex->fs.ReadIndexFile(index_path);  // logs why on failure
+    if (pIndex->datafile_name.empty()) return std::unique_ptr<OBFastSearchIndex>(nullptr);
+    return pIndex;
+}
+
+rust::String OBFastSearchIndex_datafile_name(const std::unique_ptr<OBFastSearchIndex> & pIndex) {
+    return rust::String(pIndex->datafile_name);
+}
+
+rust::String OBFastSearchIndex_fp_name(const std::unique_ptr<OBFastSearchIndex> & pIndex) {
+    const FptIndexHeader & header = pIndex->fs.GetIndexHeader();
+    return rust::String(std::string(header.fpid, strnlen(header.fpid, sizeof(header.fpid))));
+}
+
+u_int32_t OBFastSearchIndex_nbits(const std::unique_ptr<OBFastSearchIndex> & pIndex) {
+    return pIndex->fs.GetIndexHeader().words * OBFingerprint::Getbitsperint();
+}
+
+rust::Vec<u_int64_t> OBFastSearchIndex_seek_positions(const std::unique_ptr<OBFastSearchIndex> & pIndex) {
+    // FastSearch keeps its seek positions private. The empty molecule has no fingerprint bit set,
+    // so every record passes the screen and Find returns them all, in index order.
+    const FptIndexHeader & header = pIndex->fs.GetIndexHeader();
+    std::vector<unsigned long> positions;
+    if (header.nEntries > 0) {
+        OBMol empty;
+        pIndex->fs.Find(&empty, positions, header.nEntries);
+    }
+    rust::Vec<u_int64_t> result {};
+    for (auto pos : positions) result.push_back(pos);
+    return result;
+}
+
+rust::Vec<u_int64_t> OBFastSearchIndex_find(const std::unique_ptr<OBFastSearchIndex> & pIndex, const std::unique_ptr<OBMol> & pMol, u_int32_t max_candidates) {
+    std::vector<unsigned long> positions;
+    pIndex->fs.Find(pMol.get(), positions, max_candidates);
+    rust::Vec<u_int64_t> result {};
+    for (auto pos : positions) result.push_back(pos);
+    return result;
+}
+
+// std::multimap is ordered by increasing Tanimoto, the hits are returned best first
+static rust::Vec<OBFastSearchHit> fast_search_hits(const std::multimap<double, unsigned long> & seekpos_map) {
+    rust::Vec<OBFastSearchHit> result {};
+    for (auto it = seekpos_map.rbegin(); it != seekpos_map.rend(); ++it) {
+        result.push_back(OBFastSearchHit { it->second, it->first });
+    }
+    return result;
+}
+
+rust::Vec<OBFastSearchHit> OBFastSearchIndex_find_similar(const std::unique_ptr<OBFastSearchIndex> & pIndex, const std::unique_ptr<OBMol> & pMol, double min_tanimoto) {
+    std::multimap<double, unsigned long> seekpos_map;
+    pIndex->fs.FindSimilar(pMol.get(), seekpos_map, min_tanimoto);
+    return fast_search_hits(seekpos_map);
+}
+
+rust::Vec<OBFastSearchHit> OBFastSearchIndex_find_most_similar(const std::unique_ptr<OBFastSearchIndex> & pIndex, const std::unique_ptr<OBMol> & pMol, u_int32_t count) {
+    std::multimap<double, unsigned long> seekpos_map;
+    pIndex->fs.FindSimilar(pMol.get(), seekpos_map, static_cast<int>(count));
+    return fast_search_hits(seekpos_map);
+}
+
+// FastSearch - End
+
 
 // OBSmartsPattern
 
 std::unique_ptr<OBSmartsPattern> OBSmartsPattern_from_smarts(const std::string &smarts) {
     std::unique_ptr<OBSmartsPattern> pSP(new OBSmartsPattern());
-    pSP->Init(smarts);
+    if (!pSP->Init(smarts)) {
+        std::stringstream errorMsg;
+        errorMsg << "OBSmartsPattern::Init(" << smarts << ") error" << std::endl;
+        obErrorLog.ThrowError(__FUNCTION__, errorMsg.str(), obError);
+    }
     return pSP;
 }
 
+bool OBSmartsPattern_is_valid(const std::unique_ptr<OBSmartsPattern> & pSP) { return pSP->IsValid(); }
+
 unsigned int OBSmartsPattern_num_atoms(const std::unique_ptr<OBSmartsPattern> & pSP) { return pSP->NumAtoms(); }
 unsigned int OBSmartsPattern_num_bonds(const std::unique_ptr<OBSmartsPattern> & pSP) { return pSP->NumBonds(); }
-unsigned int OBSmartsPattern_num_matches(const std::unique_ptr<OBSmartsPattern> & pSP) { return pSP->NumMatches(); }
+bool OBSmartsPattern_has_match(const std::unique_ptr<OBSmartsPattern> & pSP, const std::unique_ptr<OBMol> & pMol) {
+    return pSP->HasMatch(*pMol);
+}
 
-std::unique_ptr<std::vector<int>> OBSmartsPattern_match(const std::unique_ptr<OBSmartsPattern> & pSP, const std::unique_ptr<OBMol> & pMol) {
-    pSP->Match(*pMol);
-    std::vector<int> result {};
-    for (std::vector<std::vector<int>>::iterator i = pSP->GetMapList().begin(); i != pSP->GetMapList().end(); ++i) {
-        result.insert(result.end(), i->begin(), i->end());
-        result.push_back(-1);  // CxxVector does not support nested C++ vector (std::vector<std::vector>), use -1 as delimiter
+// match_type follows OBSmartsPattern::MatchType (0 = All, 1 = Single, 2 = AllUnique), max_matches = 0 for no limit
+OBSmartsMatches OBSmartsPattern_match(const std::unique_ptr<OBSmartsPattern> & pSP, const std::unique_ptr<OBMol> & pMol, u_int32_t match_type, u_int32_t max_matches) {
+    std::vector<std::vector<int>> mlist {};
+    pSP->Match(*pMol, mlist, static_cast<OBSmartsPattern::MatchType>(match_type));  // const overload, the pattern keeps no match list
+    if (max_matches > 0 && mlist.size() > max_matches) {
+        mlist.resize(max_matches);
+    }
+    OBSmartsMatches result {};
+    result.atoms.reserve(mlist.size() * pSP->NumAtoms());
+    result.offsets.reserve(mlist.size() + 1);
+    result.offsets.push_back(0);
+    for (std::vector<std::vector<int>>::iterator i = mlist.begin(); i != mlist.end(); ++i) {
+        for (std::vector<int>::iterator j = i->begin(); j != i->end(); ++j) {
+            result.atoms.push_back(static_cast<u_int32_t>(*j - 1));  // OBAtom::GetIdx() is 1-based
+        }
+        result.offsets.push_back(static_cast<u_int32_t>(result.atoms.size()));
     }
 
-    return std::make_unique<std::vector<int>>(std::move(result));
+    return result;
 }
 
 // OBSmartsPattern - End
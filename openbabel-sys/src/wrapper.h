@@ -1,20 +1,115 @@
 #pragma once
 #include <memory>
+#include <istream>
+#include <ostream>
+#include <fstream>
 #include "rust/cxx.h"
 #include <openbabel/mol.h>
 #include <openbabel/parsmart.h>
 #include <openbabel/obconversion.h>
 #include <openbabel/forcefield.h>
+#include <openbabel/data.h>
+#include <openbabel/fingerprint.h>
+#ifdef OPENBABEL_SYS_CONFORMERSEARCH
+#include <openbabel/conformersearch.h>
+#endif
 
 namespace OpenBabel {
     class OBMol;
     class OBSmartsPattern;
     class OBConversion;
     class OBForceField;
+    class OBFFConstraints;
+    struct OBLogMessage;
+    struct OBFastSearchHit;
+    struct OBSmartsMatches;
+    struct OBFFEnergyTerms;
+    struct RustReader;
+
+    // Adapts a Rust std::io::Read (RustReader) to an input std::streambuf
+    class RustInStreamBuf : public std::streambuf {
+    public:
+        explicit RustInStreamBuf(rust::Box<RustReader> source);
+    protected:
+        int_type underflow() override;
+        pos_type seekoff(off_type off, std::ios_base::seekdir dir, std::ios_base::openmode which) override;
+    private:
+        rust::Box<RustReader> source;
+        char buffer[65536];
+        std::streamoff consumed;  // bytes before eback()
+    };
+
+    struct RustWriter;
+
+    // Adapts a Rust std::io::Write (RustWriter) to an output std::streambuf
+    class RustOutStreamBuf : public std::streambuf {
+    public:
+        explicit RustOutStreamBuf(rust::Box<RustWriter> sink);
+        ~RustOutStreamBuf();
+    protected:
+        int_type overflow(int_type ch) override;
+        int sync() override;
+    private:
+        bool write_buffer();
+        rust::Box<RustWriter> sink;
+        char buffer[65536];
+    };
+
+    // OBConversion reading successive records from a persistent stream
+    class OBStreamReader {
+    public:
+        OBStreamReader(rust::Box<RustReader> source);
+        RustInStreamBuf buf;
+        std::istream stream;
+        OBConversion conv;
+    };
+
+    // OBConversion writing successive records to a persistent stream
+    class OBStreamWriter {
+    public:
+        OBStreamWriter(rust::Box<RustWriter> sink, const std::string &format);
+        RustOutStreamBuf buf;
+        std::ostream stream;
+        OBConversion conv;
+        std::string format;
+    };
+
+    // OBConformerSearch set up for a molecule, searched by a later call
+    class OBConformerSearchRun {
+    public:
+#ifdef OPENBABEL_SYS_CONFORMERSEARCH
+        OBConformerSearch search;
+#endif
+    };
+
+    // FastSearch index (.fs) being built, written out when the indexer is destroyed
+    class OBFastSearchWriter {
+    public:
+        std::ofstream ofs;  // declared first, so that it outlives the indexer
+        std::unique_ptr<FastSearchIndexer> indexer;
+    };
+
+    // FastSearch index (.fs) opened for queries
+    class OBFastSearchIndex {
+    public:
+        FastSearch fs;
+        std::string datafile_name;
+    };
 
     // Debug
     void print_global_instances();
 
+    // Data files
+    rust::String OBDatafile_locate(const std::string &file_name);
+
+    // OBMessageHandler
+    rust::Vec<OBLogMessage> OBMessageHandler_get_messages();
+    void OBMessageHandler_clear_log();
+    void OBMessageHandler_set_output_level(int32_t level);
+    void OBMessageHandler_set_max_log_entries(u_int32_t max);
+    unsigned int OBMessageHandler_get_error_message_count();
+    unsigned int OBMessageHandler_get_warning_message_count();
+
     // OBConversion
     // std::unique_ptr<OBMol> OBConversion_smi_to_mol(const std::string &smiles);
     std::unique_ptr<OBConversion> OBConversion_new();
@@ -27,6 +122,22 @@ namespace OpenBabel {
     bool OBConversion_write_file(const std::unique_ptr<OBConversion> & pConv, const std::unique_ptr<OBMol> & pMol, const std::string &input_path);
     rust::Vec<rust::String> OBConversion_get_supported_input_format();
     rust::Vec<rust::String> OBConversion_get_supported_output_format();
+    void OBConversion_add_option(const std::unique_ptr<OBConversion> & pConv, const std::string &name, u_int32_t opt_type, const std::string &value);
+    bool OBConversion_remove_option(const std::unique_ptr<OBConversion> & pConv, const std::string &name, u_int32_t opt_type);
+    bool OBConversion_is_option(const std::unique_ptr<OBConversion> & pConv, const std::string &name, u_int32_t opt_type);
+
+    // OBStreamReader
+    std::unique_ptr<OBStreamReader> OBStreamReader_new(rust::Box<RustReader> source, const std::string &input_format);
+    int OBStreamReader_read(const std::unique_ptr<OBStreamReader> & pReader, const std::unique_ptr<OBMol> & pMol);
+    u_int64_t OBStreamReader_tell(const std::unique_ptr<OBStreamReader> & pReader);
+    void OBStreamReader_add_option(const std::unique_ptr<OBStreamReader> & pReader, const std::string &name, u_int32_t opt_type, const std::string &value);
+
+    // OBStreamWriter
+    std::unique_ptr<OBStreamWriter> OBStreamWriter_new(rust::Box<RustWriter> sink, const std::string &output_format);
+    int OBStreamWriter_write(const std::unique_ptr<OBStreamWriter> & pWriter, const std::unique_ptr<OBMol> & pMol);
+    bool OBStreamWriter_finish(const std::unique_ptr<OBStreamWriter> & pWriter);
+    bool OBStreamWriter_flush(const std::unique_ptr<OBStreamWriter> & pWriter);
+    void OBStreamWriter_add_option(const std::unique_ptr<OBStreamWriter> & pWriter, const std::string &name, u_int32_t opt_type, const std::string &value);
 
 
     // OBForceField
@@ -41,25 +152,124 @@ namespace OpenBabel {
     bool OBForceField_steepest_descent_take_n_steps(const std::unique_ptr<OBForceField> & pFF, u_int32_t n);
     double OBForceField_energy(const std::unique_ptr<OBForceField> & pFF);
     bool OBForceField_is_setup_needed(const std::unique_ptr<OBForceField> & pFF, const std::unique_ptr<OBMol> & pMol);
+    bool OBForceField_is_valid_setup(const std::unique_ptr<OBForceField> & pFF);
+    rust::Vec<rust::String> OBForceField_atom_types(const std::unique_ptr<OBForceField> & pFF, const std::unique_ptr<OBMol> & pMol);
+    OBFFEnergyTerms OBForceField_energy_terms(const std::unique_ptr<OBForceField> & pFF);
+    rust::Vec<double> OBForceField_gradients(const std::unique_ptr<OBForceField> & pFF);
+    bool OBForceField_validate_gradients(const std::unique_ptr<OBForceField> & pFF);
+    rust::String OBForceField_get_unit(const std::unique_ptr<OBForceField> & pFF);
+    bool OBForceField_get_coordinates(const std::unique_ptr<OBForceField> & pFF, const std::unique_ptr<OBMol> & pMol);
+    unsigned int OBForceField_setup_with_constraints(const std::unique_ptr<OBMol> & pMol, const std::unique_ptr<OBForceField> & pFF, const std::unique_ptr<OBFFConstraints> & pConstraints);
+    bool OBForceField_set_coordinates(const std::unique_ptr<OBForceField> & pFF, const std::unique_ptr<OBMol> & pMol);
+    bool OBForceField_get_conformers(const std::unique_ptr<OBForceField> & pFF, const std::unique_ptr<OBMol> & pMol);
+    bool OBForceField_systematic_rotor_search_initialize(const std::unique_ptr<OBForceField> & pFF, u_int32_t geom_steps);
+    bool OBForceField_systematic_rotor_search_next_conformer(const std::unique_ptr<OBForceField> & pFF, u_int32_t geom_steps);
+    void OBForceField_random_rotor_search_initialize(const std::unique_ptr<OBForceField> & pFF, u_int32_t conformers, u_int32_t geom_steps);
+    bool OBForceField_random_rotor_search_next_conformer(const std::unique_ptr<OBForceField> & pFF, u_int32_t geom_steps);
+
+    // OBFFConstraints
+    std::unique_ptr<OBFFConstraints> OBFFConstraints_new();
+    void OBFFConstraints_set_factor(const std::unique_ptr<OBFFConstraints> & pConstraints, double factor);
+    void OBFFConstraints_add_ignore(const std::unique_ptr<OBFFConstraints> & pConstraints, u_int32_t a);
+    void OBFFConstraints_add_atom_constraint(const std::unique_ptr<OBFFConstraints> & pConstraints, u_int32_t a);
+    void OBFFConstraints_add_atom_x_constraint(const std::unique_ptr<OBFFConstraints> & pConstraints, u_int32_t a);
+    void OBFFConstraints_add_atom_y_constraint(const std::unique_ptr<OBFFConstraints> & pConstraints, u_int32_t a);
+    void OBFFConstraints_add_atom_z_constraint(const std::unique_ptr<OBFFConstraints> & pConstraints, u_int32_t a);
+    void OBFFConstraints_add_distance_constraint(const std::unique_ptr<OBFFConstraints> & pConstraints, u_int32_t a, u_int32_t b, double length);
+    void OBFFConstraints_add_angle_constraint(const std::unique_ptr<OBFFConstraints> & pConstraints, u_int32_t a, u_int32_t b, u_int32_t c, double angle);
+    void OBFFConstraints_add_torsion_constraint(const std::unique_ptr<OBFFConstraints> & pConstraints, u_int32_t a, u_int32_t b, u_int32_t c, u_int32_t d, double torsion);
+    unsigned int OBFFConstraints_size(const std::unique_ptr<OBFFConstraints> & pConstraints);
+
+    // OBConformerSearch
+    std::unique_ptr<OBConformerSearchRun> OBConformerSearch_setup(const std::unique_ptr<OBMol> & pMol, u_int32_t num_conformers, u_int32_t num_children, u_int32_t mutability, u_int32_t convergence);
+    void OBConformerSearch_search(const std::unique_ptr<OBConformerSearchRun> & pRun, const std::unique_ptr<OBMol> & pMol);
 
     // OBMol
     std::unique_ptr<OBMol> OBMol_new();
     std::unique_ptr<OBMol> OBMol_from_smiles(const std::string &smiles);
+    std::unique_ptr<OBMol> OBMol_clone(const std::unique_ptr<OBMol> & pMol);
     unsigned int OBMol_num_atoms(const std::unique_ptr<OBMol> & pMol);
     unsigned int OBMol_num_bonds(const std::unique_ptr<OBMol> & pMol);
     unsigned int OBMol_num_hvy_atoms(const std::unique_ptr<OBMol> & pMol);
     double OBMol_get_mol_wt(const std::unique_ptr<OBMol> & pMol);
 
+    // Coordinates, flattened as x, y, z per atom
+    rust::Vec<double> OBMol_get_coordinates(const std::unique_ptr<OBMol> & pMol);
+    void OBMol_set_coordinates(const std::unique_ptr<OBMol> & pMol, rust::Slice<const double> coords);
+    rust::Vec<double> OBMol_get_cell_vectors(const std::unique_ptr<OBMol> & pMol);
+    unsigned int OBMol_get_dimension(const std::unique_ptr<OBMol> & pMol);
+    void OBMol_set_dimension(const std::unique_ptr<OBMol> & pMol, u_int32_t dim);
+    void OBMol_center(const std::unique_ptr<OBMol> & pMol);
+    void OBMol_translate(const std::unique_ptr<OBMol> & pMol, double x, double y, double z);
+    void OBMol_rotate(const std::unique_ptr<OBMol> & pMol, rust::Slice<const double> matrix);
+
+    // Conformers, flattened as the coordinates
+    unsigned int OBMol_num_conformers(const std::unique_ptr<OBMol> & pMol);
+    rust::Vec<double> OBMol_get_conformer(const std::unique_ptr<OBMol> & pMol, u_int32_t idx);
+    void OBMol_set_conformers(const std::unique_ptr<OBMol> & pMol, rust::Slice<const double> coords);
+    void OBMol_set_conformer(const std::unique_ptr<OBMol> & pMol, u_int32_t idx);
+    rust::Vec<double> OBMol_get_energies(const std::unique_ptr<OBMol> & pMol);
+    void OBMol_set_energies(const std::unique_ptr<OBMol> & pMol, rust::Slice<const double> energies);
+
+    // Editing, each call is wrapped in OBMol::BeginModify/EndModify
+    unsigned int OBMol_add_atom(const std::unique_ptr<OBMol> & pMol, u_int32_t atomic_num);
+    bool OBMol_add_bond(const std::unique_ptr<OBMol> & pMol, u_int32_t begin_idx, u_int32_t end_idx, u_int32_t order);
+    bool OBMol_delete_atom(const std::unique_ptr<OBMol> & pMol, u_int32_t idx);
+    bool OBMol_delete_bond(const std::unique_ptr<OBMol> & pMol, u_int32_t idx);
+    rust::Vec<u_int32_t> OBMol_get_symmetry_classes(const std::unique_ptr<OBMol> & pMol);
+    rust::Vec<u_int32_t> OBMol_get_canonical_labels(const std::unique_ptr<OBMol> & pMol);
+    bool OBMol_is_perceived(const std::unique_ptr<OBMol> & pMol);
+    void OBMol_perceive(const std::unique_ptr<OBMol> & pMol);
+    bool OBMol_has_substructure(const std::unique_ptr<OBMol> & pMol, const std::unique_ptr<OBMol> & pQuery);
+
+    // OBAtom, addressed by its 1-based index in the parent OBMol
+    unsigned int OBAtom_get_atomic_num(const std::unique_ptr<OBMol> & pMol, u_int32_t idx);
+    unsigned int OBAtom_get_isotope(const std::unique_ptr<OBMol> & pMol, u_int32_t idx);
+    int OBAtom_get_formal_charge(const std::unique_ptr<OBMol> & pMol, u_int32_t idx);
+    unsigned int OBAtom_get_implicit_h_count(const std::unique_ptr<OBMol> & pMol, u_int32_t idx);
+    unsigned int OBAtom_get_hyb(const std::unique_ptr<OBMol> & pMol, u_int32_t idx);
+    bool OBAtom_is_aromatic(const std::unique_ptr<OBMol> & pMol, u_int32_t idx);
+    rust::Vec<double> OBAtom_get_vector(const std::unique_ptr<OBMol> & pMol, u_int32_t idx);
+    bool OBAtom_is_in_ring(const std::unique_ptr<OBMol> & pMol, u_int32_t idx);
+    unsigned int OBAtom_get_explicit_degree(const std::unique_ptr<OBMol> & pMol, u_int32_t idx);
+    rust::Vec<u_int32_t> OBAtom_get_neighbors(const std::unique_ptr<OBMol> & pMol, u_int32_t idx);
+    rust::Vec<u_int32_t> OBAtom_get_bonds(const std::unique_ptr<OBMol> & pMol, u_int32_t idx);
+    void OBAtom_set_formal_charge(const std::unique_ptr<OBMol> & pMol, u_int32_t idx, int charge);
+    void OBAtom_set_isotope(const std::unique_ptr<OBMol> & pMol, u_int32_t idx, u_int32_t isotope);
+    void OBAtom_set_implicit_h_count(const std::unique_ptr<OBMol> & pMol, u_int32_t idx, u_int32_t count);
+
+    // OBBond, addressed by its 0-based index in the parent OBMol
+    unsigned int OBBond_get_bond_order(const std::unique_ptr<OBMol> & pMol, u_int32_t idx);
+    unsigned int OBBond_get_begin_atom_idx(const std::unique_ptr<OBMol> & pMol, u_int32_t idx);
+    unsigned int OBBond_get_end_atom_idx(const std::unique_ptr<OBMol> & pMol, u_int32_t idx);
+    bool OBBond_is_aromatic(const std::unique_ptr<OBMol> & pMol, u_int32_t idx);
+
     // OBFingerprint
     typedef std::vector<unsigned int> FPData;
+    bool OBFingerprint_find_fingerprint(const std::string &fp_name);
     std::unique_ptr<FPData> OBFingerprint_get_fingerprint(const std::string &fp_name, const std::unique_ptr<OBMol> & pMol, u_int32_t nbits);
+    double OBFingerprint_tanimoto(const rust::Vec<u_int32_t> & fp1, const rust::Vec<u_int32_t> & fp2);
     // std::unique_ptr<FPData> OBFingerprint_get_fingerprint_in_batch(const std::string &fp_thread_name, const rust::Vec<rust::String> & smiles_vec, u_int32_t nbits);
     // deprecated: slow performance, root cause to be identified
 
+    // FastSearch
+    std::unique_ptr<OBFastSearchWriter> OBFastSearchWriter_new(const std::string &index_path, const std::string &datafile_name, const std::string &fp_name, u_int32_t nbits);
+    bool OBFastSearchWriter_add(const std::unique_ptr<OBFastSearchWriter> & pWriter, const std::unique_ptr<OBMol> & pMol, u_int64_t seekpos);
+    bool OBFastSearchWriter_finish(const std::unique_ptr<OBFastSearchWriter> & pWriter);
+    std::unique_ptr<OBFastSearchIndex> OBFastSearchIndex_open(const std::string &index_path);
+    rust::String OBFastSearchIndex_datafile_name(const std::unique_ptr<OBFastSearchIndex> & pIndex);
+    rust::String OBFastSearchIndex_fp_name(const std::unique_ptr<OBFastSearchIndex> & pIndex);
+    u_int32_t OBFastSearchIndex_nbits(const std::unique_ptr<OBFastSearchIndex> & pIndex);
+    rust::Vec<u_int64_t> OBFastSearchIndex_seek_positions(const std::unique_ptr<OBFastSearchIndex> & pIndex);
+    rust::Vec<u_int64_t> OBFastSearchIndex_find(const std::unique_ptr<OBFastSearchIndex> & pIndex, const std::unique_ptr<OBMol> & pMol, u_int32_t max_candidates);
+    rust::Vec<OBFastSearchHit> OBFastSearchIndex_find_similar(const std::unique_ptr<OBFastSearchIndex> & pIndex, const std::unique_ptr<OBMol> & pMol, double min_tanimoto);
+    rust::Vec<OBFastSearchHit> OBFastSearchIndex_find_most_similar(const std::unique_ptr<OBFastSearchIndex> & pIndex, const std::unique_ptr<OBMol> & pMol, u_int32_t count);
+
     // OBSmartsPattern
     std::unique_ptr<OBSmartsPattern> OBSmartsPattern_from_smarts(const std::string &smarts);
+    bool OBSmartsPattern_is_valid(const std::unique_ptr<OBSmartsPattern> & pSP);
     unsigned int OBSmartsPattern_num_atoms(const std::unique_ptr<OBSmartsPattern> & pSP);
     unsigned int OBSmartsPattern_num_bonds(const std::unique_ptr<OBSmartsPattern> & pSP);
-    unsigned int OBSmartsPattern_num_matches(const std::unique_ptr<OBSmartsPattern> & pSP);
-    std::unique_ptr<std::vector<int>> OBSmartsPattern_match(const std::unique_ptr<OBSmartsPattern> & pSP, const std::unique_ptr<OBMol> &pMol);
+    bool OBSmartsPattern_has_match(const std::unique_ptr<OBSmartsPattern> & pSP, const std::unique_ptr<OBMol> &pMol);
+    OBSmartsMatches OBSmartsPattern_match(const std::unique_ptr<OBSmartsPattern> & pSP, const std::unique_ptr<OBMol> &pMol, u_int32_t match_type, u_int32_t max_matches);
 }
\ No newline at end of file
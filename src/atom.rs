@@ -0,0 +1,117 @@
+//! Openbabel Atom
+//!
+//! Read-only view of an OBAtom inside a [Molecule](crate::molecule::Molecule).
+//! Atoms are addressed by 0-based indices, i.e. OBAtom::GetIdx() - 1.
+//!
+//! # Walk over the atoms of a Molecule
+//! ```
+//! use openbabel::molecule::Molecule;
+//!
+//! let mol = Molecule::from_smiles("c1ccccc1O").unwrap();
+//! let oxygen = mol.atoms().find(|a| a.atomic_num() == 8).unwrap();
+//! assert_eq!(oxygen.idx(), 6);
+//! assert_eq!(oxygen.implicit_h_count(), 1);
+//! assert!(oxygen.neighbors().all(|a| a.is_aromatic()));
+//! ```
+
+use ob_rs::ob;
+use crate::bond::Bond;
+use crate::molecule::Molecule;
+
+#[derive(Clone, Copy)]
+pub struct Atom<'a> {
+    mol: &'a Molecule,
+    idx: usize,
+}
+
+impl<'a> Atom<'a> {
+    pub(crate) fn new(mol: &'a Molecule, idx: usize) -> Self {
+        Self { mol, idx }
+    }
+
+    fn ob_idx(&self) -> u32 {
+        self.idx as u32 + 1
+    }
+
+    /// 0-based index of the atom in its molecule
+    pub fn idx(&self) -> usize { self.idx }
+    pub fn atomic_num(&self) -> u32 { ob::OBAtom_get_atomic_num(&self.mol.ob_mol, self.ob_idx()) }
+    /// Mass number, 0 if no isotope was specified
+    pub fn isotope(&self) -> u32 { ob::OBAtom_get_isotope(&self.mol.ob_mol, self.ob_idx()) }
+    pub fn formal_charge(&self) -> i32 { ob::OBAtom_get_formal_charge(&self.mol.ob_mol, self.ob_idx()) }
+    pub fn implicit_h_count(&self) -> u32 { ob::OBAtom_get_implicit_h_count(&self.mol.ob_mol, self.ob_idx()) }
+    /// Hybridization as reported by OBAtom::GetHyb: 1 for sp, 2 for sp2, 3 for sp3, 0 if unknown
+    pub fn hybridization(&self) -> u32 {
+        self.mol.perceive();
+        ob::OBAtom_get_hyb(&self.mol.ob_mol, self.ob_idx())
+    }
+    pub fn is_aromatic(&self) -> bool {
+        self.mol.perceive();
+        ob::OBAtom_is_aromatic(&self.mol.ob_mol, self.ob_idx())
+    }
+    pub fn is_in_ring(&self) -> bool {
+        self.mol.perceive();
+        ob::OBAtom_is_in_ring(&self.mol.ob_mol, self.ob_idx())
+    }
+    /// Number of explicit bonds, implicit hydrogens are not counted
+    pub fn degree(&self) -> u32 { ob::OBAtom_get_explicit_degree(&self.mol.ob_mol, self.ob_idx()) }
+
+    /// Cartesian coordinates, all zero for molecules without coordinates
+    pub fn position(&self) -> [f64; 3] {
+        let xyz = ob::OBAtom_get_vector(&self.mol.ob_mol, self.ob_idx());
+        [xyz[0], xyz[1], xyz[2]]
+    }
+
+    pub fn neighbors(&self) -> impl Iterator<Item = Atom<'a>> {
+        let mol = self.mol;
+        ob::OBAtom_get_neighbors(&mol.ob_mol, self.ob_idx())
+            .into_iter()
+            .map(move |ob_idx| Atom::new(mol, ob_idx as usize - 1))
+    }
+
+    pub fn bonds(&self) -> impl Iterator<Item = Bond<'a>> {
+        let mol = self.mol;
+        ob::OBAtom_get_bonds(&mol.ob_mol, self.ob_idx())
+            .into_iter()
+            .map(move |idx| Bond::new(mol, idx as usize))
+    }
+}
+
+impl std::fmt::Debug for Atom<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Atom")
+            .field("idx", &self.idx)
+            .field("atomic_num", &self.atomic_num())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test_mod_atom {
+    use super::*;
+
+    #[test]
+    fn test_atom() {
+        let mol = Molecule::from_smiles("c1ccccc1[15NH3+]").unwrap();
+        let n = mol.atom(6).unwrap();
+        assert_eq!(n.atomic_num(), 7);
+        assert_eq!(n.isotope(), 15);
+        assert_eq!(n.formal_charge(), 1);
+        assert_eq!(n.implicit_h_count(), 3);
+        assert_eq!(n.hybridization(), 3);
+        assert!(!n.is_aromatic());
+        assert!(!n.is_in_ring());
+        assert_eq!(n.degree(), 1);
+        assert_eq!(n.neighbors().map(|a| a.idx()).collect::<Vec<usize>>(), vec![5]);
+
+        let c = mol.atom(5).unwrap();
+        assert!(c.is_aromatic());
+        assert!(c.is_in_ring());
+        assert_eq!(c.degree(), 3);
+        assert_eq!(c.hybridization(), 2);
+        let mut nbrs: Vec<usize> = c.neighbors().map(|a| a.idx()).collect();
+        nbrs.sort();
+        assert_eq!(nbrs, vec![0, 4, 6]);
+        assert_eq!(c.bonds().count(), 3);
+    }
+}
@@ -0,0 +1,72 @@
+//! Openbabel Bond
+//!
+//! Read-only view of an OBBond inside a [Molecule](crate::molecule::Molecule).
+//! Bonds are addressed by their 0-based OBBond::GetIdx().
+//!
+//! ```
+//! use openbabel::molecule::Molecule;
+//!
+//! let mol = Molecule::from_smiles("C=CC#N").unwrap();
+//! let orders: Vec<u32> = mol.bonds().map(|b| b.order()).collect();
+//! assert_eq!(orders, vec![2, 1, 3]);
+//! ```
+
+use ob_rs::ob;
+use crate::atom::Atom;
+use crate::molecule::Molecule;
+
+#[derive(Clone, Copy)]
+pub struct Bond<'a> {
+    mol: &'a Molecule,
+    idx: usize,
+}
+
+impl<'a> Bond<'a> {
+    pub(crate) fn new(mol: &'a Molecule, idx: usize) -> Self {
+        Self { mol, idx }
+    }
+
+    /// 0-based index of the bond in its molecule
+    pub fn idx(&self) -> usize { self.idx }
+    /// 1, 2 or 3; aromatic bonds report their Kekulé order
+    pub fn order(&self) -> u32 { ob::OBBond_get_bond_order(&self.mol.ob_mol, self.idx as u32) }
+    pub fn is_aromatic(&self) -> bool {
+        self.mol.perceive();
+        ob::OBBond_is_aromatic(&self.mol.ob_mol, self.idx as u32)
+    }
+    /// 0-based index of the begin atom
+    pub fn begin_atom_idx(&self) -> usize { ob::OBBond_get_begin_atom_idx(&self.mol.ob_mol, self.idx as u32) as usize - 1 }
+    /// 0-based index of the end atom
+    pub fn end_atom_idx(&self) -> usize { ob::OBBond_get_end_atom_idx(&self.mol.ob_mol, self.idx as u32) as usize - 1 }
+
+    pub fn begin_atom(&self) -> Atom<'a> { Atom::new(self.mol, self.begin_atom_idx()) }
+    pub fn end_atom(&self) -> Atom<'a> { Atom::new(self.mol, self.end_atom_idx()) }
+}
+
+impl std::fmt::Debug for Bond<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Bond")
+            .field("idx", &self.idx)
+            .field("begin_atom_idx", &self.begin_atom_idx())
+            .field("end_atom_idx", &self.end_atom_idx())
+            .field("order", &self.order())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test_mod_bond {
+    use super::*;
+
+    #[test]
+    fn test_bond() {
+        let mol = Molecule::from_smiles("c1ccccc1C=O").unwrap();
+        assert!(mol.bonds().filter(|b| b.is_aromatic()).count() == 6);
+        let carbonyl = mol.bonds().find(|b| b.end_atom().atomic_num() == 8).unwrap();
+        assert_eq!(carbonyl.order(), 2);
+        assert!(!carbonyl.is_aromatic());
+        assert_eq!(carbonyl.begin_atom_idx(), 6);
+        assert_eq!(carbonyl.end_atom_idx(), 7);
+        assert!(mol.bond(mol.num_bonds() as usize).is_none());
+    }
+}
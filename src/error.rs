@@ -0,0 +1,92 @@
+//! Openbabel Error
+//!
+//! Crate-wide error type. Every fallible constructor and conversion returns
+//! [Result](crate::Result), with the message reported by OpenBabel attached
+//! where one is available.
+//!
+//! ```
+//! use openbabel::molecule::Molecule;
+//! use openbabel::Error;
+//!
+//! match Molecule::from_smiles("c1ccccc") {
+//!     Err(Error::Parse { format, .. }) => assert_eq!(format, "smi"),
+//!     _ => unreachable!(),
+//! }
+//! ```
+
+use std::fmt;
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The input could not be read in the given format
+    Parse { format: String, message: String },
+    /// A record of a multi-record input or output could not be read or written,
+    /// `offset` is its position in bytes in the input (0 for output)
+    Record { index: usize, offset: u64, format: String, message: String },
+    /// The format is not known to (or not compiled into) OpenBabel
+    UnknownFormat(String),
+    /// A plugin (fingerprint, force field, ...) could not be found or set up
+    Setup(String),
+    /// Reading or writing a file failed
+    Io(std::io::Error),
+    /// A data file required by OpenBabel could not be located, see BABEL_DATADIR
+    MissingDataFile(String),
+    /// OpenBabel rejected a change to a molecule
+    InvalidEdit(String),
+    /// The molecule could not be written in the given format
+    Write { format: String, message: String },
+    /// A fingerprint of another kind or length than the others it is compared to
+    IncompatibleFingerprint(String),
+    /// A force field could not be set up for a molecule; `untyped_atoms` holds the 0-based
+    /// indices of the atoms it has no type for, known for MMFF94 and MMFF94s only
+    ForceFieldSetup { force_field: String, untyped_atoms: Vec<usize>, message: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse { format, message } => write!(f, "unable to parse {} input: {}", format, message),
+            Error::Record { index, offset, format, message } => write!(f, "{} record {} at byte {}: {}", format, index, offset, message),
+            Error::UnknownFormat(format) => write!(f, "unknown format: {}", format),
+            Error::Setup(message) => write!(f, "setup failed: {}", message),
+            Error::Io(err) => write!(f, "I/O error: {}", err),
+            Error::MissingDataFile(file_name) => write!(f, "data file {} not found, check BABEL_DATADIR", file_name),
+            Error::InvalidEdit(message) => write!(f, "invalid edit: {}", message),
+            Error::Write { format, message } => write!(f, "unable to write {} output: {}", format, message),
+            Error::IncompatibleFingerprint(message) => write!(f, "incompatible fingerprint: {}", message),
+            Error::ForceFieldSetup { force_field, message, .. } => write!(f, "{} setup failed: {}", force_field, message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod test_mod_error {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let err = Error::Parse { format: String::from("smi"), message: String::from("unbalanced ring") };
+        assert_eq!(err.to_string(), "unable to parse smi input: unbalanced ring");
+        assert_eq!(Error::UnknownFormat(String::from("xyz2")).to_string(), "unknown format: xyz2");
+        let io_err: Error = std::io::Error::new(std::io::ErrorKind::NotFound, "no such file").into();
+        assert!(matches!(io_err, Error::Io(_)));
+    }
+}
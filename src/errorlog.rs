@@ -0,0 +1,177 @@
+//! Openbabel Error Log
+//!
+//! Wrapper of the global ob::obErrorLog (OBMessageHandler).
+//!
+//! OpenBabel reports problems by appending messages to obErrorLog and printing them to stderr.
+//! The messages can be drained into Rust after each record, and stderr output can be reduced
+//! or silenced with [set_output_level].
+//!
+//! ```
+//! use openbabel::errorlog::{self, MessageLevel};
+//! use openbabel::molecule::Molecule;
+//!
+//! errorlog::set_output_level(None);  // nothing on stderr
+//! errorlog::drain();
+//! assert!(Molecule::from_smiles("C1CC(").is_err());
+//! let messages = errorlog::drain();
+//! assert!(messages.iter().any(|m| m.level == MessageLevel::Error));
+//! ```
+//!
+//! With the `log` or `tracing` feature enabled, [forward] drains the messages into the
+//! corresponding crate instead.
+
+use ob_rs::ob;
+use crate::utils::ob_lock;
+
+/// Severity of a message, following obMessageLevel
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord)]
+pub enum MessageLevel {
+    Error = 0,
+    Warning = 1,
+    Info = 2,
+    Audit = 3,
+    Debug = 4,
+}
+
+impl MessageLevel {
+    fn from_u32(level: u32) -> Self {
+        match level {
+            0 => MessageLevel::Error,
+            1 => MessageLevel::Warning,
+            2 => MessageLevel::Info,
+            3 => MessageLevel::Audit,
+            _ => MessageLevel::Debug,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Message {
+    pub level: MessageLevel,
+    /// Name of the OpenBabel function which raised the message
+    pub method: String,
+    pub text: String,
+}
+
+impl From<ob::OBLogMessage> for Message {
+    fn from(msg: ob::OBLogMessage) -> Self {
+        Self {
+            level: MessageLevel::from_u32(msg.level),
+            method: msg.method,
+            text: msg.text.trim_end().to_string(),
+        }
+    }
+}
+
+/// Returns all messages logged since the last drain and clears the log.
+pub fn drain() -> Vec<Message> {
+    let _lock = ob_lock();
+    let messages = ob::OBMessageHandler_get_messages();
+    ob::OBMessageHandler_clear_log();
+    messages.into_iter().map(Message::from).collect()
+}
+
+/// Sets the most verbose level printed to stderr, `None` prints nothing.
+/// Messages are recorded for [drain] regardless of this setting.
+pub fn set_output_level(level: Option<MessageLevel>) {
+    let _lock = ob_lock();
+    ob::OBMessageHandler_set_output_level(level.map_or(-1, |l| l as i32));
+}
+
+/// Sets how many messages are kept between drains (OpenBabel default: 100), 0 keeps all.
+pub fn set_max_entries(max: u32) {
+    let _lock = ob_lock();
+    ob::OBMessageHandler_set_max_log_entries(max);
+}
+
+/// Counts the errors logged so far, see [errors_since].
+/// Callers hold `ob_lock` from here to `errors_since`, so that no other thread logs in between.
+pub(crate) fn error_count() -> u32 {
+    ob::OBMessageHandler_get_error_message_count()
+}
+
+/// Counts the warnings logged so far, see [warnings_since].
+#[cfg(feature = "inchi")]
+pub(crate) fn warning_count() -> u32 {
+    ob::OBMessageHandler_get_warning_message_count()
+}
+
+/// Texts of the last `n` messages of `level` in the log
+fn last_texts(level: MessageLevel, n: usize) -> Vec<String> {
+    let _lock = ob_lock();
+    let texts: Vec<String> = ob::OBMessageHandler_get_messages()
+        .into_iter()
+        .map(Message::from)
+        .filter(|m| m.level == level)
+        .map(|m| m.text)
+        .collect();
+    let first = texts.len().saturating_sub(n);
+    texts[first..].to_vec()
+}
+
+/// Joins the texts of the errors logged after `error_count()` returned `count`,
+/// without removing them from the log.
+pub(crate) fn errors_since(count: u32) -> Option<String> {
+    let n = error_count().saturating_sub(count) as usize;
+    if n == 0 {
+        return None;
+    }
+    Some(last_texts(MessageLevel::Error, n).join("; "))
+}
+
+/// Texts of the warnings logged after `warning_count()` returned `count`,
+/// without removing them from the log.
+#[cfg(feature = "inchi")]
+pub(crate) fn warnings_since(count: u32) -> Vec<String> {
+    let n = warning_count().saturating_sub(count) as usize;
+    if n == 0 {
+        return Vec::new();
+    }
+    last_texts(MessageLevel::Warning, n)
+}
+
+/// Drains the log into the `log` crate, or `tracing` if only that feature is enabled.
+#[cfg(any(feature = "log", feature = "tracing"))]
+pub fn forward() {
+    for msg in drain() {
+        #[cfg(feature = "log")]
+        {
+            let level = match msg.level {
+                MessageLevel::Error => log::Level::Error,
+                MessageLevel::Warning => log::Level::Warn,
+                MessageLevel::Info => log::Level::Info,
+                MessageLevel::Audit => log::Level::Debug,
+                MessageLevel::Debug => log::Level::Trace,
+            };
+            log::log!(target: "openbabel", level, "{}: {}", msg.method, msg.text);
+        }
+        #[cfg(all(feature = "tracing", not(feature = "log")))]
+        match msg.level {
+            MessageLevel::Error => tracing::error!(target: "openbabel", method = %msg.method, "{}", msg.text),
+            MessageLevel::Warning => tracing::warn!(target: "openbabel", method = %msg.method, "{}", msg.text),
+            MessageLevel::Info => tracing::info!(target: "openbabel", method = %msg.method, "{}", msg.text),
+            MessageLevel::Audit => tracing::debug!(target: "openbabel", method = %msg.method, "{}", msg.text),
+            MessageLevel::Debug => tracing::trace!(target: "openbabel", method = %msg.method, "{}", msg.text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_mod_errorlog {
+    use super::*;
+    use crate::molecule::Molecule;
+
+    #[test]
+    fn test_drain() {
+        let _lock = ob_lock();  // keep the messages of other test threads out
+        set_output_level(None);
+        drain();
+        let count = error_count();
+        assert!(Molecule::from_smiles("C1CC(").is_err());
+        assert!(errors_since(count).is_some());
+        let messages = drain();
+        assert!(messages.iter().any(|m| m.level == MessageLevel::Error && m.method == "OBMol_from_smiles"));
+        assert!(drain().iter().all(|m| m.method != "OBMol_from_smiles"));
+        set_output_level(Some(MessageLevel::Warning));
+    }
+}
@@ -0,0 +1,336 @@
+//! Openbabel FastSearch
+//!
+//! FastSearch index files (`.fs`) of OpenBabel: the fingerprint of every record of a
+//! multi-molecule file, used to screen substructure and similarity queries before the records
+//! themselves are read. The files are those of `obabel data.sdf -ofs` and can be queried with
+//! `obabel data.fs -s<SMILES>`, and the other way around.
+//!
+//! Records are numbered from 0 in the order of the data file. As with obabel, records which
+//! cannot be read are not indexed and do not take a number.
+//!
+//! ```no_run
+//! use openbabel::fastsearch::FastSearchIndex;
+//! use openbabel::fingerprint::Kind;
+//! use openbabel::molecule::Molecule;
+//! use openbabel::InputFormat;
+//!
+//! FastSearchIndex::build("library.sdf", InputFormat::sdf, "library.fs", Kind::FP2 { nbits: 1024 }).unwrap();
+//!
+//! let index = FastSearchIndex::open("library.fs").unwrap();
+//! let phenols = index.find_substructure(&Molecule::from_smiles("c1ccccc1O").unwrap()).unwrap();
+//! let first = index.read_record(phenols[0]).unwrap();
+//! let similar = index.find_similar(&Molecule::from_smiles("Oc1ccccc1C").unwrap(), 0.7);
+//! ```
+
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use ob_rs::ob;
+use crate::error::{Error, Result};
+use crate::fingerprint::{FingerprintGenerator, Kind};
+use crate::io::formats::InputFormat;
+use crate::io::reader::MoleculeReader;
+use crate::molecule::Molecule;
+use crate::smartspattern::SmartsPattern;
+use crate::utils::ob_lock;
+
+fn path_str(path: &Path) -> Result<&str> {
+    path.to_str()
+        .ok_or_else(|| Error::Io(std::io::Error::new(std::io::ErrorKind::InvalidInput, "path is not valid UTF-8")))
+}
+
+/// Bytes of some records of a data file, one record after the other. Each range runs from the
+/// offset of a record to the offset of the next indexed record, so it may end with records
+/// which are not indexed.
+struct RecordBytes {
+    file: BufReader<std::fs::File>,
+    ranges: std::vec::IntoIter<(u64, u64)>,
+    left: u64,
+}
+
+impl Read for RecordBytes {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            if self.left == 0 {
+                match self.ranges.next() {
+                    Some((start, end)) => {
+                        self.file.seek(SeekFrom::Start(start))?;
+                        self.left = end - start;
+                    }
+                    None => return Ok(0),
+                }
+            }
+            let len = self.left.min(buf.len() as u64) as usize;
+            match self.file.read(&mut buf[..len])? {
+                0 => self.left = 0,  // end of the file
+                n => {
+                    self.left -= n as u64;
+                    return Ok(n);
+                }
+            }
+        }
+    }
+}
+
+pub struct FastSearchIndex {
+    ob_index: cxx::UniquePtr<ob::OBFastSearchIndex>,
+    kind: Kind,
+    datafile: PathBuf,
+    format: InputFormat,
+    /// Byte offset of each record in the data file
+    offsets: Vec<u64>,
+}
+
+impl FastSearchIndex {
+    /// Indexes every record of `datafile` into `index_path`, conventionally the data file with
+    /// the extension `.fs`, and returns the number of records indexed. Substructure screening
+    /// needs a path-based fingerprint (FP2, FP3 or FP4); obabel uses FP2.
+    pub fn build<P: AsRef<Path>, Q: AsRef<Path>>(datafile: P, format: InputFormat, index_path: Q, kind: Kind) -> Result<usize> {
+        if !matches!(kind, Kind::FP2 { .. } | Kind::FP3 { .. } | Kind::FP4 { .. }) {
+            return Err(Error::Setup(format!("{} cannot screen substructures, use FP2, FP3 or FP4", kind.as_str())));
+        }
+        FingerprintGenerator::new(kind.clone())?;
+        let datafile = datafile.as_ref();
+        let index_path = index_path.as_ref();
+        // the data file is found again next to the index, as obabel does
+        let datafile_name = datafile.file_name().map(Path::new).unwrap_or(datafile);
+        cxx::let_cxx_string!(index_path_cxx = path_str(index_path)?);
+        cxx::let_cxx_string!(datafile_name_cxx = path_str(datafile_name)?);
+        cxx::let_cxx_string!(fp_name_cxx = kind.as_str());
+
+        let mut reader = MoleculeReader::from_path(datafile, format)?;
+        let writer = {
+            let _lock = ob_lock();
+            ob::OBFastSearchWriter_new(&index_path_cxx, &datafile_name_cxx, &fp_name_cxx, *kind.get_nbits())
+        };
+        if writer.is_null() {
+            return Err(Error::Io(std::io::Error::other(format!("cannot create {}", index_path.display()))));
+        }
+        let mut count = 0;
+        while let Some(result) = reader.next() {
+            match result {
+                Ok(mol) => {
+                    mol.perceive();
+                    let _fp_lock = kind.lock();
+                    if ob::OBFastSearchWriter_add(&writer, &mol.ob_mol, reader.record_offset()) {
+                        count += 1;
+                    }
+                }
+                Err(Error::Record { .. }) => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        let _lock = ob_lock();
+        if !ob::OBFastSearchWriter_finish(&writer) {
+            return Err(Error::Io(std::io::Error::other(format!("cannot write {}", index_path.display()))));
+        }
+        Ok(count)
+    }
+
+    /// Opens an index and locates its data file in the same directory. The format of the data
+    /// file is guessed from its extension.
+    pub fn open<P: AsRef<Path>>(index_path: P) -> Result<Self> {
+        let index_path = index_path.as_ref();
+        std::fs::metadata(index_path)?;
+        cxx::let_cxx_string!(index_path_cxx = path_str(index_path)?);
+        let _lock = ob_lock();
+        let error_count = crate::errorlog::error_count();
+        let ob_index = ob::OBFastSearchIndex_open(&index_path_cxx);
+        if ob_index.is_null() {
+            let message = crate::errorlog::errors_since(error_count).unwrap_or_else(|| String::from("FastSearch::ReadIndexFile failed"));
+            return Err(Error::Parse { format: String::from("fs"), message });
+        }
+        let fp_name = ob::OBFastSearchIndex_fp_name(&ob_index);
+        let kind = Kind::from_name(&fp_name, ob::OBFastSearchIndex_nbits(&ob_index))
+            .ok_or_else(|| Error::Setup(format!("unknown fingerprint {}", fp_name)))?;
+        let datafile = index_path.with_file_name(ob::OBFastSearchIndex_datafile_name(&ob_index));
+        let format = InputFormat::from_path(&datafile)?;
+        let offsets = ob::OBFastSearchIndex_seek_positions(&ob_index);
+        Ok(Self { ob_index, kind, datafile, format, offsets })
+    }
+
+    /// Number of records in the index
+    pub fn len(&self) -> usize { self.offsets.len() }
+    pub fn is_empty(&self) -> bool { self.offsets.is_empty() }
+    pub fn kind(&self) -> &Kind { &self.kind }
+    pub fn datafile(&self) -> &Path { &self.datafile }
+
+    /// Byte offset of `record` in the data file
+    pub fn offset(&self, record: usize) -> Option<u64> { self.offsets.get(record).copied() }
+
+    fn record_of(&self, offset: u64) -> Option<usize> {
+        self.offsets.binary_search(&offset).ok()
+    }
+
+    /// Reads `record` from the data file
+    pub fn read_record(&self, record: usize) -> Result<Molecule> {
+        let offset = self.offset(record)
+            .ok_or_else(|| Error::Setup(format!("record {} is not in an index of {} records", record, self.len())))?;
+        let mut file = std::fs::File::open(&self.datafile)?;
+        file.seek(SeekFrom::Start(offset))?;
+        let mut reader = MoleculeReader::new(file, self.format.clone())?;
+        self.record_result(record, reader.next())
+    }
+
+    /// Item read for `record`, with the index and offset of the record in its error
+    fn record_result(&self, record: usize, item: Option<Result<Molecule>>) -> Result<Molecule> {
+        let offset = self.offsets[record];
+        match item {
+            Some(Ok(mol)) => Ok(mol),
+            Some(Err(Error::Record { format, message, .. })) => Err(Error::Record { index: record, offset, format, message }),
+            Some(Err(err)) => Err(err),
+            None => Err(Error::Record { index: record, offset, format: self.format.to_string(), message: String::from("end of the data file") }),
+        }
+    }
+
+    /// Records whose fingerprint has every bit of the fingerprint of `query` set: a superset of
+    /// the records which contain `query` as a substructure.
+    pub fn screen(&self, query: &Molecule) -> Vec<usize> {
+        query.perceive();
+        let positions = {
+            let _fp_lock = self.kind.lock();
+            ob::OBFastSearchIndex_find(&self.ob_index, &query.ob_mol, self.len() as u32)
+        };
+        let mut records: Vec<usize> = positions.into_iter().filter_map(|pos| self.record_of(pos)).collect();
+        records.sort_unstable();
+        records
+    }
+
+    /// Records containing `query` as a substructure. The candidates of the
+    /// [screen](FastSearchIndex::screen) are matched against the query molecule itself, as
+    /// `obabel data.fs -s<SMILES>` does: atoms by element, aromaticity and ring membership,
+    /// bonds by order, so that a Kekulé query finds the aromatic rings of the records.
+    pub fn find_substructure(&self, query: &Molecule) -> Result<Vec<usize>> {
+        query.perceive();
+        self.confirm(self.screen(query), |mol| {
+            mol.perceive();
+            ob::OBMol_has_substructure(&mol.ob_mol, &query.ob_mol)
+        })
+    }
+
+    /// Records matching `pattern`. A general SMARTS has no fingerprint to screen with,
+    /// so every record is read; prefer [find_substructure](FastSearchIndex::find_substructure)
+    /// for queries which are molecules.
+    pub fn find_smarts(&self, pattern: &SmartsPattern) -> Result<Vec<usize>> {
+        self.confirm((0..self.len()).collect(), |mol| pattern.has_match(mol))
+    }
+
+    /// Reads the candidates with a single reader, which is handed only their bytes
+    fn confirm<F: Fn(&Molecule) -> bool>(&self, candidates: Vec<usize>, is_match: F) -> Result<Vec<usize>> {
+        let mut ranges = Vec::with_capacity(candidates.len());
+        let mut starts = Vec::with_capacity(candidates.len());  // in the bytes handed to the reader
+        let mut start = 0u64;
+        for &record in candidates.iter() {
+            let end = self.offsets.get(record + 1).copied().unwrap_or(u64::MAX);
+            ranges.push((self.offsets[record], end));
+            starts.push(start);
+            start = start.saturating_add(end - self.offsets[record]);
+        }
+        let file = BufReader::new(std::fs::File::open(&self.datafile)?);
+        let mut reader = MoleculeReader::new(RecordBytes { file, ranges: ranges.into_iter(), left: 0 }, self.format.clone())?;
+        let mut records = Vec::new();
+        for (&record, &start) in candidates.iter().zip(starts.iter()) {
+            // Skips the records after the previous candidate which are not indexed
+            let mut item = reader.next();
+            while item.is_some() && reader.record_offset() < start {
+                item = reader.next();
+            }
+            if item.is_some() && reader.record_offset() != start {
+                let message = String::from("no record starts at the offset of the index");
+                return Err(Error::Record { index: record, offset: self.offsets[record], format: self.format.to_string(), message });
+            }
+            let mol = self.record_result(record, item)?;
+            if is_match(&mol) {
+                records.push(record);
+            }
+        }
+        Ok(records)
+    }
+
+    fn hits(&self, hits: Vec<ob::OBFastSearchHit>) -> Vec<(usize, f64)> {
+        hits.into_iter()
+            .filter_map(|hit| self.record_of(hit.position).map(|record| (record, hit.tanimoto)))
+            .collect()
+    }
+
+    /// Records with a Tanimoto similarity of at least `min_tanimoto` to `query`,
+    /// as `(record, similarity)`, best first
+    pub fn find_similar(&self, query: &Molecule, min_tanimoto: f64) -> Vec<(usize, f64)> {
+        query.perceive();
+        let _fp_lock = self.kind.lock();
+        self.hits(ob::OBFastSearchIndex_find_similar(&self.ob_index, &query.ob_mol, min_tanimoto))
+    }
+
+    /// The `count` records most similar to `query`, as `(record, similarity)`, best first
+    pub fn find_most_similar(&self, query: &Molecule, count: usize) -> Vec<(usize, f64)> {
+        query.perceive();
+        let _fp_lock = self.kind.lock();
+        self.hits(ob::OBFastSearchIndex_find_most_similar(&self.ob_index, &query.ob_mol, count as u32))
+    }
+}
+
+#[cfg(test)]
+mod test_mod_fastsearch {
+    use super::*;
+
+    const SMILES: [&str; 6] = ["c1ccccc1O phenol", "CCCC butane", "C1CC( broken", "c1ccccc1N aniline", "Oc1ccccc1C cresol", "CCO ethanol"];
+
+    fn build_index(name: &str) -> FastSearchIndex {
+        let dir = std::env::temp_dir().join(format!("openbabel_test_fastsearch_{}", name));
+        std::fs::create_dir_all(&dir).unwrap();
+        let datafile = dir.join("mols.smi");
+        std::fs::write(&datafile, SMILES.join("\n") + "\n").unwrap();
+        crate::errorlog::set_output_level(None);
+        let count = FastSearchIndex::build(&datafile, InputFormat::smi, dir.join("mols.fs"), Kind::FP2 { nbits: 1024 }).unwrap();
+        crate::errorlog::set_output_level(Some(crate::errorlog::MessageLevel::Warning));
+        assert_eq!(count, 5);
+        FastSearchIndex::open(dir.join("mols.fs")).unwrap()
+    }
+
+    #[test]
+    fn test_build_and_open() {
+        let index = build_index("open");
+        assert_eq!(index.len(), 5);
+        assert_eq!(index.kind(), &Kind::FP2 { nbits: 1024 });
+        assert_eq!(index.datafile().file_name().unwrap(), "mols.smi");
+        assert_eq!(index.offset(1), Some(17));
+        // "C1CC( broken" is not indexed
+        assert_eq!(index.read_record(2).unwrap().num_atoms(), 7);
+        assert!(index.read_record(5).is_err());
+        assert!(matches!(FastSearchIndex::build("mols.smi", InputFormat::smi, "mols.fs", Kind::ECFP4 { nbits: 1024 }), Err(Error::Setup(_))));
+        assert!(matches!(FastSearchIndex::open("./no/such/index.fs"), Err(Error::Io(_))));
+    }
+
+    #[test]
+    fn test_substructure() {
+        let index = build_index("substructure");
+        let screened = index.screen(&Molecule::from_smiles("c1ccccc1").unwrap());
+        assert!(screened.contains(&0) && screened.contains(&2) && screened.contains(&3));
+        assert!(!screened.contains(&1));
+        let find = |smiles: &str| index.find_substructure(&Molecule::from_smiles(smiles).unwrap()).unwrap();
+        assert_eq!(find("c1ccccc1"), vec![0, 2, 3]);
+        assert_eq!(find("c1ccccc1O"), vec![0, 3]);
+        // Kekulé queries are aromatic molecules, not aliphatic SMARTS
+        assert_eq!(find("C1=CC=CC=C1"), vec![0, 2, 3]);
+        assert_eq!(find("OC1=CC=CC=C1C"), vec![3]);
+        assert_eq!(find("CCO"), vec![4]);
+        assert_eq!(index.find_smarts(&SmartsPattern::from_smarts("[OX2H][CX4]").unwrap()).unwrap(), vec![4]);
+        // "C1CC( broken" follows butane in the bytes handed to the reader
+        assert_eq!(index.confirm(vec![1, 2], |mol| mol.num_atoms() == 7).unwrap(), vec![2]);
+        assert_eq!(index.confirm(vec![1, 2], |mol| mol.num_atoms() == 4).unwrap(), vec![1]);
+    }
+
+    #[test]
+    fn test_similarity() {
+        let index = build_index("similarity");
+        let phenol = Molecule::from_smiles("c1ccccc1O").unwrap();
+        let hits = index.find_similar(&phenol, 0.99);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].0, 0);
+        assert_eq!(index.find_most_similar(&phenol, 2)[0], hits[0]);
+        let all = index.find_similar(&phenol, 0.0);
+        assert!(all.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+    }
+}
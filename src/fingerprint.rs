@@ -4,7 +4,8 @@
 //! 
 //! PatternFP (fingerprint3.cpp) requires data files as input.
 //! E.g. the FP3 and FP4 require data files of patterns.txt and SMARTS_InteLigand.txt respectively.
-//! Set BABEL_DATADIR to where those files locate, if "Open Babel Error in Read PatternFile" is encountered.
+//! Both are copied next to the compiled-in data directory by openbabel-sys; set BABEL_DATADIR to
+//! where those files locate if [FingerprintGenerator::new] returns [Error::MissingDataFile](crate::Error::MissingDataFile).
 //! 
 //! 
 //! 
@@ -14,9 +15,9 @@
 //! use openbabel::molecule;
 //! use openbabel::fingerprint::Kind;
 //! 
-//! let fpg = fingerprint::FingerprintGenerator::new(Kind::ECFP4 { nbits: 2048 });
+//! let fpg = fingerprint::FingerprintGenerator::new(Kind::ECFP4 { nbits: 2048 }).unwrap();
 //! 
-//! let mol = molecule::Molecule::new_from_smiles("c1ccccc1");
+//! let mol = molecule::Molecule::from_smiles("c1ccccc1").unwrap();
 //! let fpd = fpg.get_fingerprint(&mol);
 //! assert_eq!(fpd.len(), 64);
 //! 
@@ -29,14 +30,56 @@
 //! let fpd_of_mols = fpg.get_fingerprint_for_smiles_vec(&smiles_vec);
 //! assert_eq!(fpd_of_mols.len(), 4);
 //! for i in 0..4 {
-//!     assert_eq!(fpd_of_mols[i].len(), 64);
+//!     assert_eq!(fpd_of_mols[i].as_ref().unwrap().len(), 64);
 //! }
 //! 
 //! ```
+//!
+//! # Compare fingerprints
+//! ```
+//! use openbabel::fingerprint::{FingerprintGenerator, Kind};
+//! use openbabel::molecule::Molecule;
+//!
+//! let fpg = FingerprintGenerator::new(Kind::FP2 { nbits: 1024 }).unwrap();
+//! let phenol = fpg.fingerprint(&Molecule::from_smiles("c1ccccc1O").unwrap());
+//! let toluene = fpg.fingerprint(&Molecule::from_smiles("c1ccccc1C").unwrap());
+//! assert_eq!(phenol.nbits(), 1024);
+//! assert_eq!(phenol.tanimoto(&phenol), Some(1.0));
+//! assert!(phenol.tanimoto(&toluene).unwrap() < 1.0);
+//! assert_eq!(phenol.tanimoto(&phenol.fold(256)), None);  // different lengths
+//! assert_eq!(phenol.on_bits().count() as u32, phenol.popcount());
+//! let folded = phenol.fold(256);
+//! assert_eq!(folded.nbits(), 256);
+//! assert_eq!(phenol, openbabel::fingerprint::Fingerprint::from_hex(phenol.kind().clone(), &phenol.to_hex()).unwrap());
+//! ```
+//!
+//! # Threads
+//! `FingerprintGenerator` is `Send` and `Sync`, so one generator can be shared by several threads.
+//! OpenBabel keeps a single instance of each fingerprint type, therefore fingerprints of the same
+//! [Kind] are computed one at a time, while different kinds run concurrently. FP3 and FP4 also
+//! share OpenBabel's cache of recursive SMARTS, so they run one at a time with any other call
+//! which takes OpenBabel's global lock.
 
+use std::sync::{Mutex, MutexGuard};
 use ob_rs::ob;
 use super::molecule;
+use crate::error::{Error, Result};
+use crate::utils::{ob_lock, ObLock};
 use serde::{Serialize, Deserialize};
+use base64::Engine;
+
+/// One lock per fingerprint type, see [Kind::lock_index]
+static FP_LOCKS: [Mutex<()>; 9] = [
+    Mutex::new(()), Mutex::new(()), Mutex::new(()),
+    Mutex::new(()), Mutex::new(()), Mutex::new(()),
+    Mutex::new(()), Mutex::new(()), Mutex::new(()),
+];
+
+/// Guard of [Kind::lock], released before the lock of `utils::ob_lock` it may hold
+pub(crate) struct FpLock {
+    _guard: MutexGuard<'static, ()>,
+    _ob_lock: Option<ObLock>,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
 pub enum Kind {
@@ -52,7 +95,7 @@ pub enum Kind {
 }
 
 impl Kind {
-    fn as_str(&self) -> String {
+    pub(crate) fn as_str(&self) -> String {
         let fp_name = match self {
             Kind::FP2 { nbits: _ } => "FP2",
             Kind::FP3 { nbits: _ } => "FP3",
@@ -67,6 +110,72 @@ impl Kind {
         format!("{}", fp_name)
     }
 
+    /// Index in [FP_LOCKS] of the shared OpenBabel instance of this kind
+    fn lock_index(&self) -> usize {
+        match self {
+            Kind::FP2 { nbits: _ } => 0,
+            Kind::FP3 { nbits: _ } => 1,
+            Kind::FP4 { nbits: _ } => 2,
+            Kind::ECFP0 { nbits: _ } => 3,
+            Kind::ECFP2 { nbits: _ } => 4,
+            Kind::ECFP4 { nbits: _ } => 5,
+            Kind::ECFP6 { nbits: _ } => 6,
+            Kind::ECFP8 { nbits: _ } => 7,
+            Kind::ECFP10 { nbits: _ } => 8,
+        }
+    }
+
+    /// Held while the shared OpenBabel instance of this kind computes a fingerprint.
+    /// FP3 and FP4 match recursive SMARTS from their pattern file, which share the cache of
+    /// parsmart.cpp, so they also take the lock of `utils::ob_lock`.
+    pub(crate) fn lock(&self) -> FpLock {
+        let ob_lock = self.data_file().map(|_| ob_lock());
+        FpLock {
+            _guard: FP_LOCKS[self.lock_index()].lock().unwrap_or_else(|poisoned| poisoned.into_inner()),
+            _ob_lock: ob_lock,
+        }
+    }
+
+    /// Pattern file read by OpenBabel for this kind, if any
+    fn data_file(&self) -> Option<&'static str> {
+        match self {
+            Kind::FP3 { nbits: _ } => Some("patterns.txt"),
+            Kind::FP4 { nbits: _ } => Some("SMARTS_InteLigand.txt"),
+            _ => None,
+        }
+    }
+
+    /// Inverse of `as_str`
+    pub(crate) fn from_name(name: &str, nbits: u32) -> Option<Kind> {
+        match name {
+            "FP2" => Some(Kind::FP2 { nbits }),
+            "FP3" => Some(Kind::FP3 { nbits }),
+            "FP4" => Some(Kind::FP4 { nbits }),
+            "ECFP0" => Some(Kind::ECFP0 { nbits }),
+            "ECFP2" => Some(Kind::ECFP2 { nbits }),
+            "ECFP4" => Some(Kind::ECFP4 { nbits }),
+            "ECFP6" => Some(Kind::ECFP6 { nbits }),
+            "ECFP8" => Some(Kind::ECFP8 { nbits }),
+            "ECFP10" => Some(Kind::ECFP10 { nbits }),
+            _ => None,
+        }
+    }
+
+    /// The same kind with `nbits` bits
+    fn with_nbits(&self, nbits: u32) -> Kind {
+        match self {
+            Kind::FP2 { nbits: _ } => Kind::FP2 { nbits },
+            Kind::FP3 { nbits: _ } => Kind::FP3 { nbits },
+            Kind::FP4 { nbits: _ } => Kind::FP4 { nbits },
+            Kind::ECFP0 { nbits: _ } => Kind::ECFP0 { nbits },
+            Kind::ECFP2 { nbits: _ } => Kind::ECFP2 { nbits },
+            Kind::ECFP4 { nbits: _ } => Kind::ECFP4 { nbits },
+            Kind::ECFP6 { nbits: _ } => Kind::ECFP6 { nbits },
+            Kind::ECFP8 { nbits: _ } => Kind::ECFP8 { nbits },
+            Kind::ECFP10 { nbits: _ } => Kind::ECFP10 { nbits },
+        }
+    }
+
     pub fn get_nbits(&self) -> &u32 {
         match self {
             Kind::FP2 { nbits } => nbits,
@@ -87,48 +196,341 @@ pub struct FingerprintGenerator {
 }
 
 impl FingerprintGenerator {
-    pub fn new(kind: Kind) -> Self {
-        Self { kind }
+    /// Fails with [Error::Setup](crate::Error::Setup) if the fingerprint is not registered in
+    /// OpenBabel, or [Error::MissingDataFile](crate::Error::MissingDataFile) if its pattern file
+    /// cannot be located.
+    pub fn new(kind: Kind) -> Result<Self> {
+        cxx::let_cxx_string!(fp_name = &kind.as_str());
+        let _lock = ob_lock();
+        if !ob::OBFingerprint_find_fingerprint(&fp_name) {
+            return Err(Error::Setup(format!("unknown fingerprint {}", kind.as_str())));
+        }
+        if let Some(data_file) = kind.data_file() {
+            cxx::let_cxx_string!(data_file_cxx = data_file);
+            if ob::OBDatafile_locate(&data_file_cxx).is_empty() {
+                return Err(Error::MissingDataFile(String::from(data_file)));
+            }
+        }
+        let generator = Self { kind };
+        generator.get_fingerprint(&molecule::Molecule::new_empty());  // PatternFP reads its pattern file on first use
+        Ok(generator)
     }
 
     // pub fn get_fingerprint(&self, mol: &molecule::Molecule) -> cxx::UniquePtr<cxx::CxxVector<u32>> {
     pub fn get_fingerprint(&self, mol: &molecule::Molecule) -> Vec<u32> {
         cxx::let_cxx_string!(fp_name = &self.kind.as_str());
+        mol.perceive();
+        let _fp_lock = self.kind.lock();
         ob::OBFingerprint_get_fingerprint(&fp_name, &mol.ob_mol, *self.kind.get_nbits()) // If nbits <=0, nbits = 4096
             .iter().cloned().collect()
     }
 
-    pub fn get_fingerprint_for_smiles_vec(&self, smiles_vec: &Vec<String>) -> Vec<Vec<u32>> {
+    /// Fingerprint of `mol` with its [Kind] and similarity metrics
+    pub fn fingerprint(&self, mol: &molecule::Molecule) -> Fingerprint {
+        Fingerprint::from_words(self.kind.clone(), self.get_fingerprint(mol))
+    }
+
+    pub fn kind(&self) -> &Kind { &self.kind }
+
+    /// Returns one fingerprint per SMILES, or the parse error for SMILES that cannot be read.
+    pub fn get_fingerprint_for_smiles_vec(&self, smiles_vec: &Vec<String>) -> Vec<Result<Vec<u32>>> {
         smiles_vec.iter()
             .map(|smiles| {
-                let mol = molecule::Molecule::new_from_smiles(smiles);
-                // self.get_fingerprint(&mol).iter().cloned().collect()
-                self.get_fingerprint(&mol)
+                let mol = molecule::Molecule::from_smiles(smiles)?;
+                Ok(self.get_fingerprint(&mol))
             })
             .collect()
     }
 }
 
 
+/// Fingerprint bits as computed by OpenBabel: bit `i` is bit `i % 32` of word `i / 32`.
+///
+/// Serialized with serde as its [Kind] and its bits in hex, bit `i` being in byte `i / 8`
+/// (see [to_hex](Fingerprint::to_hex)); [as_base64] gives a more compact form.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+#[serde(into = "FingerprintHex", try_from = "FingerprintHex")]
+pub struct Fingerprint {
+    kind: Kind,
+    words: Vec<u32>,
+}
+
+impl Fingerprint {
+    /// Wraps the words returned by [FingerprintGenerator::get_fingerprint]. The number of bits
+    /// recorded in `kind` is set to the length of `words`.
+    pub fn from_words(kind: Kind, words: Vec<u32>) -> Self {
+        let kind = kind.with_nbits(words.len() as u32 * 32);
+        Self { kind, words }
+    }
+
+    pub fn kind(&self) -> &Kind { &self.kind }
+    pub fn nbits(&self) -> u32 { self.words.len() as u32 * 32 }
+    pub fn words(&self) -> &[u32] { &self.words }
+    pub fn into_words(self) -> Vec<u32> { self.words }
+
+    /// Panics if `bit` is not below [nbits](Fingerprint::nbits).
+    pub fn get(&self, bit: u32) -> bool {
+        self.words[(bit / 32) as usize] & (1 << (bit % 32)) != 0
+    }
+
+    /// Panics if `bit` is not below [nbits](Fingerprint::nbits).
+    pub fn set(&mut self, bit: u32, value: bool) {
+        let word = &mut self.words[(bit / 32) as usize];
+        if value {
+            *word |= 1 << (bit % 32);
+        } else {
+            *word &= !(1 << (bit % 32));
+        }
+    }
+
+    /// Number of bits set
+    pub fn popcount(&self) -> u32 { popcount::count(&self.words) }
+
+    /// Indices of the bits set, in increasing order
+    pub fn on_bits(&self) -> impl Iterator<Item = u32> + '_ {
+        self.words.iter().enumerate().flat_map(|(i, &word)| {
+            (0..32).filter(move |b| word & (1 << b) != 0).map(move |b| i as u32 * 32 + b)
+        })
+    }
+
+    /// Number of bits set in both fingerprints, None if they have different lengths,
+    /// as for the similarity metrics.
+    pub fn intersection_count(&self, other: &Fingerprint) -> Option<u32> {
+        if self.words.len() != other.words.len() {
+            return None;
+        }
+        Some(popcount::and_count(&self.words, &other.words))
+    }
+
+    /// |A∩B| / |A∪B|, equal to OBFingerprint::Tanimoto except that two empty fingerprints give 0.0 instead of NaN
+    pub fn tanimoto(&self, other: &Fingerprint) -> Option<f64> {
+        let common = self.intersection_count(other)?;
+        let union = self.popcount() + other.popcount() - common;
+        Some(if union == 0 { 0.0 } else { common as f64 / union as f64 })
+    }
+
+    /// 2|A∩B| / (|A| + |B|)
+    pub fn dice(&self, other: &Fingerprint) -> Option<f64> {
+        let common = self.intersection_count(other)?;
+        let total = self.popcount() + other.popcount();
+        Some(if total == 0 { 0.0 } else { 2.0 * common as f64 / total as f64 })
+    }
+
+    /// |A∩B| / (α|A−B| + β|B−A| + |A∩B|), with `self` as A. α = β = 1 is the Tanimoto
+    /// coefficient and α = β = 0.5 the Dice coefficient.
+    pub fn tversky(&self, other: &Fingerprint, alpha: f64, beta: f64) -> Option<f64> {
+        let common = self.intersection_count(other)?;
+        let denominator = alpha * (self.popcount() - common) as f64 + beta * (other.popcount() - common) as f64 + common as f64;
+        Some(if denominator == 0.0 { 0.0 } else { common as f64 / denominator })
+    }
+
+    /// |A∩B| / √(|A||B|)
+    pub fn cosine(&self, other: &Fingerprint) -> Option<f64> {
+        let common = self.intersection_count(other)?;
+        let product = self.popcount() as f64 * other.popcount() as f64;
+        Some(if product == 0.0 { 0.0 } else { common as f64 / product.sqrt() })
+    }
+
+    /// Folds the fingerprint in halves, OR-ing the upper half onto the lower one, while it keeps
+    /// at least `nbits` bits (and at least 32), like OBFingerprint::Fold.
+    pub fn fold(&self, nbits: u32) -> Fingerprint {
+        let nbits = nbits.max(32);
+        let mut words = self.words.clone();
+        while words.len() % 2 == 0 && words.len() as u32 * 32 / 2 >= nbits {
+            let half = words.len() / 2;
+            let (low, high) = words.split_at_mut(half);
+            for (l, h) in low.iter_mut().zip(high.iter()) {
+                *l |= *h;
+            }
+            words.truncate(half);
+        }
+        Self::from_words(self.kind.clone(), words)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.words.iter().flat_map(|word| word.to_le_bytes()).collect()
+    }
+
+    fn from_bytes(kind: Kind, bytes: &[u8], format: &str) -> Result<Self> {
+        if bytes.len() as u64 * 8 != *kind.get_nbits() as u64 {
+            let message = format!("{} bytes do not hold the {} bits of {}", bytes.len(), kind.get_nbits(), kind.as_str());
+            return Err(Error::Parse { format: String::from(format), message });
+        }
+        if bytes.len() % 4 != 0 {
+            return Err(Error::Parse { format: String::from(format), message: format!("{} bytes is not a whole number of 32-bit words", bytes.len()) });
+        }
+        let words = bytes.chunks_exact(4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect();
+        Ok(Self::from_words(kind, words))
+    }
+
+    /// Lowercase hex of the bits, two digits per byte, bit `i` in byte `i / 8`
+    pub fn to_hex(&self) -> String { hex::encode(self.to_bytes()) }
+
+    /// Reads the output of [to_hex](Fingerprint::to_hex), failing with [Error::Parse](crate::Error::Parse)
+    /// if `bits` is malformed or does not hold the `nbits` of `kind`
+    pub fn from_hex(kind: Kind, bits: &str) -> Result<Self> {
+        let bytes = hex::decode(bits).map_err(|err| Error::Parse { format: String::from("hex"), message: err.to_string() })?;
+        Self::from_bytes(kind, &bytes, "hex")
+    }
+
+    /// Standard base64 (with padding) of the bytes of [to_hex](Fingerprint::to_hex)
+    pub fn to_base64(&self) -> String {
+        base64::engine::general_purpose::STANDARD.encode(self.to_bytes())
+    }
+
+    /// Reads the output of [to_base64](Fingerprint::to_base64), failing with [Error::Parse](crate::Error::Parse)
+    /// if `bits` is malformed or does not hold the `nbits` of `kind`
+    pub fn from_base64(kind: Kind, bits: &str) -> Result<Self> {
+        let bytes = base64::engine::general_purpose::STANDARD.decode(bits)
+            .map_err(|err| Error::Parse { format: String::from("base64"), message: err.to_string() })?;
+        Self::from_bytes(kind, &bytes, "base64")
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct FingerprintHex {
+    kind: Kind,
+    bits: String,
+}
+
+impl From<Fingerprint> for FingerprintHex {
+    fn from(fp: Fingerprint) -> Self {
+        let bits = fp.to_hex();
+        Self { kind: fp.kind, bits }
+    }
+}
+
+impl std::convert::TryFrom<FingerprintHex> for Fingerprint {
+    type Error = Error;
+
+    fn try_from(repr: FingerprintHex) -> Result<Self> {
+        Fingerprint::from_hex(repr.kind, &repr.bits)
+    }
+}
+
+/// Serializes a [Fingerprint] with its bits in base64:
+/// `#[serde(with = "openbabel::fingerprint::as_base64")]`
+pub mod as_base64 {
+    use super::{Fingerprint, Kind};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct FingerprintBase64 {
+        kind: Kind,
+        bits: String,
+    }
+
+    pub fn serialize<S: Serializer>(fp: &Fingerprint, serializer: S) -> Result<S::Ok, S::Error> {
+        FingerprintBase64 { kind: fp.kind.clone(), bits: fp.to_base64() }.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Fingerprint, D::Error> {
+        let repr = FingerprintBase64::deserialize(deserializer)?;
+        Fingerprint::from_base64(repr.kind, &repr.bits).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Bit counting. Plain `count_ones` loops, also compiled with `#[target_feature]` popcnt and
+/// avx2 so that the compiler can emit the popcnt instruction and auto-vectorize them; the
+/// variant is picked at run time from the features of the CPU.
+pub(crate) mod popcount {
+    #[inline(always)]
+    fn count_words(words: &[u32]) -> u32 {
+        words.iter().map(|w| w.count_ones()).sum()
+    }
+
+    #[inline(always)]
+    fn and_count_words(a: &[u32], b: &[u32]) -> u32 {
+        a.iter().zip(b.iter()).map(|(x, y)| (x & y).count_ones()).sum()
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    mod x86 {
+        #[target_feature(enable = "avx2,popcnt")]
+        pub(super) unsafe fn count_avx2(words: &[u32]) -> u32 { super::count_words(words) }
+
+        #[target_feature(enable = "popcnt")]
+        pub(super) unsafe fn count_popcnt(words: &[u32]) -> u32 { super::count_words(words) }
+
+        #[target_feature(enable = "avx2,popcnt")]
+        pub(super) unsafe fn and_count_avx2(a: &[u32], b: &[u32]) -> u32 { super::and_count_words(a, b) }
+
+        #[target_feature(enable = "popcnt")]
+        pub(super) unsafe fn and_count_popcnt(a: &[u32], b: &[u32]) -> u32 { super::and_count_words(a, b) }
+    }
+
+    pub(crate) fn count(words: &[u32]) -> u32 {
+        #[cfg(target_arch = "x86_64")]
+        {
+            // SAFETY: the CPU supports the features the functions are compiled for
+            if is_x86_feature_detected!("avx2") && is_x86_feature_detected!("popcnt") {
+                return unsafe { x86::count_avx2(words) };
+            }
+            if is_x86_feature_detected!("popcnt") {
+                return unsafe { x86::count_popcnt(words) };
+            }
+        }
+        count_words(words)
+    }
+
+    pub(crate) fn and_count(a: &[u32], b: &[u32]) -> u32 {
+        #[cfg(target_arch = "x86_64")]
+        {
+            // SAFETY: as in `count`
+            if is_x86_feature_detected!("avx2") && is_x86_feature_detected!("popcnt") {
+                return unsafe { x86::and_count_avx2(a, b) };
+            }
+            if is_x86_feature_detected!("popcnt") {
+                return unsafe { x86::and_count_popcnt(a, b) };
+            }
+        }
+        and_count_words(a, b)
+    }
+
+    #[cfg(test)]
+    mod test_mod_popcount {
+        use super::*;
+
+        #[test]
+        fn test_dispatch() {
+            let a: Vec<u32> = (0..1000u32).map(|i| i.wrapping_mul(2654435761)).collect();
+            let b: Vec<u32> = (0..1000u32).map(|i| i.wrapping_mul(40503).rotate_left(i % 32)).collect();
+            assert_eq!(count(&a), count_words(&a));
+            assert_eq!(and_count(&a, &b), and_count_words(&a, &b));
+            assert_eq!(count(&[]), 0);
+        }
+    }
+}
+
+
 #[cfg(test)]
 mod test_mod_fingerprint {
     use super::*;
 
-    // #[test]
-    // #[ignore]
-    // fn test_error_cases() {
-    //     let thread_id = 0;
-    //     // FP generated by get_fingerprint() and get_fingerprint_in_batch() are different for the same SMILES "CCCN"
-    //     // Root causes to be investigated
-    //     let fpk = Kind::FP2 { nbits: 4096 };
-    //     let fpds = get_fingerprint_in_batch(&vec![String::from("CCCC"), String::from("CCCN")], &fpk, thread_id);
-    //     let fpd = get_fingerprint(&String::from("CCCN"), &fpk, thread_id);
-    //     for i in 0..128 {
-    //         let offset: usize = 128;
-    //         println!("{}: {} - {}", i, fpds.as_ref().unwrap().get(i + offset).unwrap(), fpd.as_ref().unwrap().get(i).unwrap());
-    //         assert_eq!(fpds.as_ref().unwrap().get(i + offset).unwrap(), fpd.as_ref().unwrap().get(i).unwrap());
-    //     }
-    // }
+    #[test]
+    fn test_get_fp_in_threads() {
+        let smiles_vec: Vec<String> = vec!["CCCC", "CCCN", "c1ccccc1O", "CC(=O)Nc1ccc(O)cc1"]
+            .into_iter().map(String::from).collect();
+        for fpk in [Kind::FP2 { nbits: 4096 }, Kind::FP4 { nbits: 4096 }, Kind::ECFP4 { nbits: 2048 }].iter() {
+            let fpg = FingerprintGenerator::new(fpk.clone()).unwrap();
+            let expected: Vec<Vec<u32>> = fpg.get_fingerprint_for_smiles_vec(&smiles_vec)
+                .into_iter().map(|fpd| fpd.unwrap()).collect();
+            std::thread::scope(|s| {
+                let handles: Vec<_> = (0..4)
+                    .map(|_| s.spawn(|| {
+                        (0..10).map(|_| {
+                            fpg.get_fingerprint_for_smiles_vec(&smiles_vec).into_iter().map(|fpd| fpd.unwrap()).collect::<Vec<_>>()
+                        }).collect::<Vec<_>>()
+                    }))
+                    .collect();
+                for handle in handles {
+                    for fpds in handle.join().unwrap() {
+                        assert_eq!(fpds, expected);
+                    }
+                }
+            });
+        }
+    }
 
     #[test]
     fn test_get_fp() {
@@ -143,8 +545,8 @@ mod test_mod_fingerprint {
             Kind::ECFP8 { nbits: 4096 },
             Kind::ECFP10 { nbits: 4096 },
         ].iter() {
-            let mol = molecule::Molecule::new_from_smiles("CCNCC");
-            let fpg = FingerprintGenerator::new(fpk.clone());
+            let mol = molecule::Molecule::from_smiles("CCNCC").unwrap();
+            let fpg = FingerprintGenerator::new(fpk.clone()).unwrap();
             let fpd = fpg.get_fingerprint(&mol);
             assert_eq!(fpd.len(), 128);
         }
@@ -168,12 +570,111 @@ mod test_mod_fingerprint {
             Kind::ECFP8 { nbits: 4096 },
             Kind::ECFP10 { nbits: 4096 },
         ].iter() {
-            let fpg = FingerprintGenerator::new(fpk.clone());
+            let fpg = FingerprintGenerator::new(fpk.clone()).unwrap();
             let fpd_vec = fpg.get_fingerprint_for_smiles_vec(&smiles_vec);
             assert_eq!(fpd_vec.len(), 2);
-            assert_eq!(fpd_vec[0].len(), 128);
-            assert_eq!(fpd_vec[1].len(), 128);
+            assert_eq!(fpd_vec[0].as_ref().unwrap().len(), 128);
+            assert_eq!(fpd_vec[1].as_ref().unwrap().len(), 128);
         }
     }
+
+    #[test]
+    fn test_error_cases() {
+        // The fingerprint of a SMILES must not depend on the SMILES parsed before it
+        let fpg = FingerprintGenerator::new(Kind::FP2 { nbits: 4096 }).unwrap();
+        let fpd_vec = fpg.get_fingerprint_for_smiles_vec(&vec![String::from("CCCC"), String::from("CCCN")]);
+        let fpd = fpg.get_fingerprint(&molecule::Molecule::from_smiles("CCCN").unwrap());
+        assert_eq!(fpd_vec[1].as_ref().unwrap(), &fpd);
+    }
+
+    #[test]
+    fn test_similarity() {
+        let fpg = FingerprintGenerator::new(Kind::FP2 { nbits: 1024 }).unwrap();
+        let fps: Vec<Fingerprint> = ["c1ccccc1O", "c1ccccc1C", "CC(=O)Nc1ccc(O)cc1", "CCCCCC"].iter()
+            .map(|smiles| fpg.fingerprint(&molecule::Molecule::from_smiles(smiles).unwrap()))
+            .collect();
+        for a in fps.iter() {
+            for b in fps.iter() {
+                assert_eq!(a.tanimoto(b), Some(ob::OBFingerprint_tanimoto(&a.words().to_vec(), &b.words().to_vec())));
+                assert_eq!(a.tanimoto(b), a.tversky(b, 1.0, 1.0));
+                assert!((a.dice(b).unwrap() - a.tversky(b, 0.5, 0.5).unwrap()).abs() < 1e-12);
+            }
+        }
+
+        let a = Fingerprint::from_words(Kind::ECFP4 { nbits: 0 }, vec![0b1111, 0]);
+        let b = Fingerprint::from_words(Kind::ECFP4 { nbits: 0 }, vec![0b0011, 0b1]);
+        assert_eq!(a.kind(), &Kind::ECFP4 { nbits: 64 });
+        assert_eq!((a.popcount(), b.popcount(), a.intersection_count(&b)), (4, 3, Some(2)));
+        assert_eq!(a.tanimoto(&b), Some(2.0 / 5.0));
+        assert_eq!(a.dice(&b), Some(4.0 / 7.0));
+        assert_eq!(a.cosine(&b), Some(2.0 / 12f64.sqrt()));
+        assert_eq!(a.tversky(&b, 1.0, 0.0), Some(2.0 / 4.0));
+        let empty = Fingerprint::from_words(Kind::ECFP4 { nbits: 64 }, vec![0, 0]);
+        assert_eq!(empty.tanimoto(&empty), Some(0.0));
+
+        let longer = Fingerprint::from_words(Kind::ECFP4 { nbits: 0 }, vec![0b1111, 0, 0, 0]);
+        assert_eq!(a.intersection_count(&longer), None);
+        assert_eq!(a.tanimoto(&longer), None);
+        assert_eq!(a.dice(&longer), None);
+        assert_eq!(a.tversky(&longer, 1.0, 1.0), None);
+        assert_eq!(a.cosine(&longer), None);
+    }
+
+    #[test]
+    fn test_bits() {
+        let mut fp = Fingerprint::from_words(Kind::FP2 { nbits: 128 }, vec![0; 4]);
+        fp.set(3, true);
+        fp.set(70, true);
+        fp.set(127, true);
+        assert!(fp.get(70) && !fp.get(71));
+        assert_eq!(fp.on_bits().collect::<Vec<u32>>(), vec![3, 70, 127]);
+        fp.set(70, false);
+        assert_eq!(fp.popcount(), 2);
+
+        let folded = fp.fold(64);
+        assert_eq!(folded.nbits(), 64);
+        assert_eq!(folded.kind(), &Kind::FP2 { nbits: 64 });
+        assert_eq!(folded.on_bits().collect::<Vec<u32>>(), vec![3, 63]);
+        assert_eq!(fp.fold(100).nbits(), 128);
+        assert_eq!(fp.fold(0).nbits(), 32);
+    }
+
+    #[test]
+    fn test_serialization() {
+        let fpg = FingerprintGenerator::new(Kind::ECFP4 { nbits: 1024 }).unwrap();
+        let fp = fpg.fingerprint(&molecule::Molecule::from_smiles("CC(=O)Nc1ccc(O)cc1").unwrap());
+        assert_eq!(fp.to_hex().len(), 256);
+        assert_eq!(Fingerprint::from_hex(fp.kind().clone(), &fp.to_hex()).unwrap(), fp);
+        assert_eq!(Fingerprint::from_base64(fp.kind().clone(), &fp.to_base64()).unwrap(), fp);
+        assert!(matches!(Fingerprint::from_hex(fp.kind().clone(), "0g"), Err(Error::Parse { .. })));
+        assert!(matches!(Fingerprint::from_hex(fp.kind().clone(), "00ff"), Err(Error::Parse { .. })));
+        assert!(matches!(Fingerprint::from_hex(Kind::ECFP4 { nbits: 2048 }, &fp.to_hex()), Err(Error::Parse { .. })));
+        assert!(matches!(Fingerprint::from_base64(Kind::ECFP4 { nbits: 512 }, &fp.to_base64()), Err(Error::Parse { .. })));
+
+        let single = Fingerprint::from_words(Kind::FP2 { nbits: 32 }, vec![0x0000_0102]);
+        assert_eq!(single.to_hex(), "02010000");
+        assert_eq!(serde_json::to_string(&single).unwrap(), r#"{"kind":{"FP2":{"nbits":32}},"bits":"02010000"}"#);
+        let json = serde_json::to_string(&fp).unwrap();
+        assert_eq!(serde_json::from_str::<Fingerprint>(&json).unwrap(), fp);
+
+        #[derive(Serialize, Deserialize)]
+        struct Record {
+            #[serde(with = "as_base64")]
+            fp: Fingerprint,
+        }
+        let json = serde_json::to_string(&Record { fp: single.clone() }).unwrap();
+        assert_eq!(json, r#"{"fp":{"kind":{"FP2":{"nbits":32}},"bits":"AgEAAA=="}}"#);
+        assert_eq!(serde_json::from_str::<Record>(&json).unwrap().fp, single);
+        assert!(serde_json::from_str::<Fingerprint>(r#"{"kind":{"FP2":{"nbits":64}},"bits":"02010000"}"#).is_err());
+    }
+
+    #[test]
+    fn test_get_fp_for_invalid_smiles() {
+        let smiles_vec = vec![String::from("CCNCC"), String::from("C1CC(")];
+        let fpg = FingerprintGenerator::new(Kind::ECFP4 { nbits: 2048 }).unwrap();
+        let fpd_vec = fpg.get_fingerprint_for_smiles_vec(&smiles_vec);
+        assert_eq!(fpd_vec[0].as_ref().unwrap().len(), 64);
+        assert!(matches!(fpd_vec[1], Err(Error::Parse { .. })));
+    }
 }
 
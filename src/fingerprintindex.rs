@@ -0,0 +1,543 @@
+//! Openbabel Fingerprint Index
+//!
+//! In-memory Tanimoto similarity search over many [Fingerprint]s of one [Kind].
+//!
+//! Fingerprints are grouped by popcount. A fingerprint with `b` bits set cannot reach a
+//! Tanimoto similarity above `min(a, b) / max(a, b)` with a query of `a` bits
+//! (Swamidass & Baldi, J. Chem. Inf. Model. 2007), so whole groups are skipped.
+//!
+//! ```
+//! use openbabel::fingerprint::{FingerprintGenerator, Kind};
+//! use openbabel::fingerprintindex::FingerprintIndex;
+//! use openbabel::molecule::Molecule;
+//!
+//! let fpg = FingerprintGenerator::new(Kind::ECFP4 { nbits: 1024 }).unwrap();
+//! let mut index = FingerprintIndex::new(Kind::ECFP4 { nbits: 1024 }).unwrap();
+//! for smiles in ["CCO", "CCCO", "c1ccccc1O", "CC(=O)Nc1ccc(O)cc1"].iter() {
+//!     index.add(&fpg.fingerprint(&Molecule::from_smiles(smiles).unwrap())).unwrap();
+//! }
+//!
+//! let query = fpg.fingerprint(&Molecule::from_smiles("c1ccccc1O").unwrap());
+//! let hits = index.search_top_k(&query, 2).unwrap();
+//! assert_eq!(hits[0].index, 2);
+//! assert_eq!(hits[0].similarity, 1.0);
+//! assert!(index.search_threshold(&query, 0.3).unwrap().iter().all(|hit| hit.similarity >= 0.3));
+//!
+//! let mut buffer = Vec::new();
+//! index.write(&mut buffer).unwrap();
+//! let reloaded = FingerprintIndex::read(buffer.as_slice()).unwrap();
+//! assert_eq!(reloaded.search_top_k(&query, 2).unwrap(), hits);
+//! ```
+//!
+//! With the `rayon` feature, [search_top_k_par](FingerprintIndex::search_top_k_par) and
+//! [search_threshold_par](FingerprintIndex::search_threshold_par) scan on the rayon thread pool.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::io::{Read, Write};
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+use crate::error::{Error, Result};
+use crate::fingerprint::{popcount, Fingerprint, Kind};
+
+/// First bytes of the file written by [FingerprintIndex::write]
+const MAGIC: &[u8; 8] = b"OBFPIDX1";
+
+/// Largest fingerprint length an index accepts
+const MAX_NBITS: u32 = 1 << 20;
+
+/// Why an index cannot hold fingerprints of `nbits` bits, if it cannot
+fn nbits_error(nbits: u32) -> Option<String> {
+    (nbits == 0 || nbits % 32 != 0 || nbits > MAX_NBITS)
+        .then(|| format!("{} bits is not a positive multiple of 32 up to {}", nbits, MAX_NBITS))
+}
+
+/// A fingerprint of the index and its Tanimoto similarity to the query
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hit {
+    /// Position of the fingerprint in the order of [add](FingerprintIndex::add)
+    pub index: usize,
+    pub similarity: f64,
+}
+
+/// Hit ordered from worst to best: lower similarity first, then higher index
+#[derive(PartialEq)]
+struct Ranked(Hit);
+
+impl Eq for Ranked {}
+
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.similarity.total_cmp(&other.0.similarity)
+            .then_with(|| other.0.index.cmp(&self.0.index))
+    }
+}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
+/// The best `k` hits seen so far
+struct TopK {
+    k: usize,
+    heap: BinaryHeap<Reverse<Ranked>>,
+}
+
+impl TopK {
+    fn new(k: usize) -> Self {
+        Self { k, heap: BinaryHeap::with_capacity(k + 1) }
+    }
+
+    fn push(&mut self, hit: Hit) {
+        if self.heap.len() < self.k {
+            self.heap.push(Reverse(Ranked(hit)));
+        } else if let Some(Reverse(worst)) = self.heap.peek() {
+            if Ranked(hit) > *worst {
+                self.heap.pop();
+                self.heap.push(Reverse(Ranked(hit)));
+            }
+        }
+    }
+
+    /// Similarity a fingerprint must reach to enter, once `k` hits are kept
+    fn min_similarity(&self) -> Option<f64> {
+        if self.heap.len() < self.k {
+            return None;
+        }
+        self.heap.peek().map(|Reverse(worst)| worst.0.similarity)
+    }
+
+    #[cfg(feature = "rayon")]
+    fn merge(mut self, other: TopK) -> Self {
+        for Reverse(Ranked(hit)) in other.heap {
+            self.push(hit);
+        }
+        self
+    }
+
+    fn into_hits(self) -> Vec<Hit> {
+        sorted(self.heap.into_iter().map(|Reverse(Ranked(hit))| hit).collect())
+    }
+}
+
+/// Best hits first, ties by index
+fn sorted(mut hits: Vec<Hit>) -> Vec<Hit> {
+    hits.sort_by_key(|hit| Reverse(Ranked(*hit)));
+    hits
+}
+
+/// Upper bound of the Tanimoto similarity between fingerprints with `a` and `b` bits set
+fn bound(a: u32, b: u32) -> f64 {
+    let max = a.max(b);
+    if max == 0 { 0.0 } else { a.min(b) as f64 / max as f64 }
+}
+
+/// Same value as [Fingerprint::tanimoto]
+fn tanimoto(common: u32, a: u32, b: u32) -> f64 {
+    let union = a + b - common;
+    if union == 0 { 0.0 } else { common as f64 / union as f64 }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(into = "FingerprintIndexRepr", try_from = "FingerprintIndexRepr")]
+pub struct FingerprintIndex {
+    kind: Kind,
+    words_per_fp: usize,
+    /// Words of all fingerprints, one after the other
+    words: Vec<u32>,
+    popcounts: Vec<u32>,
+    /// Indices of the fingerprints by popcount
+    buckets: Vec<Vec<usize>>,
+}
+
+impl FingerprintIndex {
+    /// Empty index for fingerprints of `kind`. Fails with
+    /// [Error::IncompatibleFingerprint](crate::Error::IncompatibleFingerprint) unless the number
+    /// of bits of `kind` is a positive multiple of 32 (as with OpenBabel) up to 2^20.
+    pub fn new(kind: Kind) -> Result<Self> {
+        let nbits = *kind.get_nbits();
+        if let Some(message) = nbits_error(nbits) {
+            return Err(Error::IncompatibleFingerprint(message));
+        }
+        Ok(Self {
+            kind,
+            words_per_fp: (nbits / 32) as usize,
+            words: Vec::new(),
+            popcounts: Vec::new(),
+            buckets: vec![Vec::new(); nbits as usize + 1],
+        })
+    }
+
+    pub fn kind(&self) -> &Kind { &self.kind }
+    pub fn len(&self) -> usize { self.popcounts.len() }
+    pub fn is_empty(&self) -> bool { self.popcounts.is_empty() }
+
+    fn check(&self, fp: &Fingerprint) -> Result<()> {
+        if fp.kind() != &self.kind {
+            return Err(Error::IncompatibleFingerprint(format!("{:?} in an index of {:?}", fp.kind(), self.kind)));
+        }
+        Ok(())
+    }
+
+    /// Adds a fingerprint and returns its index, failing with
+    /// [Error::IncompatibleFingerprint](crate::Error::IncompatibleFingerprint) if it is not of the kind of the index.
+    pub fn add(&mut self, fp: &Fingerprint) -> Result<usize> {
+        self.check(fp)?;
+        Ok(self.push_words(fp.words()))
+    }
+
+    fn push_words(&mut self, words: &[u32]) -> usize {
+        let index = self.popcounts.len();
+        let count = popcount::count(words);
+        self.words.extend_from_slice(words);
+        self.popcounts.push(count);
+        self.buckets[count as usize].push(index);
+        index
+    }
+
+    /// The fingerprint at `index`
+    pub fn get(&self, index: usize) -> Option<Fingerprint> {
+        if index >= self.len() {
+            return None;
+        }
+        Some(Fingerprint::from_words(self.kind.clone(), self.fp_words(index).to_vec()))
+    }
+
+    fn fp_words(&self, index: usize) -> &[u32] {
+        &self.words[index * self.words_per_fp..(index + 1) * self.words_per_fp]
+    }
+
+    fn hit(&self, query: &[u32], query_count: u32, index: usize) -> Hit {
+        let common = popcount::and_count(query, self.fp_words(index));
+        Hit { index, similarity: tanimoto(common, query_count, self.popcounts[index]) }
+    }
+
+    /// Popcounts of the buckets from the highest bound to the lowest
+    fn bucket_order(&self, query_count: u32) -> Vec<u32> {
+        let mut order: Vec<u32> = (0..self.buckets.len() as u32)
+            .filter(|&b| !self.buckets[b as usize].is_empty())
+            .collect();
+        order.sort_by(|&x, &y| bound(query_count, y).total_cmp(&bound(query_count, x)));
+        order
+    }
+
+    /// The `k` fingerprints most similar to `query`, best first (ties by index)
+    pub fn search_top_k(&self, query: &Fingerprint, k: usize) -> Result<Vec<Hit>> {
+        self.check(query)?;
+        let query_count = query.popcount();
+        if k == 0 {
+            return Ok(Vec::new());
+        }
+        let mut top_k = TopK::new(k);
+        for b in self.bucket_order(query_count) {
+            if let Some(min_similarity) = top_k.min_similarity() {
+                if bound(query_count, b) < min_similarity {
+                    break;
+                }
+            }
+            for &index in self.buckets[b as usize].iter() {
+                top_k.push(self.hit(query.words(), query_count, index));
+            }
+        }
+        Ok(top_k.into_hits())
+    }
+
+    /// All fingerprints with a similarity of at least `min_similarity` to `query`, best first
+    pub fn search_threshold(&self, query: &Fingerprint, min_similarity: f64) -> Result<Vec<Hit>> {
+        self.check(query)?;
+        let query_count = query.popcount();
+        let hits = self.candidates(query_count, min_similarity)
+            .map(|index| self.hit(query.words(), query_count, index))
+            .filter(|hit| hit.similarity >= min_similarity)
+            .collect();
+        Ok(sorted(hits))
+    }
+
+    /// Fingerprints in the buckets whose bound reaches `min_similarity`
+    fn candidates(&self, query_count: u32, min_similarity: f64) -> impl Iterator<Item = usize> + '_ {
+        self.buckets.iter().enumerate()
+            .filter(move |(b, _)| bound(query_count, *b as u32) >= min_similarity)
+            .flat_map(|(_, bucket)| bucket.iter().copied())
+    }
+
+    /// Writes the index in a compact binary form, read back by [read](FingerprintIndex::read)
+    pub fn write<W: Write>(&self, mut writer: W) -> Result<()> {
+        let name = self.kind.as_str();
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[name.len() as u8])?;
+        writer.write_all(name.as_bytes())?;
+        writer.write_all(&self.kind.get_nbits().to_le_bytes())?;
+        writer.write_all(&(self.len() as u64).to_le_bytes())?;
+        for word in self.words.iter() {
+            writer.write_all(&word.to_le_bytes())?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Reads an index written by [write](FingerprintIndex::write), up to the end of `reader`.
+    /// Fails with [Error::Parse](crate::Error::Parse) if the data is not such an index, has a
+    /// fingerprint length which is not a positive multiple of 32 (up to 2^20 bits), or does not
+    /// hold exactly the number of fingerprints of its header.
+    pub fn read<R: Read>(mut reader: R) -> Result<Self> {
+        let invalid = |message: &str| Error::Parse { format: String::from("fingerprint index"), message: String::from(message) };
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not a fingerprint index"));
+        }
+        let mut name_len = [0u8; 1];
+        reader.read_exact(&mut name_len)?;
+        let mut name = vec![0u8; name_len[0] as usize];
+        reader.read_exact(&mut name)?;
+        let mut nbits = [0u8; 4];
+        reader.read_exact(&mut nbits)?;
+        let nbits = u32::from_le_bytes(nbits);
+        if let Some(message) = nbits_error(nbits) {
+            return Err(invalid(&message));
+        }
+        let mut count = [0u8; 8];
+        reader.read_exact(&mut count)?;
+        let count = u64::from_le_bytes(count);
+        let kind = std::str::from_utf8(&name).ok()
+            .and_then(|name| Kind::from_name(name, nbits))
+            .ok_or_else(|| invalid("unknown fingerprint kind"))?;
+
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let fp_len = nbits as u64 / 8;
+        if count.checked_mul(fp_len) != Some(data.len() as u64) {
+            return Err(invalid(&format!("{} fingerprints of {} bytes do not fit the {} bytes of data", count, fp_len, data.len())));
+        }
+        let mut index = Self::new(kind)?;
+        let mut words = vec![0u32; index.words_per_fp];
+        for fp_bytes in data.chunks_exact(fp_len as usize) {
+            for (word, bytes) in words.iter_mut().zip(fp_bytes.chunks_exact(4)) {
+                *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+            }
+            index.push_words(&words);
+        }
+        Ok(index)
+    }
+
+    /// Writes the index to a file, see [write](FingerprintIndex::write)
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.write(std::io::BufWriter::new(std::fs::File::create(path)?))
+    }
+
+    /// Reads an index from a file, see [read](FingerprintIndex::read)
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::read(std::io::BufReader::new(std::fs::File::open(path)?))
+    }
+}
+
+/// Number of fingerprints scored per parallel batch in [FingerprintIndex::search_top_k_par]
+#[cfg(feature = "rayon")]
+const PAR_BATCH: usize = 1 << 14;
+
+#[cfg(feature = "rayon")]
+impl FingerprintIndex {
+    /// Parallel [search_top_k](FingerprintIndex::search_top_k). The buckets are scored in
+    /// batches, so the pruning stops the scan at the same bound.
+    pub fn search_top_k_par(&self, query: &Fingerprint, k: usize) -> Result<Vec<Hit>> {
+        use rayon::prelude::*;
+        self.check(query)?;
+        let query_count = query.popcount();
+        if k == 0 {
+            return Ok(Vec::new());
+        }
+        let score = |batch: &[usize]| batch.par_iter()
+            .fold(|| TopK::new(k), |mut top_k, &index| {
+                top_k.push(self.hit(query.words(), query_count, index));
+                top_k
+            })
+            .reduce(|| TopK::new(k), TopK::merge);
+        let mut top_k = TopK::new(k);
+        let mut batch: Vec<usize> = Vec::with_capacity(PAR_BATCH);
+        for b in self.bucket_order(query_count) {
+            if let Some(min_similarity) = top_k.min_similarity() {
+                if bound(query_count, b) < min_similarity {
+                    break;
+                }
+            }
+            batch.extend_from_slice(&self.buckets[b as usize]);
+            if batch.len() >= PAR_BATCH {
+                top_k = top_k.merge(score(&batch));
+                batch.clear();
+            }
+        }
+        Ok(top_k.merge(score(&batch)).into_hits())
+    }
+
+    /// Parallel [search_threshold](FingerprintIndex::search_threshold)
+    pub fn search_threshold_par(&self, query: &Fingerprint, min_similarity: f64) -> Result<Vec<Hit>> {
+        use rayon::prelude::*;
+        self.check(query)?;
+        let query_count = query.popcount();
+        let candidates: Vec<usize> = self.candidates(query_count, min_similarity).collect();
+        let hits = candidates.par_iter()
+            .map(|&index| self.hit(query.words(), query_count, index))
+            .filter(|hit| hit.similarity >= min_similarity)
+            .collect();
+        Ok(sorted(hits))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct FingerprintIndexRepr {
+    kind: Kind,
+    words: Vec<u32>,
+}
+
+impl From<FingerprintIndex> for FingerprintIndexRepr {
+    fn from(index: FingerprintIndex) -> Self {
+        Self { kind: index.kind, words: index.words }
+    }
+}
+
+impl std::convert::TryFrom<FingerprintIndexRepr> for FingerprintIndex {
+    type Error = Error;
+
+    fn try_from(repr: FingerprintIndexRepr) -> Result<Self> {
+        let invalid = |message: String| Error::Parse { format: String::from("fingerprint index"), message };
+        if let Some(message) = nbits_error(*repr.kind.get_nbits()) {
+            return Err(invalid(message));
+        }
+        let mut index = FingerprintIndex::new(repr.kind)?;
+        if repr.words.len() % index.words_per_fp != 0 {
+            return Err(invalid(format!("{} words in an index of {:?}", repr.words.len(), index.kind)));
+        }
+        for words in repr.words.chunks_exact(index.words_per_fp) {
+            index.push_words(words);
+        }
+        Ok(index)
+    }
+}
+
+#[cfg(test)]
+mod test_mod_fingerprintindex {
+    use super::*;
+    use std::convert::TryFrom;
+    use crate::fingerprint::FingerprintGenerator;
+    use crate::molecule::Molecule;
+
+    const SMILES: [&str; 12] = [
+        "CCO", "CCCO", "CCCCO", "c1ccccc1", "c1ccccc1O", "c1ccccc1C", "Oc1ccc(O)cc1",
+        "CC(=O)Oc1ccccc1C(=O)O", "CC(=O)Nc1ccc(O)cc1", "CN1C=NC2=C1C(=O)N(C(=O)N2C)C", "C1CCCCC1", "CC(C)Cc1ccc(cc1)C(C)C(=O)O",
+    ];
+
+    fn fingerprints() -> Vec<Fingerprint> {
+        let fpg = FingerprintGenerator::new(Kind::FP2 { nbits: 1024 }).unwrap();
+        SMILES.iter().map(|smiles| fpg.fingerprint(&Molecule::from_smiles(smiles).unwrap())).collect()
+    }
+
+    fn index_of(fps: &[Fingerprint]) -> FingerprintIndex {
+        let mut index = FingerprintIndex::new(Kind::FP2 { nbits: 1024 }).unwrap();
+        for (i, fp) in fps.iter().enumerate() {
+            assert_eq!(index.add(fp).unwrap(), i);
+        }
+        index
+    }
+
+    fn brute_force(fps: &[Fingerprint], query: &Fingerprint) -> Vec<Hit> {
+        sorted(fps.iter().enumerate().map(|(index, fp)| Hit { index, similarity: query.tanimoto(fp).unwrap() }).collect())
+    }
+
+    #[test]
+    fn test_search() {
+        let fps = fingerprints();
+        let index = index_of(&fps);
+        assert_eq!(index.len(), fps.len());
+        assert_eq!(index.get(4).unwrap(), fps[4]);
+        assert!(index.get(fps.len()).is_none());
+        for query in fps.iter() {
+            let expected = brute_force(&fps, query);
+            for k in [0, 1, 3, fps.len(), fps.len() + 5].iter() {
+                let hits = index.search_top_k(query, *k).unwrap();
+                assert_eq!(hits, expected[..(*k).min(fps.len())].to_vec());
+            }
+            for threshold in [0.0, 0.3, 0.7, 1.0].iter() {
+                let hits = index.search_threshold(query, *threshold).unwrap();
+                let expected_hits: Vec<Hit> = expected.iter().filter(|hit| hit.similarity >= *threshold).cloned().collect();
+                assert_eq!(hits, expected_hits);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bound() {
+        for fp_a in fingerprints().iter() {
+            for fp_b in fingerprints().iter() {
+                assert!(fp_a.tanimoto(fp_b).unwrap() <= bound(fp_a.popcount(), fp_b.popcount()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_incompatible() {
+        let fps = fingerprints();
+        let mut index = index_of(&fps);
+        let other_kind = Fingerprint::from_words(Kind::ECFP4 { nbits: 1024 }, fps[0].words().to_vec());
+        assert!(matches!(index.add(&other_kind), Err(Error::IncompatibleFingerprint(_))));
+        assert!(matches!(index.search_top_k(&fps[0].fold(512), 1), Err(Error::IncompatibleFingerprint(_))));
+        for nbits in [0u32, 1000, 1 << 30].iter() {
+            assert!(matches!(FingerprintIndex::new(Kind::FP2 { nbits: *nbits }), Err(Error::IncompatibleFingerprint(_))));
+        }
+    }
+
+    #[test]
+    fn test_serialization() {
+        let fps = fingerprints();
+        let index = index_of(&fps);
+        let mut buffer = Vec::new();
+        index.write(&mut buffer).unwrap();
+        assert_eq!(buffer.len(), 8 + 1 + 3 + 4 + 8 + fps.len() * 128);
+        let reloaded = FingerprintIndex::read(buffer.as_slice()).unwrap();
+        assert_eq!(reloaded.kind(), index.kind());
+        assert_eq!(reloaded.search_top_k(&fps[3], 5).unwrap(), index.search_top_k(&fps[3], 5).unwrap());
+        assert!(matches!(FingerprintIndex::read(&b"OBFPIDX0"[..]), Err(Error::Parse { .. })));
+        assert!(matches!(FingerprintIndex::read(&buffer[..20]), Err(Error::Io(_))));
+        assert!(matches!(FingerprintIndex::read(&buffer[..buffer.len() - 1]), Err(Error::Parse { .. })));
+        let mut trailing = buffer.clone();
+        trailing.push(0);
+        assert!(matches!(FingerprintIndex::read(trailing.as_slice()), Err(Error::Parse { .. })));
+        let mut huge_count = buffer.clone();
+        huge_count[16..24].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(matches!(FingerprintIndex::read(huge_count.as_slice()), Err(Error::Parse { .. })));
+        for nbits in [0u32, 1000, 1 << 30].iter() {
+            let mut bad_nbits = buffer.clone();
+            bad_nbits[12..16].copy_from_slice(&nbits.to_le_bytes());
+            assert!(matches!(FingerprintIndex::read(bad_nbits.as_slice()), Err(Error::Parse { .. })));
+        }
+
+        let path = std::env::temp_dir().join("openbabel_test_fingerprintindex.bin");
+        index.save(&path).unwrap();
+        let loaded = FingerprintIndex::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.get(7), index.get(7));
+
+        let json = serde_json::to_string(&index).unwrap();
+        let from_json: FingerprintIndex = serde_json::from_str(&json).unwrap();
+        assert_eq!(from_json.search_threshold(&fps[8], 0.2).unwrap(), index.search_threshold(&fps[8], 0.2).unwrap());
+        for json in [r#"{"kind":{"FP2":{"nbits":1000}},"words":[]}"#, r#"{"kind":{"FP2":{"nbits":64}},"words":[1,2,3]}"#].iter() {
+            let repr: FingerprintIndexRepr = serde_json::from_str(json).unwrap();
+            assert!(matches!(FingerprintIndex::try_from(repr), Err(Error::Parse { .. })));
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_search_par() {
+        let fps = fingerprints();
+        let mut index = index_of(&fps);
+        for _ in 0..2000 {  // more than one parallel batch
+            for fp in fps.iter() {
+                index.add(fp).unwrap();
+            }
+        }
+        for query in fps.iter() {
+            assert_eq!(index.search_top_k_par(query, 10).unwrap(), index.search_top_k(query, 10).unwrap());
+            assert_eq!(index.search_threshold_par(query, 0.5).unwrap(), index.search_threshold(query, 0.5).unwrap());
+        }
+    }
+}
@@ -5,14 +5,69 @@
 //! use openbabel::molecule;
 //! use openbabel::forcefields::{ForceField, ForceFieldVariant, Method};
 //!
-//! let mut mol = molecule::Molecule::new_from_smiles("c1ccccc1");
-//! let force_field = ForceField::new(ForceFieldVariant::GAFF, &mut mol);
-//! force_field.optimize(Method::ConjugateGradient, 4, 1e-5);
+//! let mut mol = molecule::Molecule::from_smiles("c1ccccc1").unwrap();
+//! let force_field = ForceField::new(ForceFieldVariant::GAFF, &mut mol).unwrap();
+//! force_field.optimize(Method::ConjugateGradient, 4, 1e-5).unwrap();
 //! ```
+//!
+//! # Fall back to another force field
+//! ```
+//! use openbabel::molecule::Molecule;
+//! use openbabel::forcefields::{ForceField, ForceFieldVariant};
+//! use openbabel::Error;
+//!
+//! let mut mol = Molecule::from_smiles("C[Sn](C)(C)C").unwrap();
+//! match ForceField::new(ForceFieldVariant::MMFF94, &mut mol) {
+//!     Err(Error::ForceFieldSetup { untyped_atoms, .. }) => assert_eq!(untyped_atoms, vec![1]),
+//!     _ => unreachable!(),
+//! }
+//! let force_field = ForceField::new(ForceFieldVariant::UFF, &mut mol).unwrap();
+//! ```
+//!
+//! # Optimize with constraints
+//! ```
+//! use openbabel::molecule::Molecule;
+//! use openbabel::forcefields::{Constraints, ForceField, ForceFieldVariant, Method};
+//!
+//! let mut mol = Molecule::from_smiles("CCCC").unwrap();
+//! let constraints = Constraints::new()
+//!     .fix_atom(0)
+//!     .torsion(0, 1, 2, 3, 60.0);
+//! let force_field = ForceField::new_with_constraints(ForceFieldVariant::UFF, &mut mol, &constraints).unwrap();
+//! force_field.optimize(Method::ConjugateGradient, 100, 1e-6).unwrap();
+//! ```
+//!
+//! # Search conformers
+//! ```
+//! use openbabel::molecule::Molecule;
+//! use openbabel::forcefields::{ConformerSearch, ForceField, ForceFieldVariant};
+//! use openbabel::io::formats::InputFormat;
+//!
+//! let xyz = "4
+//! butane, heavy atoms
+//! C 0.000 0.000 0.000
+//! C 1.530 0.000 0.000
+//! C 2.041 1.442 0.000
+//! C 1.637 2.127 1.307";
+//! let mut mol = Molecule::from_str(xyz, InputFormat::xyz).unwrap();
+//! let force_field = ForceField::new(ForceFieldVariant::UFF, &mut mol).unwrap();
+//! let search = ConformerSearch::systematic().geometry_steps(100).prune_rmsd(0.1).energy_window(50.0);
+//! let conformers = force_field.search_conformers(&search).unwrap();
+//! assert!(conformers.windows(2).all(|pair| pair[0].energy <= pair[1].energy));
+//! assert_eq!(mol.num_conformers() as usize, conformers.len());
+//! ```
+//!
+//! The force field optimizes its own copy of the coordinates. [ForceField::optimize] copies
+//! them back into the molecule when it is done, unless turned off with
+//! [ForceField::set_update_coordinates]; after stepwise optimization, call
+//! [ForceField::update_coordinates].
 
 
 
+use crate::error::{Error, Result};
+use crate::errorlog;
 use crate::molecule::Molecule;
+use crate::utils::ob_lock;
 use ob_rs::ob;
 
 #[derive(Eq, PartialEq)]
@@ -53,33 +108,445 @@ pub enum Method {
     SteepestDescent,
 }
 
+/// Unit of the energies of a force field: kcal/mol for MMFF94 and MMFF94s, kJ/mol for the others
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EnergyUnit {
+    KcalPerMol,
+    KJPerMol,
+    /// Any other unit reported by `OBForceField::GetUnit`
+    Other(String),
+}
+
+impl EnergyUnit {
+    fn from_name(name: &str) -> Self {
+        match name {
+            "kcal/mol" => EnergyUnit::KcalPerMol,
+            "kJ/mol" => EnergyUnit::KJPerMol,
+            _ => EnergyUnit::Other(String::from(name)),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            EnergyUnit::KcalPerMol => "kcal/mol",
+            EnergyUnit::KJPerMol => "kJ/mol",
+            EnergyUnit::Other(name) => name,
+        }
+    }
+
+    /// Converts `energy` from this unit into `unit`, `None` if no factor is known between them
+    pub fn convert(&self, energy: f64, unit: &EnergyUnit) -> Option<f64> {
+        const KJ_PER_KCAL: f64 = 4.184;
+        match (self, unit) {
+            (EnergyUnit::KcalPerMol, EnergyUnit::KJPerMol) => Some(energy * KJ_PER_KCAL),
+            (EnergyUnit::KJPerMol, EnergyUnit::KcalPerMol) => Some(energy / KJ_PER_KCAL),
+            _ if self == unit => Some(energy),
+            _ => None,
+        }
+    }
+}
+
+/// Energy of each interaction type, in [ForceField::energy_unit]. Terms a force field does not
+/// have are 0, e.g. `stretch_bend` outside MMFF94.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EnergyTerms {
+    pub bond: f64,
+    pub angle: f64,
+    pub stretch_bend: f64,
+    pub torsion: f64,
+    pub out_of_plane: f64,
+    pub van_der_waals: f64,
+    pub electrostatic: f64,
+}
+
+impl EnergyTerms {
+    /// Sum of the terms, equal to [ForceField::energy] without [Constraints] (the energy also
+    /// counts the restraints)
+    pub fn total(&self) -> f64 {
+        self.bond + self.angle + self.stretch_bend + self.torsion + self.out_of_plane + self.van_der_waals + self.electrostatic
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Constraint {
+    Ignore(usize),
+    Fix(usize),
+    FixX(usize),
+    FixY(usize),
+    FixZ(usize),
+    /// Target value and force constant of a restraint
+    Distance([usize; 2], f64, Option<f64>),
+    Angle([usize; 3], f64, Option<f64>),
+    Torsion([usize; 4], f64, Option<f64>),
+}
+
+impl Constraint {
+    fn atoms(&self) -> &[usize] {
+        match self {
+            Constraint::Ignore(idx) | Constraint::Fix(idx) | Constraint::FixX(idx) | Constraint::FixY(idx) | Constraint::FixZ(idx) => std::slice::from_ref(idx),
+            Constraint::Distance(atoms, ..) => atoms,
+            Constraint::Angle(atoms, ..) => atoms,
+            Constraint::Torsion(atoms, ..) => atoms,
+        }
+    }
+
+    fn force_constant(&self) -> Option<f64> {
+        match *self {
+            Constraint::Distance(.., k) | Constraint::Angle(.., k) | Constraint::Torsion(.., k) => k,
+            _ => None,
+        }
+    }
+}
+
+/// Fixed atoms and geometric restraints for [ForceField::new_with_constraints] (OBFFConstraints).
+/// Atom indices are 0-based, distances in Ångström and angles in degrees.
+///
+/// The restraints are harmonic. OpenBabel has a single force constant for all restraints of a
+/// force field: a constant given to one restraint applies to all of them, and
+/// [ForceField::new_with_constraints] fails if restraints are given different constants.
+#[derive(Clone, Debug, Default)]
+pub struct Constraints {
+    constraints: Vec<Constraint>,
+    force_constant: Option<f64>,
+}
+
+impl Constraints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Leaves the atom out of the force field, as if it did not exist
+    pub fn ignore_atom(mut self, idx: usize) -> Self {
+        self.constraints.push(Constraint::Ignore(idx));
+        self
+    }
+
+    /// Keeps the atom at its position
+    pub fn fix_atom(mut self, idx: usize) -> Self {
+        self.constraints.push(Constraint::Fix(idx));
+        self
+    }
+
+    /// Keeps the x coordinate of the atom
+    pub fn fix_x(mut self, idx: usize) -> Self {
+        self.constraints.push(Constraint::FixX(idx));
+        self
+    }
+
+    /// Keeps the y coordinate of the atom
+    pub fn fix_y(mut self, idx: usize) -> Self {
+        self.constraints.push(Constraint::FixY(idx));
+        self
+    }
+
+    /// Keeps the z coordinate of the atom
+    pub fn fix_z(mut self, idx: usize) -> Self {
+        self.constraints.push(Constraint::FixZ(idx));
+        self
+    }
+
+    /// Restrains the distance between atoms `a` and `b` to `length`
+    pub fn distance(mut self, a: usize, b: usize, length: f64) -> Self {
+        self.constraints.push(Constraint::Distance([a, b], length, None));
+        self
+    }
+
+    /// Restrains the angle a-b-c to `angle`
+    pub fn angle(mut self, a: usize, b: usize, c: usize, angle: f64) -> Self {
+        self.constraints.push(Constraint::Angle([a, b, c], angle, None));
+        self
+    }
+
+    /// Restrains the torsion a-b-c-d to `torsion`
+    pub fn torsion(mut self, a: usize, b: usize, c: usize, d: usize, torsion: f64) -> Self {
+        self.constraints.push(Constraint::Torsion([a, b, c, d], torsion, None));
+        self
+    }
+
+    /// [distance](Constraints::distance) with force constant `k`, see [Constraints] for the limit
+    pub fn distance_with_force_constant(mut self, a: usize, b: usize, length: f64, k: f64) -> Self {
+        self.constraints.push(Constraint::Distance([a, b], length, Some(k)));
+        self
+    }
+
+    /// [angle](Constraints::angle) with force constant `k`, see [Constraints] for the limit
+    pub fn angle_with_force_constant(mut self, a: usize, b: usize, c: usize, angle: f64, k: f64) -> Self {
+        self.constraints.push(Constraint::Angle([a, b, c], angle, Some(k)));
+        self
+    }
+
+    /// [torsion](Constraints::torsion) with force constant `k`, see [Constraints] for the limit
+    pub fn torsion_with_force_constant(mut self, a: usize, b: usize, c: usize, d: usize, torsion: f64, k: f64) -> Self {
+        self.constraints.push(Constraint::Torsion([a, b, c, d], torsion, Some(k)));
+        self
+    }
+
+    /// Force constant of all restraints, in the energy unit of the force field per Å² or
+    /// per degree², 50000 by default. Must agree with the constants given to single restraints.
+    pub fn force_constant(mut self, k: f64) -> Self {
+        self.force_constant = Some(k);
+        self
+    }
+
+    pub fn len(&self) -> usize { self.constraints.len() }
+    pub fn is_empty(&self) -> bool { self.constraints.is_empty() }
+
+    fn to_ob(&self, mol: &Molecule) -> Result<cxx::UniquePtr<ob::OBFFConstraints>> {
+        let num_atoms = mol.num_atoms() as usize;
+        if let Some(constraint) = self.constraints.iter().find(|constraint| constraint.atoms().iter().any(|&idx| idx >= num_atoms)) {
+            return Err(Error::Setup(format!("{:?} refers to an atom beyond the {} atoms of the molecule", constraint, num_atoms)));
+        }
+        // OBFFConstraints::SetFactor is the one force constant of every restraint
+        let mut force_constants = self.force_constant.into_iter().chain(self.constraints.iter().filter_map(Constraint::force_constant));
+        let force_constant = force_constants.next();
+        if let Some(k) = force_constants.find(|&k| Some(k) != force_constant) {
+            return Err(Error::Setup(format!(
+                "OpenBabel applies one force constant to all restraints, found {} and {}", force_constant.unwrap_or_default(), k
+            )));
+        }
+        let ob_constraints = ob::OBFFConstraints_new();
+        if let Some(k) = force_constant {
+            ob::OBFFConstraints_set_factor(&ob_constraints, k);
+        }
+        let ob_idx = |idx: usize| idx as u32 + 1;
+        for constraint in self.constraints.iter() {
+            match *constraint {
+                Constraint::Ignore(a) => ob::OBFFConstraints_add_ignore(&ob_constraints, ob_idx(a)),
+                Constraint::Fix(a) => ob::OBFFConstraints_add_atom_constraint(&ob_constraints, ob_idx(a)),
+                Constraint::FixX(a) => ob::OBFFConstraints_add_atom_x_constraint(&ob_constraints, ob_idx(a)),
+                Constraint::FixY(a) => ob::OBFFConstraints_add_atom_y_constraint(&ob_constraints, ob_idx(a)),
+                Constraint::FixZ(a) => ob::OBFFConstraints_add_atom_z_constraint(&ob_constraints, ob_idx(a)),
+                Constraint::Distance([a, b], length, _) => {
+                    ob::OBFFConstraints_add_distance_constraint(&ob_constraints, ob_idx(a), ob_idx(b), length)
+                }
+                Constraint::Angle([a, b, c], angle, _) => {
+                    ob::OBFFConstraints_add_angle_constraint(&ob_constraints, ob_idx(a), ob_idx(b), ob_idx(c), angle)
+                }
+                Constraint::Torsion([a, b, c, d], torsion, _) => {
+                    ob::OBFFConstraints_add_torsion_constraint(&ob_constraints, ob_idx(a), ob_idx(b), ob_idx(c), ob_idx(d), torsion)
+                }
+            }
+        }
+        Ok(ob_constraints)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SearchMethod {
+    Systematic,
+    Random(u32),
+    Genetic(u32),
+}
+
+/// How [ForceField::search_conformers] generates conformers and which of them it keeps.
+///
+/// The rotor searches turn the rotatable bonds to the torsions of OpenBabel's torsion library and
+/// optimize each conformer with the force field. Pruning runs on the optimized conformers.
+#[derive(Clone, Debug)]
+pub struct ConformerSearch {
+    method: SearchMethod,
+    geometry_steps: u32,
+    rmsd_threshold: Option<f64>,
+    energy_window: Option<f64>,
+    max_conformers: Option<usize>,
+}
+
+impl ConformerSearch {
+    fn new(method: SearchMethod) -> Self {
+        Self { method, geometry_steps: 2500, rmsd_threshold: None, energy_window: None, max_conformers: None }
+    }
+
+    /// Every combination of torsions (SystematicRotorSearch); their number grows exponentially
+    /// with the rotatable bonds.
+    pub fn systematic() -> Self {
+        Self::new(SearchMethod::Systematic)
+    }
+
+    /// `conformers` random combinations of torsions (RandomRotorSearch)
+    pub fn random(conformers: u32) -> Self {
+        Self::new(SearchMethod::Random(conformers))
+    }
+
+    /// Genetic algorithm of OBConformerSearch on a population of `conformers`, selecting for
+    /// diversity. Needs the `conformersearch` feature (Eigen3).
+    pub fn genetic(conformers: u32) -> Self {
+        Self::new(SearchMethod::Genetic(conformers))
+    }
+
+    /// Steps of geometry optimization of each conformer, 2500 by default
+    pub fn geometry_steps(mut self, steps: u32) -> Self {
+        self.geometry_steps = steps;
+        self
+    }
+
+    /// Drops the conformers within `rmsd` Ångström of a conformer of lower energy. The RMSD
+    /// is taken over the heavy atoms after superposition, without symmetry.
+    pub fn prune_rmsd(mut self, rmsd: f64) -> Self {
+        self.rmsd_threshold = Some(rmsd);
+        self
+    }
+
+    /// Drops the conformers more than `window` above the lowest energy, in
+    /// [ForceField::energy_unit]
+    pub fn energy_window(mut self, window: f64) -> Self {
+        self.energy_window = Some(window);
+        self
+    }
+
+    /// Keeps at most the `n` conformers of lowest energy, at least one
+    pub fn max_conformers(mut self, n: usize) -> Self {
+        self.max_conformers = Some(n);
+        self
+    }
+}
+
+/// A conformer found by [ForceField::search_conformers]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conformer {
+    pub coordinates: Vec<[f64; 3]>,
+    /// In [ForceField::energy_unit]
+    pub energy: f64,
+}
+
+/// RMSD of two sets of positions after optimal superposition, by Horn's quaternion method:
+/// only the largest eigenvalue of its 4x4 key matrix is needed.
+fn rmsd(a: &[[f64; 3]], b: &[[f64; 3]]) -> f64 {
+    let n = a.len() as f64;
+    let center = |xs: &[[f64; 3]]| {
+        let mut c = [0.0; 3];
+        for x in xs {
+            for k in 0..3 {
+                c[k] += x[k] / n;
+            }
+        }
+        c
+    };
+    let (ca, cb) = (center(a), center(b));
+    let mut s = [[0.0; 3]; 3];
+    let mut g = 0.0;
+    for (x, y) in a.iter().zip(b.iter()) {
+        let x = [x[0] - ca[0], x[1] - ca[1], x[2] - ca[2]];
+        let y = [y[0] - cb[0], y[1] - cb[1], y[2] - cb[2]];
+        for i in 0..3 {
+            g += x[i] * x[i] + y[i] * y[i];
+            for j in 0..3 {
+                s[i][j] += x[i] * y[j];
+            }
+        }
+    }
+    let key = [
+        [s[0][0] + s[1][1] + s[2][2], s[1][2] - s[2][1], s[2][0] - s[0][2], s[0][1] - s[1][0]],
+        [s[1][2] - s[2][1], s[0][0] - s[1][1] - s[2][2], s[0][1] + s[1][0], s[2][0] + s[0][2]],
+        [s[2][0] - s[0][2], s[0][1] + s[1][0], -s[0][0] + s[1][1] - s[2][2], s[1][2] + s[2][1]],
+        [s[0][1] - s[1][0], s[2][0] + s[0][2], s[1][2] + s[2][1], -s[0][0] - s[1][1] + s[2][2]],
+    ];
+    ((g - 2.0 * max_eigenvalue(key)).max(0.0) / n).sqrt()
+}
+
+/// Largest eigenvalue of a symmetric matrix, by Jacobi rotations
+fn max_eigenvalue(mut m: [[f64; 4]; 4]) -> f64 {
+    for _ in 0..50 {
+        let off_diagonal: f64 = (0..4).flat_map(|p| (p + 1..4).map(move |q| (p, q))).map(|(p, q)| m[p][q] * m[p][q]).sum();
+        if off_diagonal < 1e-24 {
+            break;
+        }
+        for p in 0..3 {
+            for q in p + 1..4 {
+                if m[p][q] == 0.0 {
+                    continue;
+                }
+                let theta = (m[q][q] - m[p][p]) / (2.0 * m[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                for row in m.iter_mut() {
+                    let (kp, kq) = (row[p], row[q]);
+                    row[p] = c * kp - s * kq;
+                    row[q] = s * kp + c * kq;
+                }
+                let (row_p, row_q) = (m[p], m[q]);
+                for (k, (pk, qk)) in row_p.iter().zip(row_q.iter()).enumerate() {
+                    m[p][k] = c * pk - s * qk;
+                    m[q][k] = s * pk + c * qk;
+                }
+            }
+        }
+    }
+    (0..4).map(|i| m[i][i]).fold(f64::MIN, f64::max)
+}
+
 pub struct ForceField<'a> {
     pub variant: ForceFieldVariant,
     pub mol: &'a mut Molecule,
 
     ff: cxx::UniquePtr<ob::OBForceField>,
+    constraints: Option<cxx::UniquePtr<ob::OBFFConstraints>>,
+    update_coordinates: bool,
 }
 
 impl<'a> ForceField<'a> {
     /// We take a mutable reference to `mol` to make sure that no one else is optimizing the
     /// structure of the molecule while we're doing it.
-    pub fn new(force_field: ForceFieldVariant, mol: &'a mut Molecule) -> Self {
+    ///
+    /// The force field is set up for `mol` right away. Fails with
+    /// [Error::Setup](crate::Error::Setup) if the force field is not registered in OpenBabel, or
+    /// [Error::ForceFieldSetup](crate::Error::ForceFieldSetup) if it cannot handle the molecule,
+    /// e.g. MMFF94 with atoms it has no parameters for.
+    pub fn new(force_field: ForceFieldVariant, mol: &'a mut Molecule) -> Result<Self> {
+        Self::create(force_field, mol, None)
+    }
+
+    /// Like [new](ForceField::new), with atoms fixed or restrained during every optimization.
+    /// Fails with [Error::Setup](crate::Error::Setup) if a constraint refers to an atom not in `mol`.
+    pub fn new_with_constraints(force_field: ForceFieldVariant, mol: &'a mut Molecule, constraints: &Constraints) -> Result<Self> {
+        let constraints = constraints.to_ob(mol)?;
+        Self::create(force_field, mol, Some(constraints))
+    }
+
+    fn create(force_field: ForceFieldVariant, mol: &'a mut Molecule, constraints: Option<cxx::UniquePtr<ob::OBFFConstraints>>) -> Result<Self> {
         cxx::let_cxx_string!(ff_name_cxx = force_field.as_str());
-        let ff = ob::OBForceField_find_forcefield(&ff_name_cxx);
+        let ff = {
+            let _lock = ob_lock();
+            ob::OBForceField_find_forcefield(&ff_name_cxx)
+        };
+        if ff.is_null() {
+            return Err(Error::Setup(format!("unknown force field {}", force_field.as_str())));
+        }
 
-        Self {
+        let force_field = Self {
             variant: force_field,
             mol,
             ff,
+            constraints,
+            update_coordinates: true,
+        };
+        force_field.setup_if_needed()?;
+        Ok(force_field)
+    }
+
+    /// Whether [optimize](ForceField::optimize) writes the optimized coordinates into the
+    /// molecule, on by default. Turn it off to run several optimizations before one
+    /// [update_coordinates](ForceField::update_coordinates).
+    pub fn set_update_coordinates(&mut self, update: bool) {
+        self.update_coordinates = update;
+    }
+
+    /// Copies the coordinates of the force field into the attached molecule and marks them as
+    /// 3D. Returns `false` if the force field was not set up for this molecule.
+    pub fn update_coordinates(&self) -> bool {
+        if !ob::OBForceField_get_coordinates(&self.ff, &self.mol.ob_mol) {
+            return false;
         }
+        ob::OBMol_set_dimension(&self.mol.ob_mol, 3);
+        true
     }
 
     /// Optimizes the attached molecule with [Method] for a maximum of `max_steps` or until
-    /// energy is below `nconv`.
-    pub fn optimize(&self, method: Method, max_steps: u32, nconv: f64) {
-        if ob::OBForceField_is_setup_needed(&self.ff, &self.mol.ob_mol) {
-            ob::OBForceField_setup(&self.mol.ob_mol, &self.ff);
-        }
+    /// energy is below `nconv`, then updates its coordinates (see
+    /// [set_update_coordinates](ForceField::set_update_coordinates)). Fails if the molecule was
+    /// changed since [new](ForceField::new) and the force field cannot be set up for it again.
+    pub fn optimize(&self, method: Method, max_steps: u32, nconv: f64) -> Result<()> {
+        self.setup_if_needed()?;
         match method {
             Method::ConjugateGradient => {
                 ob::OBForceField_conjugate_gradients(&self.ff, max_steps, nconv);
@@ -88,6 +555,10 @@ impl<'a> ForceField<'a> {
                 ob::OBForceField_steepest_descent(&self.ff, max_steps, nconv);
             }
         }
+        if self.update_coordinates {
+            self.update_coordinates();
+        }
+        Ok(())
     }
 
     /// Initializes the force field.
@@ -96,10 +567,8 @@ impl<'a> ForceField<'a> {
     /// energy is below `nconv`.
     ///
     /// Must be called prior to calling [optimize_n_steps](ForceField::optimize_n_steps).
-    pub fn initialize_force_field(&self, method: Method, max_steps: u32, nconv: f64) {
-        if ob::OBForceField_is_setup_needed(&self.ff, &self.mol.ob_mol) {
-            ob::OBForceField_setup(&self.mol.ob_mol, &self.ff);
-        }
+    pub fn initialize_force_field(&self, method: Method, max_steps: u32, nconv: f64) -> Result<()> {
+        self.setup_if_needed()?;
         match method {
             Method::ConjugateGradient => {
                 ob::OBForceField_conjugate_gradients_initialize(&self.ff, max_steps, nconv);
@@ -108,10 +577,12 @@ impl<'a> ForceField<'a> {
                 ob::OBForceField_steepest_descent_initialize(&self.ff, max_steps, nconv);
             }
         }
+        Ok(())
     }
 
     /// Optimizes the molecule `n` steps. Is recommended by OpenBabel if you wish to do stuff in
     /// between optimization steps as you avoid calling initialization every iteration.
+    /// The molecule keeps its coordinates until [update_coordinates](ForceField::update_coordinates).
     pub fn optimize_n_steps(&self, method: Method, n: u32) -> bool {
         match method {
             Method::ConjugateGradient => {
@@ -121,15 +592,192 @@ impl<'a> ForceField<'a> {
         }
     }
 
+    /// Atom typing uses SMARTS and the perception of the molecule, so it runs under the lock.
+    /// The optimization itself only touches the force field instance and the molecule.
+    fn setup_if_needed(&self) -> Result<()> {
+        let _lock = ob_lock();
+        // IsSetupNeeded compares the molecule with the last one set up, even if that failed
+        if !ob::OBForceField_is_setup_needed(&self.ff, &self.mol.ob_mol) && ob::OBForceField_is_valid_setup(&self.ff) {
+            return Ok(());
+        }
+        let error_count = errorlog::error_count();
+        let status = match &self.constraints {
+            Some(constraints) => ob::OBForceField_setup_with_constraints(&self.mol.ob_mol, &self.ff, constraints),
+            None => ob::OBForceField_setup(&self.mol.ob_mol, &self.ff),
+        };
+        if status == 0 {
+            return Ok(());
+        }
+        // MMFF94 types atoms by number and leaves 0 where it has none; other force fields keep
+        // a default or the input type, their errors are only known from the log
+        let untyped_atoms: Vec<usize> = match self.variant {
+            ForceFieldVariant::MMFF94 | ForceFieldVariant::MMFF94s => self.atom_types()
+                .iter()
+                .enumerate()
+                .filter(|(_, atom_type)| atom_type.as_str() == "0")
+                .map(|(idx, _)| idx)
+                .collect(),
+            _ => Vec::new(),
+        };
+        let mut reasons: Vec<String> = untyped_atoms.iter()
+            .map(|&idx| format!("no atom type for atom {} (atomic number {})", idx, self.mol.atom(idx).map_or(0, |atom| atom.atomic_num())))
+            .collect();
+        reasons.extend(errorlog::errors_since(error_count));
+        if reasons.is_empty() {
+            reasons.push(String::from("OBForceField::Setup failed"));
+        }
+        Err(Error::ForceFieldSetup { force_field: self.variant.as_str(), untyped_atoms, message: reasons.join("; ") })
+    }
+
+    /// Atom type of each atom in this force field, as assigned by the last setup; empty if the
+    /// molecule has gained or lost atoms since
+    pub fn atom_types(&self) -> Vec<String> {
+        ob::OBForceField_atom_types(&self.ff, &self.mol.ob_mol)
+    }
+
     /// Gets the energy of the attached molecule in the specified force field.
     pub fn energy(&self) -> f64 {
         ob::OBForceField_energy(&self.ff)
     }
+
+    /// [energy](ForceField::energy) split by interaction type. Sets the force field up again
+    /// first if the molecule was changed, as [optimize](ForceField::optimize) does.
+    pub fn energy_terms(&self) -> Result<EnergyTerms> {
+        self.setup_if_needed()?;
+        let terms = ob::OBForceField_energy_terms(&self.ff);
+        Ok(EnergyTerms {
+            bond: terms.bond,
+            angle: terms.angle,
+            stretch_bend: terms.str_bnd,
+            torsion: terms.torsion,
+            out_of_plane: terms.oop,
+            van_der_waals: terms.vdw,
+            electrostatic: terms.electrostatic,
+        })
+    }
+
+    /// Analytical gradient of the energy with respect to the position of each atom, in
+    /// [energy_unit](ForceField::energy_unit) per Ångström. The forces are the negated gradients.
+    /// Sets the force field up again first if the molecule was changed.
+    pub fn gradients(&self) -> Result<Vec<[f64; 3]>> {
+        self.setup_if_needed()?;
+        Ok(ob::OBForceField_gradients(&self.ff)
+            .chunks_exact(3)
+            .map(|force| [-force[0], -force[1], -force[2]])  // OpenBabel keeps the forces
+            .collect())
+    }
+
+    /// Compares the analytical gradients with numerical ones, true if they agree
+    pub fn validate_gradients(&self) -> bool {
+        ob::OBForceField_validate_gradients(&self.ff)
+    }
+
+    /// Searches the conformers of the attached molecule, starting from its 3D coordinates and
+    /// keeping its constraints. Returns them sorted by energy, lowest first, after the pruning of
+    /// `search`. A molecule without rotatable bonds has its current geometry as only conformer.
+    ///
+    /// The conformers replace those of the molecule, with their energies (see
+    /// [Molecule::conformer_energies]); the molecule and the force field are left at the lowest
+    /// one. Fails with [Error::Setup](crate::Error::Setup) if the genetic search is not built in
+    /// or cannot run on the molecule.
+    ///
+    /// Searches on several threads run side by side once their rotors are set up.
+    pub fn search_conformers(&self, search: &ConformerSearch) -> Result<Vec<Conformer>> {
+        self.setup_if_needed()?;
+        let ob_mol = &self.mol.ob_mol;
+        let steps = search.geometry_steps;
+        match search.method {
+            SearchMethod::Systematic => {
+                // the rotor list is built with SMARTS and ring perception, under the lock;
+                // turning the rotors and optimizing only touch this force field
+                let has_rotors = {
+                    let _lock = ob_lock();
+                    ob::OBForceField_systematic_rotor_search_initialize(&self.ff, steps)
+                };
+                if has_rotors {
+                    while ob::OBForceField_systematic_rotor_search_next_conformer(&self.ff, steps) {}
+                }
+            }
+            SearchMethod::Random(n) => {
+                {
+                    let _lock = ob_lock();
+                    ob::OBForceField_random_rotor_search_initialize(&self.ff, n, steps);
+                }
+                while ob::OBForceField_random_rotor_search_next_conformer(&self.ff, steps) {}
+            }
+            SearchMethod::Genetic(n) => {
+                let genetic_search = {
+                    let _lock = ob_lock();
+                    let error_count = errorlog::error_count();
+                    // OpenBabel's defaults for the children, mutability and convergence
+                    let genetic_search = ob::OBConformerSearch_setup(ob_mol, n, 5, 5, 25);
+                    if genetic_search.is_null() {
+                        let message = errorlog::errors_since(error_count).unwrap_or_else(|| String::from("OBConformerSearch failed"));
+                        return Err(Error::Setup(message));
+                    }
+                    genetic_search
+                };
+                ob::OBConformerSearch_search(&genetic_search, ob_mol);
+            }
+        }
+        if !matches!(search.method, SearchMethod::Genetic(_)) {
+            ob::OBForceField_get_conformers(&self.ff, ob_mol);
+        }
+
+        let mut conformers: Vec<Conformer> = (0..ob::OBMol_num_conformers(ob_mol))
+            .map(|idx| {
+                ob::OBMol_set_conformer(ob_mol, idx);
+                ob::OBForceField_set_coordinates(&self.ff, ob_mol);
+                if let SearchMethod::Genetic(_) = search.method {
+                    // the genetic search only sets torsions
+                    ob::OBForceField_conjugate_gradients(&self.ff, search.geometry_steps, 1e-6);
+                    ob::OBForceField_get_coordinates(&self.ff, ob_mol);
+                }
+                Conformer { coordinates: self.mol.coordinates(), energy: self.energy() }
+            })
+            .collect();
+        conformers.sort_by(|a, b| a.energy.total_cmp(&b.energy));
+
+        if let (Some(window), Some(lowest)) = (search.energy_window, conformers.first().map(|conformer| conformer.energy)) {
+            conformers.retain(|conformer| conformer.energy - lowest <= window);
+        }
+        if let Some(threshold) = search.rmsd_threshold {
+            let mut heavy_atoms: Vec<usize> = self.mol.atoms().enumerate().filter(|(_, atom)| atom.atomic_num() > 1).map(|(idx, _)| idx).collect();
+            if heavy_atoms.is_empty() {
+                heavy_atoms = (0..self.mol.num_atoms() as usize).collect();
+            }
+            let mut kept: Vec<Vec<[f64; 3]>> = vec![];
+            conformers.retain(|conformer| {
+                let positions: Vec<[f64; 3]> = heavy_atoms.iter().map(|&idx| conformer.coordinates[idx]).collect();
+                if kept.iter().any(|other| rmsd(other, &positions) <= threshold) {
+                    return false;
+                }
+                kept.push(positions);
+                true
+            });
+        }
+        if let Some(n) = search.max_conformers {
+            conformers.truncate(n.max(1));
+        }
+
+        let flat: Vec<f64> = conformers.iter().flat_map(|conformer| conformer.coordinates.iter().flatten()).cloned().collect();
+        let energies: Vec<f64> = conformers.iter().map(|conformer| conformer.energy).collect();
+        ob::OBMol_set_conformers(ob_mol, &flat);
+        ob::OBMol_set_energies(ob_mol, &energies);
+        ob::OBMol_set_dimension(ob_mol, 3);
+        ob::OBForceField_set_coordinates(&self.ff, ob_mol);
+        Ok(conformers)
+    }
+
+    pub fn energy_unit(&self) -> EnergyUnit {
+        EnergyUnit::from_name(&ob::OBForceField_get_unit(&self.ff))
+    }
 }
 
 #[cfg(test)]
 mod test_mod_forcefields {
     use super::*;
+    use crate::io::formats::InputFormat;
 
     #[test]
     fn test_direct_optimization() {
@@ -140,22 +788,252 @@ mod test_mod_forcefields {
             ForceFieldVariant::MMFF94s,
             ForceFieldVariant::UFF,
         ] {
-            let mut mol = Molecule::new_from_smiles("cc");
-            let force_field = ForceField::new(ff, &mut mol);
-            force_field.optimize(Method::ConjugateGradient, 4, 1e-5);
+            let mut mol = Molecule::from_smiles("cc").unwrap();
+            let force_field = ForceField::new(ff, &mut mol).unwrap();
+            force_field.optimize(Method::ConjugateGradient, 4, 1e-5).unwrap();
             assert!(force_field.energy() < 1e-5);
         }
     }
 
     #[test]
     fn test_stepwise_optimization() {
-        let mut mol = Molecule::new_from_smiles("S1SSSSSSS1");
-        let force_field = ForceField::new(ForceFieldVariant::GAFF, &mut mol);
-        force_field.initialize_force_field(Method::ConjugateGradient, 8, 1e-5);
+        let mut mol = Molecule::from_smiles("S1SSSSSSS1").unwrap();
+        let force_field = ForceField::new(ForceFieldVariant::GAFF, &mut mol).unwrap();
+        let before = force_field.mol.coordinates();
+        force_field.initialize_force_field(Method::ConjugateGradient, 8, 1e-5).unwrap();
         let mut count = 0;
         while force_field.optimize_n_steps(Method::ConjugateGradient, 1) {
             count += 1;
         }
         assert!(count > 0);
+        assert_eq!(before, force_field.mol.coordinates());
+        assert!(force_field.update_coordinates());
+        assert_ne!(before, force_field.mol.coordinates());
+        assert_eq!(force_field.mol.dimension(), 3);
+    }
+
+    fn distance(a: [f64; 3], b: [f64; 3]) -> f64 {
+        a.iter().zip(b.iter()).map(|(x, y)| (x - y) * (x - y)).sum::<f64>().sqrt()
+    }
+
+    /// Angle a-b-c in degrees
+    fn angle(a: [f64; 3], b: [f64; 3], c: [f64; 3]) -> f64 {
+        let dot: f64 = (0..3).map(|i| (a[i] - b[i]) * (c[i] - b[i])).sum();
+        (dot / (distance(a, b) * distance(c, b))).acos().to_degrees()
+    }
+
+    /// Torsion a-b-c-d in degrees, in (-180, 180]
+    fn torsion(a: [f64; 3], b: [f64; 3], c: [f64; 3], d: [f64; 3]) -> f64 {
+        let sub = |x: [f64; 3], y: [f64; 3]| [x[0] - y[0], x[1] - y[1], x[2] - y[2]];
+        let dot = |x: [f64; 3], y: [f64; 3]| x[0] * y[0] + x[1] * y[1] + x[2] * y[2];
+        let cross = |x: [f64; 3], y: [f64; 3]| [x[1] * y[2] - x[2] * y[1], x[2] * y[0] - x[0] * y[2], x[0] * y[1] - x[1] * y[0]];
+        let (b0, b1, b2) = (sub(a, b), sub(c, b), sub(d, c));
+        let (n1, n2) = (cross(b0, b1), cross(b1, b2));
+        let m = cross(n1, b1);
+        let len = dot(b1, b1).sqrt();
+        dot(m, n2).atan2(dot(n1, n2) * len).to_degrees()
+    }
+
+    const BUTANE: &str = "14
+butane, gauche
+C 0.000 0.000 0.000
+C 1.530 0.000 0.000
+C 2.041 1.442 0.000
+C 1.637 2.127 1.307
+H -0.364 0.514 -0.890
+H -0.364 -1.027 -0.000
+H -0.364 0.514 0.890
+H 1.893 -0.514 -0.890
+H 1.893 -0.514 0.890
+H 3.127 1.444 -0.090
+H 1.607 1.982 -0.842
+H 2.071 1.589 2.149
+H 2.001 3.155 1.307
+H 0.550 2.127 1.397";
+
+    #[test]
+    fn test_update_coordinates() {
+        let xyz = "3
+
+O 0.0 0.0 0.0
+H 1.6 0.0 0.0
+H 0.0 1.6 0.0";
+        let mut mol = Molecule::from_str(xyz, InputFormat::xyz).unwrap();
+        let force_field = ForceField::new(ForceFieldVariant::UFF, &mut mol).unwrap();
+        force_field.optimize(Method::ConjugateGradient, 500, 1e-6).unwrap();
+        let coords = force_field.mol.coordinates();
+        assert!((distance(coords[0], coords[1]) - 1.0).abs() < 0.1);
+
+        let mut mol = Molecule::from_str(xyz, InputFormat::xyz).unwrap();
+        let mut force_field = ForceField::new(ForceFieldVariant::UFF, &mut mol).unwrap();
+        force_field.set_update_coordinates(false);
+        force_field.optimize(Method::ConjugateGradient, 500, 1e-6).unwrap();
+        assert_eq!(force_field.mol.coordinates()[1], [1.6, 0.0, 0.0]);
+        assert!(force_field.update_coordinates());
+        assert!(force_field.mol.coordinates()[1][0] < 1.2);
+    }
+
+    #[test]
+    fn test_energy_terms() {
+        for (ff, unit) in [
+            (ForceFieldVariant::GAFF, EnergyUnit::KJPerMol),
+            (ForceFieldVariant::Ghemical, EnergyUnit::KJPerMol),
+            (ForceFieldVariant::MMFF94, EnergyUnit::KcalPerMol),
+            (ForceFieldVariant::UFF, EnergyUnit::KJPerMol),
+        ] {
+            let mut mol = Molecule::from_smiles("CC(=O)Nc1ccccc1").unwrap();
+            let force_field = ForceField::new(ff, &mut mol).unwrap();
+            force_field.optimize(Method::SteepestDescent, 20, 1e-5).unwrap();
+            assert_eq!(force_field.energy_unit(), unit);
+            let terms = force_field.energy_terms().unwrap();
+            let energy = force_field.energy();
+            assert!((terms.total() - energy).abs() < 1e-6 * energy.abs().max(1.0));
+            assert!(terms.bond > 0.0 && terms.angle > 0.0);
+        }
+        assert_eq!(EnergyUnit::KcalPerMol.convert(1.0, &EnergyUnit::KJPerMol), Some(4.184));
+        assert_eq!(EnergyUnit::KJPerMol.convert(4.184, &EnergyUnit::KcalPerMol), Some(1.0));
+        let hartree = EnergyUnit::from_name("Hartree");
+        assert_eq!(hartree, EnergyUnit::Other(String::from("Hartree")));
+        assert_eq!(hartree.convert(1.0, &hartree), Some(1.0));
+        assert_eq!(hartree.convert(1.0, &EnergyUnit::KJPerMol), None);
+    }
+
+    #[test]
+    fn test_gradients() {
+        let mut mol = Molecule::from_str("3
+
+O 0.0 0.0 0.0
+H 1.6 0.0 0.0
+H 0.0 1.6 0.0", InputFormat::xyz).unwrap();
+        let force_field = ForceField::new(ForceFieldVariant::UFF, &mut mol).unwrap();
+        let gradients = force_field.gradients().unwrap();
+        assert_eq!(gradients.len(), 3);
+        // stretched O-H bonds: the energy grows as the hydrogens move away from the oxygen
+        assert!(gradients[1][0] > 0.0 && gradients[2][1] > 0.0);
+        // no net force on the molecule
+        for axis in 0..3 {
+            assert!(gradients.iter().map(|g| g[axis]).sum::<f64>().abs() < 1e-6);
+        }
+
+        force_field.optimize(Method::ConjugateGradient, 500, 1e-8).unwrap();
+        assert!(force_field.gradients().unwrap().iter().flatten().all(|g| g.abs() < 0.1));
+    }
+
+    #[test]
+    fn test_constraints() {
+        let xyz = "3
+
+O 0.0 0.0 0.0
+H 1.6 0.0 0.0
+H 0.0 1.6 0.0";
+        let mut mol = Molecule::from_str(xyz, InputFormat::xyz).unwrap();
+        let constraints = Constraints::new().fix_atom(1).distance(0, 2, 1.4).force_constant(10000.0);
+        assert_eq!(constraints.len(), 2);
+        let force_field = ForceField::new_with_constraints(ForceFieldVariant::UFF, &mut mol, &constraints).unwrap();
+        force_field.optimize(Method::ConjugateGradient, 500, 1e-8).unwrap();
+        let coords = force_field.mol.coordinates();
+        assert_eq!(coords[1], [1.6, 0.0, 0.0]);
+        assert!((distance(coords[0], coords[1]) - 1.0).abs() < 0.1);
+        assert!((distance(coords[0], coords[2]) - 1.4).abs() < 0.05);
+        assert!(force_field.energy() > force_field.energy_terms().unwrap().total());
+
+        let mut mol = Molecule::from_str(xyz, InputFormat::xyz).unwrap();
+        let constraints = Constraints::new().fix_z(0).fix_z(1).fix_z(2).angle(1, 0, 2, 150.0);
+        let force_field = ForceField::new_with_constraints(ForceFieldVariant::UFF, &mut mol, &constraints).unwrap();
+        force_field.optimize(Method::ConjugateGradient, 500, 1e-8).unwrap();
+        let coords = force_field.mol.coordinates();
+        assert!(coords.iter().all(|xyz| xyz[2] == 0.0));
+        assert!((angle(coords[1], coords[0], coords[2]) - 150.0).abs() < 5.0);
+
+        let mut mol = Molecule::from_str(xyz, InputFormat::xyz).unwrap();
+        let constraints = Constraints::new().torsion(0, 1, 2, 3, 60.0);
+        assert!(matches!(ForceField::new_with_constraints(ForceFieldVariant::UFF, &mut mol, &constraints), Err(Error::Setup(_))));
+
+        // one force constant for all restraints
+        let constraints = Constraints::new().distance_with_force_constant(0, 1, 1.0, 500.0).angle_with_force_constant(1, 0, 2, 104.5, 500.0);
+        assert!(ForceField::new_with_constraints(ForceFieldVariant::UFF, &mut mol, &constraints).is_ok());
+        let constraints = Constraints::new().distance_with_force_constant(0, 1, 1.0, 500.0).angle(1, 0, 2, 104.5).force_constant(1000.0);
+        assert!(matches!(ForceField::new_with_constraints(ForceFieldVariant::UFF, &mut mol, &constraints), Err(Error::Setup(_))));
+    }
+
+    #[test]
+    fn test_setup_failure() {
+        let _lock = ob_lock();
+        errorlog::set_output_level(None);
+        let mut mol = Molecule::from_smiles("C[Sn](C)(C)C").unwrap();
+        match ForceField::new(ForceFieldVariant::MMFF94, &mut mol) {
+            Err(Error::ForceFieldSetup { force_field, untyped_atoms, message }) => {
+                assert_eq!(force_field, "mmff94");
+                assert_eq!(untyped_atoms, vec![1]);
+                assert!(message.contains("atomic number 50"));
+            }
+            _ => panic!("MMFF94 set up for an organotin"),
+        }
+        let force_field = ForceField::new(ForceFieldVariant::UFF, &mut mol).unwrap();
+        assert_eq!(force_field.atom_types().len(), 5);
+        assert!(force_field.atom_types().iter().all(|atom_type| !atom_type.is_empty()));
+        force_field.optimize(Method::SteepestDescent, 10, 1e-5).unwrap();
+
+        // the molecule is changed through the force field into one it cannot handle
+        let mut mol = Molecule::from_smiles("CC").unwrap();
+        let force_field = ForceField::new(ForceFieldVariant::MMFF94, &mut mol).unwrap();
+        force_field.mol.add_atom(50).unwrap();
+        assert!(matches!(force_field.optimize(Method::SteepestDescent, 10, 1e-5), Err(Error::ForceFieldSetup { .. })));
+        assert!(matches!(force_field.optimize(Method::SteepestDescent, 10, 1e-5), Err(Error::ForceFieldSetup { .. })));
+        assert!(matches!(force_field.energy_terms(), Err(Error::ForceFieldSetup { .. })));
+        assert!(matches!(force_field.gradients(), Err(Error::ForceFieldSetup { .. })));
+        errorlog::set_output_level(Some(errorlog::MessageLevel::Warning));
+    }
+
+    #[test]
+    fn test_rmsd() {
+        let a = [[0.0, 0.0, 0.0], [1.53, 0.0, 0.0], [2.041, 1.442, 0.0], [1.637, 2.127, 1.307]];
+        let (cos, sin) = (0.3f64.cos(), 0.3f64.sin());
+        let moved: Vec<[f64; 3]> = a.iter().map(|x| [x[0], cos * x[1] - sin * x[2] + 2.0, sin * x[1] + cos * x[2] - 1.0]).collect();
+        assert!(rmsd(&a, &moved) < 1e-6);
+        let mirrored: Vec<[f64; 3]> = a.iter().map(|x| [x[0], x[1], -x[2]]).collect();
+        assert!((rmsd(&a, &mirrored) - 0.512).abs() < 1e-3);
+        let mut shifted = a;
+        shifted[0][2] += 1.0;
+        assert!(rmsd(&a, &shifted) < 0.5);  // 0.5 without superposition
+    }
+
+    #[test]
+    fn test_search_conformers() {
+        let mut mol = Molecule::from_str(BUTANE, InputFormat::xyz).unwrap();
+        let force_field = ForceField::new(ForceFieldVariant::UFF, &mut mol).unwrap();
+        let conformers = force_field.search_conformers(&ConformerSearch::systematic().geometry_steps(200)).unwrap();
+        assert!(conformers.len() > 1);
+        assert!(conformers.windows(2).all(|pair| pair[0].energy <= pair[1].energy));
+        let anti = &conformers[0].coordinates;
+        assert!(torsion(anti[0], anti[1], anti[2], anti[3]).abs() > 165.0);
+        assert_eq!(force_field.mol.num_conformers() as usize, conformers.len());
+        assert_eq!(force_field.mol.conformer_energies(), conformers.iter().map(|conformer| conformer.energy).collect::<Vec<_>>());
+        assert_eq!(&force_field.mol.coordinates(), anti);
+        assert!((force_field.energy() - conformers[0].energy).abs() < 1e-6);
+
+        let search = ConformerSearch::systematic().geometry_steps(200).prune_rmsd(10.0);
+        assert_eq!(force_field.search_conformers(&search).unwrap().len(), 1);
+        let search = ConformerSearch::random(5).geometry_steps(200).energy_window(1.0);
+        let conformers = force_field.search_conformers(&search).unwrap();
+        assert!(conformers.iter().all(|conformer| conformer.energy - conformers[0].energy <= 1.0));
+        let search = ConformerSearch::random(5).geometry_steps(200).max_conformers(2);
+        let conformers = force_field.search_conformers(&search).unwrap();
+        assert!(!conformers.is_empty() && conformers.len() <= 2);
+        assert_eq!(force_field.mol.num_conformers() as usize, conformers.len());
+    }
+
+    #[test]
+    fn test_genetic_search() {
+        let _lock = ob_lock();
+        errorlog::set_output_level(None);
+        let mut mol = Molecule::from_str(BUTANE, InputFormat::xyz).unwrap();
+        let force_field = ForceField::new(ForceFieldVariant::UFF, &mut mol).unwrap();
+        let result = force_field.search_conformers(&ConformerSearch::genetic(10).geometry_steps(200));
+        errorlog::set_output_level(Some(errorlog::MessageLevel::Warning));
+        #[cfg(feature = "conformersearch")]
+        assert!(!result.unwrap().is_empty());
+        #[cfg(not(feature = "conformersearch"))]
+        assert!(matches!(result, Err(Error::Setup(message)) if message.contains("conformersearch")));
     }
 }
@@ -0,0 +1,89 @@
+//! Openbabel InChI
+//!
+//! IUPAC InChI and InChIKey identifiers, through OpenBabel's InChI format and the
+//! InChI library bundled with it. Needs the `inchi` feature.
+//!
+//! ```
+//! use openbabel::molecule::Molecule;
+//!
+//! let mol = Molecule::from_smiles("CCO").unwrap();
+//! assert_eq!(mol.to_inchi().unwrap(), "InChI=1S/C2H6O/c1-2-3/h3H,2H2,1H3");
+//! assert_eq!(mol.to_inchikey().unwrap(), "LFQSCWFLJHTTHZ-UHFFFAOYSA-N");
+//!
+//! let benzene = Molecule::from_inchi("InChI=1S/C6H6/c1-2-4-6-5-3-1/h1-6H").unwrap();
+//! assert_eq!(benzene.num_hvy_atoms(), 6);
+//! ```
+
+use crate::error::{Error, Result};
+use crate::errorlog;
+use crate::io::conversion::Conversion;
+use crate::io::formats::{InputFormat, OutputFormat};
+use crate::molecule::Molecule;
+use crate::utils::ob_lock;
+
+impl Molecule {
+    /// Creates a new `Molecule` from an InChI string, failing with
+    /// [Error::Parse](crate::Error::Parse) if it cannot be read.
+    pub fn from_inchi(inchi: &str) -> Result<Self> {
+        Self::from_str(inchi, InputFormat::inchi)
+    }
+
+    /// Standard InChI, e.g. "InChI=1S/CH4/h1H4"
+    pub fn to_inchi(&self) -> Result<String> {
+        self.write_identifier(OutputFormat::inchi).map(|(inchi, _)| inchi)
+    }
+
+    /// Standard InChI together with the warnings of the InChI library,
+    /// e.g. "Accepted unusual valence(s)"
+    pub fn to_inchi_with_warnings(&self) -> Result<(String, Vec<String>)> {
+        self.write_identifier(OutputFormat::inchi)
+    }
+
+    /// Standard InChIKey, e.g. "VNWKTOKETHGBQD-UHFFFAOYSA-N"
+    pub fn to_inchikey(&self) -> Result<String> {
+        self.write_identifier(OutputFormat::inchikey).map(|(inchikey, _)| inchikey)
+    }
+
+    fn write_identifier(&self, format: OutputFormat) -> Result<(String, Vec<String>)> {
+        let conv = Conversion::new();
+        let _lock = ob_lock();
+        let error_count = errorlog::error_count();
+        let warning_count = errorlog::warning_count();
+        let output = self.to_string(&conv, &format)?;
+        let identifier = output.trim_end();
+        if identifier.is_empty() {
+            let message = errorlog::errors_since(error_count).unwrap_or_else(|| String::from("OBConversion::WriteString failed"));
+            return Err(Error::Write { format: format.to_string(), message });
+        }
+        Ok((identifier.to_string(), errorlog::warnings_since(warning_count)))
+    }
+}
+
+#[cfg(test)]
+mod test_mod_inchi {
+    use super::*;
+
+    #[test]
+    fn test_inchi() {
+        let mol = Molecule::from_smiles("c1ccccc1O").unwrap();
+        let (inchi, warnings) = mol.to_inchi_with_warnings().unwrap();
+        assert_eq!(inchi, "InChI=1S/C6H6O/c7-6-4-2-1-3-5-6/h1-5,7H");
+        assert!(warnings.is_empty());
+        assert_eq!(mol.to_inchikey().unwrap(), "ISWSIDIOOBJBQZ-UHFFFAOYSA-N");
+
+        let phenol = Molecule::from_inchi(&inchi).unwrap();
+        assert_eq!(phenol.num_hvy_atoms(), 7);
+        assert_eq!(phenol.to_inchi().unwrap(), inchi);
+    }
+
+    #[test]
+    fn test_inchi_warnings() {
+        let _lock = ob_lock();
+        errorlog::set_output_level(None);
+        let mol = Molecule::from_smiles("C[C](C)(C)(C)C").unwrap();
+        let (_, warnings) = mol.to_inchi_with_warnings().unwrap();
+        assert!(!warnings.is_empty());
+        assert!(matches!(Molecule::from_inchi("not an InChI"), Err(Error::Parse { .. })));
+        errorlog::set_output_level(Some(errorlog::MessageLevel::Warning));
+    }
+}
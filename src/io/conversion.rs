@@ -2,22 +2,215 @@
 //! 
 //! Wrapper of ob::OBConversion
 //! 
+//! Format options are the ones passed to `obabel` as `-a` (input), `-x` (output) and
+//! single-letter flags (general), see `obabel -L <format>` for the options of a format.
+//! General options (`-h`, `-d`, `-p`, `--title`, `-s`, ...) transform every molecule read or
+//! written with the conversion, as `obabel` does; molecules are written from a transformed copy.
+//! A molecule filtered out by them fails to read, is written as an empty string, and is
+//! skipped by [MoleculeReader](crate::MoleculeReader) and [MoleculeWriter](crate::MoleculeWriter).
+//!
+//! # Write SMILES without the molecule title
+//! ```
+//! use openbabel::io::conversion::{Conversion, OptionType};
+//! use openbabel::io::formats::OutputFormat;
+//! use openbabel::molecule::Molecule;
+//!
+//! let mol = Molecule::from_smiles("OCC").unwrap();
+//! let conv = Conversion::new()
+//!     .with_option(OptionType::Output, "n", None)
+//!     .with_option(OptionType::Output, "c", None);
+//! assert_eq!(mol.to_string(&conv, &OutputFormat::smi).unwrap(), "CCO\n");
+//! ```
 
 use ob_rs::ob;
-use super::formats::OutputFormat;
+use super::formats::{InputFormat, OutputFormat};
+use crate::error::{Error, Result};
+use crate::utils::ob_lock;
 
+/// Kind of a format option, OBConversion::Option_type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionType {
+    /// Options of the input format, `obabel -a`
+    Input,
+    /// Options of the output format, `obabel -x`
+    Output,
+    /// Options of the conversion itself, `obabel` flags such as `-h` or `--title`
+    General,
+}
+
+impl OptionType {
+    pub(crate) fn ob_type(&self) -> u32 {
+        match self {
+            OptionType::Input => 0,
+            OptionType::Output => 1,
+            OptionType::General => 2,
+        }
+    }
+}
+
+/// `Conversion` is `Send` but not `Sync`, its options and formats are changed through `&self`.
 pub struct Conversion {
     pub ob_conv: cxx::UniquePtr<ob::OBConversion>, 
 }
 
+// SAFETY: the OBConversion is owned by this Conversion alone, reading and writing through the
+// shared format instances takes the lock of `utils::ob_lock`.
+unsafe impl Send for Conversion {}
+
 impl Conversion {
     pub fn new() -> Self {
         let ob_conv = ob::OBConversion_new();
         Self { ob_conv }
     }
 
-    pub fn set_output_format(&self, output_format: &OutputFormat) {
+    /// Input formats compiled into OpenBabel, as "id -- description"
+    pub fn supported_input_formats() -> Vec<String> {
+        let _lock = ob_lock();
+        ob::OBConversion_get_supported_input_format()
+    }
+
+    /// Output formats compiled into OpenBabel, as "id -- description"
+    pub fn supported_output_formats() -> Vec<String> {
+        let _lock = ob_lock();
+        ob::OBConversion_get_supported_output_format()
+    }
+
+    /// Fails with [Error::UnknownFormat](crate::Error::UnknownFormat) if the format is not
+    /// compiled into OpenBabel.
+    pub fn set_input_format(&self, input_format: &InputFormat) -> Result<()> {
+        cxx::let_cxx_string!(input_format_cxx = input_format.to_string());
+        let _lock = ob_lock();
+        if !ob::OBConversion_set_in_format(&self.ob_conv, &input_format_cxx) {
+            return Err(Error::UnknownFormat(input_format.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Fails with [Error::UnknownFormat](crate::Error::UnknownFormat) if the format is not
+    /// compiled into OpenBabel.
+    pub fn set_output_format(&self, output_format: &OutputFormat) -> Result<()> {
         cxx::let_cxx_string!(output_format_cxx = output_format.to_string());
-        ob::OBConversion_set_out_format(&self.ob_conv, &output_format_cxx);
+        let _lock = ob_lock();
+        if !ob::OBConversion_set_out_format(&self.ob_conv, &output_format_cxx) {
+            return Err(Error::UnknownFormat(output_format.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Adds `name`, with an optional parameter, to the options of type `opt_type`.
+    /// An option that is already set is replaced.
+    pub fn add_option(&self, opt_type: OptionType, name: &str, value: Option<&str>) {
+        cxx::let_cxx_string!(name_cxx = name);
+        cxx::let_cxx_string!(value_cxx = value.unwrap_or(""));
+        ob::OBConversion_add_option(&self.ob_conv, &name_cxx, opt_type.ob_type(), &value_cxx);
+    }
+
+    /// Builder form of [add_option](Conversion::add_option)
+    pub fn with_option(self, opt_type: OptionType, name: &str, value: Option<&str>) -> Self {
+        self.add_option(opt_type, name, value);
+        self
+    }
+
+    /// Returns false if the option was not set
+    pub fn remove_option(&self, opt_type: OptionType, name: &str) -> bool {
+        cxx::let_cxx_string!(name_cxx = name);
+        ob::OBConversion_remove_option(&self.ob_conv, &name_cxx, opt_type.ob_type())
+    }
+
+    pub fn is_option(&self, opt_type: OptionType, name: &str) -> bool {
+        cxx::let_cxx_string!(name_cxx = name);
+        ob::OBConversion_is_option(&self.ob_conv, &name_cxx, opt_type.ob_type())
+    }
+
+    /// Canonical SMILES output, `-xc`
+    pub fn canonical(self) -> Self {
+        self.with_option(OptionType::Output, "c", None)
+    }
+
+    /// SMILES output without the molecule title, `-xn`
+    pub fn without_title(self) -> Self {
+        self.with_option(OptionType::Output, "n", None)
+    }
+
+    /// MDL V3000 molfile/SDF output, `-x3`
+    pub fn sdf_v3000(self) -> Self {
+        self.with_option(OptionType::Output, "3", None)
+    }
+
+    /// PDB output without the duplicated CONECT records that encode bond orders, `-xn`
+    pub fn pdb_without_bond_orders(self) -> Self {
+        self.with_option(OptionType::Output, "n", None)
+    }
+
+    /// InChI options such as "FixedH" or "SNon", passed as `-xX`
+    pub fn inchi_options(self, options: &str) -> Self {
+        self.with_option(OptionType::Output, "X", Some(options))
+    }
+
+    /// Makes hydrogens explicit, `-h`
+    pub fn add_hydrogens(self) -> Self {
+        self.with_option(OptionType::General, "h", None)
+    }
+
+    /// Makes hydrogens implicit, `-d`; SMILES output then has no `[H]` atoms
+    pub fn delete_hydrogens(self) -> Self {
+        self.with_option(OptionType::General, "d", None)
+    }
+
+    /// Adds hydrogens appropriate for `ph`, `-p`
+    pub fn add_hydrogens_for_ph(self, ph: f64) -> Self {
+        self.with_option(OptionType::General, "p", Some(&ph.to_string()))
+    }
+
+    /// Replaces the molecule title, `--title`
+    pub fn title(self, title: &str) -> Self {
+        self.with_option(OptionType::General, "title", Some(title))
+    }
+}
+
+impl Default for Conversion {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test_mod_conversion {
+    use super::*;
+    use crate::molecule::Molecule;
+
+    #[test]
+    fn test_options() {
+        let mol = Molecule::from_smiles("OCC").unwrap();
+        let conv = Conversion::new().with_option(OptionType::Output, "n", None);
+        assert!(conv.is_option(OptionType::Output, "n"));
+        assert!(!conv.is_option(OptionType::Input, "n"));
+        assert_eq!(mol.to_string(&conv, &OutputFormat::smi).unwrap(), "OCC\n");
+
+        conv.add_option(OptionType::Output, "c", None);
+        assert_eq!(mol.to_string(&conv, &OutputFormat::smi).unwrap(), "CCO\n");
+
+        assert!(conv.remove_option(OptionType::Output, "n"));
+        assert!(!conv.remove_option(OptionType::Output, "n"));
+        assert_eq!(mol.to_string(&conv, &OutputFormat::smi).unwrap(), "CCO\t\n");
+    }
+
+    #[test]
+    fn test_general_options() {
+        let mol = Molecule::from_smiles("OCC").unwrap();
+        let conv = Conversion::new().without_title().add_hydrogens();
+        let smiles = mol.to_string(&conv, &OutputFormat::smi).unwrap();
+        assert_eq!(smiles.matches("[H]").count(), 6);
+        assert_eq!(mol.num_atoms(), 3);  // written from a copy
+
+        let conv = Conversion::new().canonical().delete_hydrogens().title("ethanol");
+        let mol = Molecule::from_smiles("[H]OC([H])([H])C([H])([H])[H]").unwrap();
+        assert_eq!(mol.to_string(&conv, &OutputFormat::smi).unwrap(), "CCO\tethanol\n");
+
+        let input = std::io::Cursor::new("OCC\nc1ccccc1\n");
+        let reader = crate::MoleculeReader::new(input, crate::InputFormat::smi).unwrap()
+            .with_option(OptionType::General, "h", None);
+        let atom_counts: Vec<u32> = reader.map(|mol| mol.unwrap().num_atoms()).collect();
+        assert_eq!(atom_counts, vec![9, 12]);
     }
 }
\ No newline at end of file
@@ -4,9 +4,22 @@
 //! Not all supported formats from Openbabel are included. New formats can be added.
 //! 
 
+use std::path::Path;
+use std::str::FromStr;
 use strum_macros::{Display, EnumCount as EnumCountMacro, EnumString};
+use crate::error::{Error, Result};
 
-#[derive(Eq, PartialEq, Display, Debug, EnumCountMacro, EnumString)]
+/// Lower-cased extension of `path`, e.g. "sdf" for "ligand.SDF".
+/// Like `obabel`, falls back on the file name if there is no extension (POSCAR, CONTCAR).
+fn extension(path: &Path) -> Result<String> {
+    path.extension()
+        .or_else(|| path.file_name())
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+        .ok_or_else(|| Error::UnknownFormat(path.display().to_string()))
+}
+
+#[derive(Clone, Eq, PartialEq, Display, Debug, EnumCountMacro, EnumString)]
 #[non_exhaustive]
 #[allow(non_camel_case_types)]
 pub enum InputFormat {
@@ -59,12 +72,57 @@ pub enum InputFormat {
     sdf,
     /// PDB format
     pdb,
+    /// Sybyl Mol2 format
+    mol2,
+    /// Sybyl Mol2 format
+    ml2,
+    /// Sybyl Mol2 format
+    sy2,
+    /// Chemical Markup Language, needs the `cml` feature
+    #[cfg(feature = "cml")]
+    cml,
+    /// Macromolecular Crystallographic Info (mmCIF, PDBx)
+    mmcif,
+    /// Macromolecular Crystallographic Info (mmCIF, PDBx)
+    mcif,
+    /// Crystallographic Information File
+    cif,
+    /// Molden format
+    molden,
+    /// Molden format
+    mold,
+    /// Molden format
+    molf,
+    /// VASP format
+    #[strum(ascii_case_insensitive)]
+    CONTCAR,
+    /// VASP format
+    #[strum(ascii_case_insensitive)]
+    POSCAR,
+    /// VASP format
+    #[strum(ascii_case_insensitive)]
+    VASP,
+    /// CASTEP format, read only
+    castep,
+    /// IUPAC InChI, needs the `inchi` feature
+    #[cfg(feature = "inchi")]
+    inchi,
+}
+
+impl InputFormat {
+    /// Guesses the format from the extension of `path`, as `obabel` does.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let ext = extension(path.as_ref())?;
+        Self::from_str(&ext).map_err(|_| Error::UnknownFormat(ext))
+    }
 }
 
 #[derive(Eq, PartialEq, Display, Debug, EnumString)]
 #[non_exhaustive]
 #[allow(non_camel_case_types)]
 pub enum OutputFormat {
+    /// Canonical SMILES format
+    can,
     /// Gaussian Input
     com,
     /// Gaussian cube format
@@ -96,4 +154,50 @@ pub enum OutputFormat {
     /// SDF format
     sd,
     sdf,
+    /// Sybyl Mol2 format
+    mol2,
+    /// Sybyl Mol2 format
+    ml2,
+    /// Sybyl Mol2 format
+    sy2,
+    /// PDB format, one MODEL per record
+    pdb,
+    /// Chemical Markup Language, needs the `cml` feature
+    #[cfg(feature = "cml")]
+    cml,
+    /// Macromolecular Crystallographic Info (mmCIF, PDBx)
+    mmcif,
+    /// Macromolecular Crystallographic Info (mmCIF, PDBx)
+    mcif,
+    /// Crystallographic Information File
+    cif,
+    /// Molden format
+    molden,
+    /// Molden format
+    mold,
+    /// Molden format
+    molf,
+    /// VASP format
+    #[strum(ascii_case_insensitive)]
+    CONTCAR,
+    /// VASP format
+    #[strum(ascii_case_insensitive)]
+    POSCAR,
+    /// VASP format
+    #[strum(ascii_case_insensitive)]
+    VASP,
+    /// IUPAC InChI, needs the `inchi` feature
+    #[cfg(feature = "inchi")]
+    inchi,
+    /// InChIKey, hashed InChI, needs the `inchi` feature
+    #[cfg(feature = "inchi")]
+    inchikey,
+}
+
+impl OutputFormat {
+    /// Guesses the format from the extension of `path`, as `obabel` does.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let ext = extension(path.as_ref())?;
+        Self::from_str(&ext).map_err(|_| Error::UnknownFormat(ext))
+    }
 }
@@ -1,16 +1,52 @@
+//! Openbabel I/O
+//!
+//! Reading and writing molecules in the formats listed by
+//! [InputFormat](formats::InputFormat) and [OutputFormat](formats::OutputFormat).
+//!
+//! # Read a single molecule from a string or a file
+//! ```
+//! use openbabel::io::formats::{InputFormat, OutputFormat};
+//! use openbabel::io::conversion::Conversion;
+//! use openbabel::molecule::Molecule;
+//!
+//! let mol = Molecule::from_str("2
+//!
+//! Au  0.0  0.0  0.0
+//! Au  1.4  2.5  0.0", InputFormat::xyz).unwrap();
+//! assert_eq!(mol.num_atoms(), 2);
+//!
+//! let conv = Conversion::new();
+//! assert_eq!(mol.to_string(&conv, &OutputFormat::smi).unwrap(), "[Au][Au]\t\n");
+//!
+//! let format = InputFormat::from_path("ligand.sdf").unwrap();
+//! assert_eq!(format, InputFormat::sdf);
+//! ```
+//!
+//! Multi-record inputs and outputs are handled by [MoleculeReader](reader::MoleculeReader)
+//! and [MoleculeWriter](writer::MoleculeWriter).
+
 pub mod formats;
 pub mod conversion;
+pub mod reader;
+pub mod writer;
 
 use std::path::Path;
+use crate::error::{Error, Result};
+use crate::errorlog;
 use crate::molecule::{ToMol, Molecule};
+use crate::utils::ob_lock;
 use ob_rs::ob;
 
-struct StringForMol {
+pub struct StringForMol {
     string: String,
     format: formats::InputFormat
 }
 
 impl StringForMol {
+    pub fn new(string: String, format: formats::InputFormat) -> Self {
+        Self { string, format }
+    }
+
     pub fn as_str(&self) -> &str {
         self.string.as_str()
     }
@@ -21,38 +57,58 @@ impl StringForMol {
 /// [InputFormat](crate::io::formats::InputFormat) lists the supported formats.
 ///
 impl ToMol for StringForMol {
-    fn to_mol(&self, conv: &conversion::Conversion) -> Result<Molecule, ()> {
+    fn to_mol(&self, conv: &conversion::Conversion) -> Result<Molecule> {
         let ob_mol = ob::OBMol_new();
         cxx::let_cxx_string!(input_cxx = self.as_str());
-        cxx::let_cxx_string!(input_format_cxx = self.format.to_string());
-        ob::OBConversion_set_in_format(&conv.ob_conv, &input_format_cxx);
+        let _lock = ob_lock();
+        conv.set_input_format(&self.format)?;
+        let error_count = errorlog::error_count();
         let read_succesful = ob::OBConversion_read_string(&conv.ob_conv, &ob_mol, &input_cxx);
         if read_succesful {
-            return Ok(Molecule { ob_mol: ob_mol });
+            return Ok(Molecule { ob_mol });
         }
-        Err(())
+        let message = errorlog::errors_since(error_count).unwrap_or_else(|| String::from("OBConversion::ReadString failed"));
+        Err(Error::Parse { format: self.format.to_string(), message })
     }
 }
 
-struct FileForMol<'a> {
+pub struct FileForMol<'a> {
     path_ref: &'a Path, 
     format: formats::InputFormat
 }
 
+impl<'a> FileForMol<'a> {
+    pub fn new(path_ref: &'a Path, format: formats::InputFormat) -> Self {
+        Self { path_ref, format }
+    }
+
+    /// Guesses the format from the file extension, see
+    /// [InputFormat::from_path](formats::InputFormat::from_path).
+    pub fn from_path(path_ref: &'a Path) -> Result<Self> {
+        let format = formats::InputFormat::from_path(path_ref)?;
+        Ok(Self { path_ref, format })
+    }
+}
+
 /// Creates a new `Molecule` by reading a file. [InputFormat](crate::io::formats::InputFormat)
 /// lists the supported input formats.
 ///
 impl ToMol for FileForMol<'_> {
-    fn to_mol(&self, conv: &conversion::Conversion) -> Result<Molecule, ()> {
+    fn to_mol(&self, conv: &conversion::Conversion) -> Result<Molecule> {
+        std::fs::metadata(self.path_ref)?;  // report a missing file as Error::Io, not a parse error
         let ob_mol = ob::OBMol_new();
-        cxx::let_cxx_string!(file_path_cxx = self.path_ref.to_str().expect("invalid path"));
-        cxx::let_cxx_string!(input_format_cxx = self.format.to_string());
-        ob::OBConversion_set_in_format(&conv.ob_conv, &input_format_cxx);
+        let file_path = self.path_ref.to_str()
+            .ok_or_else(|| Error::Io(std::io::Error::new(std::io::ErrorKind::InvalidInput, "path is not valid UTF-8")))?;
+        cxx::let_cxx_string!(file_path_cxx = file_path);
+        let _lock = ob_lock();
+        conv.set_input_format(&self.format)?;
+        let error_count = errorlog::error_count();
         let read_succesful = ob::OBConversion_read_file(&conv.ob_conv, &ob_mol, &file_path_cxx);
         if read_succesful {
             return Ok(Molecule { ob_mol });
         }
-        Err(())
+        let message = errorlog::errors_since(error_count).unwrap_or_else(|| format!("OBConversion::ReadFile({}) failed", file_path));
+        Err(Error::Parse { format: self.format.to_string(), message })
     }
 }
 
@@ -70,14 +126,14 @@ Au  1.44249783  0.83282651  2.35558910
 Au  0.00000000  3.33130605  2.35558910".to_string();
 
         let format = formats::InputFormat::xyz;
-        let string_for_mol = StringForMol { string, format };
+        let string_for_mol = StringForMol::new(string, format);
         let mol = string_for_mol.to_mol(conv).expect("unable to create Molecule from string");
         assert!(mol.is_valid());
         assert_eq!(mol.num_atoms(), 4);
         assert_eq!(mol.num_bonds(), 5);
 
         let format_out = formats::OutputFormat::smi;
-        assert_eq!(mol.to_string(&conv, &format_out), "[Au]1[Au]2[Au]1[Au]2\t\n");
+        assert_eq!(mol.to_string(&conv, &format_out).unwrap(), "[Au]1[Au]2[Au]1[Au]2\t\n");
     }
 
     #[test]
@@ -89,7 +145,7 @@ Au  0.00000000  3.33130605  2.35558910".to_string();
     fn test_file(conv: &conversion::Conversion) {
         let path_ref = std::path::Path::new("./openbabel-sys/openbabel/test/files/3o8g_uff.sdf").as_ref();
         let format = formats::InputFormat::sdf;
-        let file_for_mol = FileForMol { path_ref, format };
+        let file_for_mol = FileForMol::new(path_ref, format);
         let mol = file_for_mol.to_mol(conv).expect("unable to create Molecule from file");
         assert!(mol.is_valid());
         assert_eq!(mol.num_atoms(), 36);
@@ -113,7 +169,7 @@ Au  0.00000000  3.33130605  2.35558910".to_string();
         let path_ref =
             std::path::Path::new("./openbabel-sys/openbabel/test/files/00T_ideal.pdb").as_ref();
         let format = formats::InputFormat::pdb;
-        let file_for_mol = FileForMol { path_ref, format };
+        let file_for_mol = FileForMol::new(path_ref, format);
         let mol = file_for_mol
             .to_mol(conv)
             .expect("unable to create Molecule from file");
@@ -127,4 +183,172 @@ Au  0.00000000  3.33130605  2.35558910".to_string();
         let conv = conversion::Conversion::new();
         test_pdb(&conv);
     }
+
+    #[test]
+    fn test_guess_format() {
+        let path_ref = std::path::Path::new("./openbabel-sys/openbabel/test/files/00T_ideal.pdb");
+        let file_for_mol = FileForMol::from_path(path_ref).unwrap();
+        let mol = file_for_mol.to_mol(&conversion::Conversion::new()).unwrap();
+        assert_eq!(mol.num_atoms(), 22);
+        assert!(matches!(FileForMol::from_path(std::path::Path::new("notes.txt")), Err(Error::UnknownFormat(_))));
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let conv = conversion::Conversion::new();
+        let path_ref = std::path::Path::new("./no/such/file.sdf");
+        let file_for_mol = FileForMol::new(path_ref, formats::InputFormat::sdf);
+        assert!(matches!(file_for_mol.to_mol(&conv), Err(Error::Io(_))));
+    }
+
+    #[test]
+    fn test_guess_vasp_format() {
+        assert_eq!(formats::InputFormat::from_path("run/CONTCAR").unwrap(), formats::InputFormat::CONTCAR);
+        assert_eq!(formats::OutputFormat::from_path("run/POSCAR").unwrap(), formats::OutputFormat::POSCAR);
+        assert_eq!(formats::InputFormat::from_path("ligand.MOL2").unwrap(), formats::InputFormat::mol2);
+    }
+
+    fn assert_close(actual: &[[f64; 3]], expected: &[[f64; 3]], tolerance: f64) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected.iter()) {
+            assert!(a.iter().zip(e.iter()).all(|(x, y)| (x - y).abs() < tolerance), "{:?} != {:?}", a, e);
+        }
+    }
+
+    fn elements(mol: &Molecule) -> Vec<u32> {
+        mol.atoms().map(|atom| atom.atomic_num()).collect()
+    }
+
+    fn round_trip(mol: &Molecule, output_format: formats::OutputFormat, input_format: formats::InputFormat) -> Molecule {
+        let conv = conversion::Conversion::new();
+        let string = mol.to_string(&conv, &output_format).unwrap();
+        StringForMol::new(string, input_format).to_mol(&conv).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip_molecular_formats() {
+        let path_ref = std::path::Path::new("./openbabel-sys/openbabel/test/files/3o8g_uff.sdf");
+        let mol = FileForMol::new(path_ref, formats::InputFormat::sdf).to_mol(&conversion::Conversion::new()).unwrap();
+
+        let mol2 = round_trip(&mol, formats::OutputFormat::mol2, formats::InputFormat::mol2);
+        assert_eq!(mol2.num_atoms(), 36);
+        assert_eq!(mol2.num_bonds(), 38);
+        #[cfg(feature = "cml")]
+        {
+            let cml = round_trip(&mol, formats::OutputFormat::cml, formats::InputFormat::cml);
+            assert_eq!(cml.num_atoms(), 36);
+            assert_eq!(cml.num_bonds(), 38);
+        }
+        // bonds are perceived again from the coordinates
+        for (output_format, input_format) in [
+            (formats::OutputFormat::mmcif, formats::InputFormat::mmcif),
+            (formats::OutputFormat::molden, formats::InputFormat::molden),
+        ] {
+            let read_back = round_trip(&mol, output_format, input_format);
+            assert_eq!(elements(&read_back), elements(&mol));
+            assert_close(&read_back.coordinates(), &mol.coordinates(), 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_round_trip_crystal_formats() {
+        let string = "data_NaCl
+_cell_length_a 5.64
+_cell_length_b 5.64
+_cell_length_c 5.64
+_cell_angle_alpha 90
+_cell_angle_beta 90
+_cell_angle_gamma 90
+_symmetry_space_group_name_H-M 'P 1'
+loop_
+_atom_site_label
+_atom_site_type_symbol
+_atom_site_fract_x
+_atom_site_fract_y
+_atom_site_fract_z
+Na1 Na 0.0 0.0 0.0
+Cl1 Cl 0.5 0.5 0.5
+".to_string();
+        let mol = StringForMol::new(string, formats::InputFormat::cif).to_mol(&conversion::Conversion::new()).unwrap();
+        assert_eq!(elements(&mol), vec![11, 17]);
+        assert_close(&mol.coordinates(), &[[0.0, 0.0, 0.0], [2.82, 2.82, 2.82]], 1e-6);
+        let cell = [[5.64, 0.0, 0.0], [0.0, 5.64, 0.0], [0.0, 0.0, 5.64]];
+        assert_close(&mol.unit_cell().unwrap(), &cell, 1e-6);
+        for (output_format, input_format) in [
+            (formats::OutputFormat::cif, formats::InputFormat::cif),
+            (formats::OutputFormat::POSCAR, formats::InputFormat::POSCAR),
+        ] {
+            let read_back = round_trip(&mol, output_format, input_format);
+            assert_eq!(elements(&read_back), vec![11, 17]);
+            assert_close(&read_back.coordinates(), &mol.coordinates(), 1e-4);
+            assert_close(&read_back.unit_cell().unwrap(), &cell, 1e-4);
+        }
+    }
+
+    fn read_test_file(name: &str, format: formats::InputFormat) -> Molecule {
+        let path = std::path::Path::new("./tests/files").join(name);
+        FileForMol::new(&path, format).to_mol(&conversion::Conversion::new()).unwrap()
+    }
+
+    #[test]
+    fn test_read_vasp() {
+        let mol = read_test_file("POSCAR", formats::InputFormat::POSCAR);
+        assert_eq!(elements(&mol), vec![11, 17]);
+        assert_close(&mol.unit_cell().unwrap(), &[[0.0, 2.82, 2.82], [2.82, 0.0, 2.82], [2.82, 2.82, 0.0]], 1e-6);
+        assert_close(&mol.coordinates(), &[[0.0, 0.0, 0.0], [2.82, 2.82, 2.82]], 1e-6);
+    }
+
+    #[test]
+    fn test_read_castep() {
+        let mol = read_test_file("si.castep", formats::InputFormat::castep);
+        assert_eq!(elements(&mol), vec![14, 14]);
+        assert_close(&mol.unit_cell().unwrap(), &[[0.0, 2.715, 2.715], [2.715, 0.0, 2.715], [2.715, 2.715, 0.0]], 1e-6);
+        assert_close(&mol.coordinates(), &[[0.0, 0.0, 0.0], [1.3575, 1.3575, 1.3575]], 1e-6);
+    }
+
+    #[test]
+    fn test_read_molden() {
+        let mol = read_test_file("water.molden", formats::InputFormat::molden);
+        assert_eq!(elements(&mol), vec![8, 1, 1]);
+        assert_eq!(mol.num_bonds(), 2);
+        assert!(mol.unit_cell().is_none());
+        assert_close(&mol.coordinates(), &[[0.0, 0.0, 0.1173], [0.0, 0.7572, -0.4692], [0.0, -0.7572, -0.4692]], 1e-6);
+    }
+
+    #[test]
+    fn test_read_mmcif() {
+        let mol = read_test_file("gly.mmcif", formats::InputFormat::mmcif);
+        assert_eq!(elements(&mol), vec![7, 6, 6, 8, 8]);
+        assert_eq!(mol.num_bonds(), 4);
+        assert_close(
+            &mol.coordinates(),
+            &[[-1.195, 0.41, 0.0], [0.0, 1.25, 0.0], [1.25, 0.4, 0.0], [1.25, -0.83, 0.0], [2.33, 1.05, 0.0]],
+            1e-6,
+        );
+    }
+
+    #[test]
+    fn test_round_trip_test_files() {
+        for (name, output_format, input_format) in [
+            ("POSCAR", formats::OutputFormat::POSCAR, formats::InputFormat::POSCAR),
+            ("water.molden", formats::OutputFormat::molden, formats::InputFormat::molden),
+            ("gly.mmcif", formats::OutputFormat::mmcif, formats::InputFormat::mmcif),
+        ] {
+            let mol = read_test_file(name, input_format.clone());
+            let read_back = round_trip(&mol, output_format, input_format);
+            assert_eq!(elements(&read_back), elements(&mol), "{}", name);
+            assert_eq!(read_back.num_bonds(), mol.num_bonds(), "{}", name);
+            assert_close(&read_back.coordinates(), &mol.coordinates(), 1e-4);
+            match (read_back.unit_cell(), mol.unit_cell()) {
+                (Some(cell), Some(expected)) => assert_close(&cell, &expected, 1e-4),
+                (cell, expected) => assert_eq!(cell.is_some(), expected.is_some(), "{}", name),
+            }
+        }
+    }
+
+    #[test]
+    fn test_read_only_formats() {
+        assert!(conversion::Conversion::supported_input_formats().iter().any(|f| f.starts_with("castep ")));
+        assert!(!conversion::Conversion::supported_output_formats().iter().any(|f| f.starts_with("castep ")));
+    }
 }
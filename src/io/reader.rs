@@ -0,0 +1,197 @@
+//! Openbabel MoleculeReader
+//!
+//! Reads every record of a multi-molecule input (SDF, SMILES, multi-model PDB, ...) from a file
+//! or any [std::io::Read], one `OBConversion::Read` call per record on a persistent stream.
+//!
+//! A record that fails to parse is reported as [Error::Record](crate::Error::Record) with its
+//! index and byte offset, and reading continues with the next record.
+//!
+//! ```
+//! use openbabel::{InputFormat, MoleculeReader};
+//!
+//! let input = "CCO ethanol\nC1CC( broken\nc1ccccc1 benzene\n";
+//! let reader = MoleculeReader::new(std::io::Cursor::new(input), InputFormat::smi).unwrap();
+//! let results: Vec<_> = reader.collect();
+//! assert_eq!(results.len(), 3);
+//! assert_eq!(results[0].as_ref().unwrap().num_atoms(), 3);
+//! assert!(matches!(results[1], Err(openbabel::Error::Record { index: 1, offset: 12, .. })));
+//! assert_eq!(results[2].as_ref().unwrap().num_atoms(), 6);
+//! ```
+
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use ob_rs::ob;
+use super::conversion::OptionType;
+use super::formats::InputFormat;
+use crate::error::{Error, Result};
+use crate::errorlog;
+use crate::molecule::Molecule;
+use crate::utils::ob_lock;
+
+pub struct MoleculeReader {
+    ob_reader: cxx::UniquePtr<ob::OBStreamReader>,
+    format: InputFormat,
+    io_error: Arc<Mutex<Option<std::io::Error>>>,
+    index: usize,
+    offset: u64,
+    done: bool,
+}
+
+impl MoleculeReader {
+    /// Reads records in `format` from `source`. Fails with
+    /// [Error::UnknownFormat](crate::Error::UnknownFormat) if the format is not compiled in.
+    pub fn new<R: std::io::Read + 'static>(source: R, format: InputFormat) -> Result<Self> {
+        let rust_reader = ob_rs::RustReader::new(Box::new(source));
+        let io_error = rust_reader.error_handle();
+        cxx::let_cxx_string!(input_format_cxx = format.to_string());
+        let _lock = ob_lock();
+        let ob_reader = ob::OBStreamReader_new(Box::new(rust_reader), &input_format_cxx);
+        if ob_reader.is_null() {
+            return Err(Error::UnknownFormat(format.to_string()));
+        }
+        Ok(Self { ob_reader, format, io_error, index: 0, offset: 0, done: false })
+    }
+
+    /// Opens the file at `path` and reads records in `format` from it.
+    pub fn from_path<P: AsRef<Path>>(path: P, format: InputFormat) -> Result<Self> {
+        let file = std::fs::File::open(path)?;
+        Self::new(file, format)
+    }
+
+    /// Sets a format option for all following records, see
+    /// [Conversion::add_option](super::conversion::Conversion::add_option).
+    pub fn with_option(self, opt_type: OptionType, name: &str, value: Option<&str>) -> Self {
+        cxx::let_cxx_string!(name_cxx = name);
+        cxx::let_cxx_string!(value_cxx = value.unwrap_or(""));
+        ob::OBStreamReader_add_option(&self.ob_reader, &name_cxx, opt_type.ob_type(), &value_cxx);
+        self
+    }
+
+    /// Number of records read so far, including failed ones
+    pub fn records_read(&self) -> usize {
+        self.index
+    }
+
+    /// Byte offset in the input of the record last returned by `next`
+    pub fn record_offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+impl Iterator for MoleculeReader {
+    type Item = Result<Molecule>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let offset = ob::OBStreamReader_tell(&self.ob_reader);
+        self.offset = offset;
+        let ob_mol = ob::OBMol_new();
+        let _lock = ob_lock();
+        let error_count = errorlog::error_count();
+        let status = ob::OBStreamReader_read(&self.ob_reader, &ob_mol);
+        if let Some(err) = self.io_error.lock().unwrap().take() {
+            self.done = true;
+            return Some(Err(Error::Io(err)));
+        }
+        let index = self.index;
+        match status {
+            0 => {
+                self.done = true;
+                None
+            }
+            1 => {
+                self.index += 1;
+                Some(Ok(Molecule { ob_mol }))
+            }
+            _ => {
+                self.index += 1;
+                self.done = status < -1;  // the stream could not be moved to the next record
+                let message = errorlog::errors_since(error_count).unwrap_or_else(|| String::from("OBConversion::Read failed"));
+                Some(Err(Error::Record { index, offset, format: self.format.to_string(), message }))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_mod_reader {
+    use super::*;
+
+    #[test]
+    fn test_read_sdf() {
+        let reader = MoleculeReader::from_path("./openbabel-sys/openbabel/test/files/3o8g_uff.sdf", InputFormat::sdf).unwrap();
+        let mols: Vec<Molecule> = reader.map(|r| r.unwrap()).collect();
+        assert_eq!(mols.len(), 1);
+        assert_eq!(mols[0].num_atoms(), 36);
+    }
+
+    #[test]
+    fn test_read_multi_sdf() {
+        let sdf = "ethanol
+  manual
+
+  3  2  0  0  0  0  0  0  0  0999 V2000
+    0.0000    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+    1.5000    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+    2.0000    1.4000    0.0000 O   0  0  0  0  0  0  0  0  0  0  0  0
+  1  2  1  0  0  0  0
+  2  3  1  0  0  0  0
+M  END
+$$$$
+broken
+  manual
+
+  3  2  0  0  0  0  0  0  0  0999 V2000
+    0.0000    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+M  END
+$$$$
+methane
+  manual
+
+  1  0  0  0  0  0  0  0  0  0999 V2000
+    0.0000    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+M  END
+$$$$
+";
+        let mut reader = MoleculeReader::new(std::io::Cursor::new(sdf), InputFormat::sdf).unwrap();
+        assert_eq!(reader.next().unwrap().unwrap().num_atoms(), 3);
+        match reader.next().unwrap() {
+            Err(Error::Record { index, offset, .. }) => {
+                assert_eq!(index, 1);
+                assert_eq!(offset as usize, sdf.find("broken").unwrap());
+            }
+            _ => panic!("expected a record error"),
+        }
+        assert_eq!(reader.next().unwrap().unwrap().num_atoms(), 1);
+        assert_eq!(reader.record_offset() as usize, sdf.find("methane").unwrap());
+        assert!(reader.next().is_none());
+        assert_eq!(reader.records_read(), 3);
+    }
+
+    #[test]
+    fn test_io_error() {
+        struct FailingRead;
+        impl std::io::Read for FailingRead {
+            fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::new(std::io::ErrorKind::BrokenPipe, "disk on fire"))
+            }
+        }
+        let mut reader = MoleculeReader::new(FailingRead, InputFormat::smi).unwrap();
+        assert!(matches!(reader.next(), Some(Err(Error::Io(_)))));
+        assert!(reader.next().is_none());
+        assert!(matches!(MoleculeReader::from_path("./no/such/file.smi", InputFormat::smi), Err(Error::Io(_))));
+    }
+
+    #[test]
+    fn test_read_with_option() {
+        // -ab: no bond perception for xyz input
+        let xyz = "2\n\nAu  0.0  0.0  0.0\nAu  1.4  2.5  0.0\n";
+        let mut reader = MoleculeReader::new(std::io::Cursor::new(xyz), InputFormat::xyz).unwrap()
+            .with_option(OptionType::Input, "b", None);
+        let mol = reader.next().unwrap().unwrap();
+        assert_eq!(mol.num_atoms(), 2);
+        assert_eq!(mol.num_bonds(), 0);
+    }
+}
@@ -0,0 +1,236 @@
+//! Openbabel MoleculeWriter
+//!
+//! Writes many molecules in turn to a file or any [std::io::Write], one `OBConversion::Write`
+//! call per record on a persistent stream.
+//!
+//! Each molecule is written as soon as it is handed over. [finish](MoleculeWriter::finish)
+//! writes what follows the last record (`END` after the models of a PDB, the end of a CML
+//! document) and flushes the sink; dropping the writer finishes it silently. As the number of
+//! records is not known up front, PDB output always uses `MODEL`/`ENDMDL` records.
+//!
+//! ```
+//! use openbabel::molecule::Molecule;
+//! use openbabel::{MoleculeWriter, OutputFormat};
+//!
+//! let path = std::env::temp_dir().join("openbabel_writer_doc.smi");
+//! let mut writer = MoleculeWriter::create(&path, OutputFormat::smi).unwrap();
+//! for smiles in ["CCO", "c1ccccc1"] {
+//!     writer.write(&Molecule::from_smiles(smiles).unwrap()).unwrap();
+//! }
+//! writer.finish().unwrap();
+//! assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
+//! ```
+
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use ob_rs::ob;
+use super::conversion::OptionType;
+use super::formats::OutputFormat;
+use crate::error::{Error, Result};
+use crate::molecule::Molecule;
+use crate::utils::ob_lock;
+
+pub struct MoleculeWriter {
+    ob_writer: cxx::UniquePtr<ob::OBStreamWriter>,
+    format: OutputFormat,
+    io_error: Arc<Mutex<Option<std::io::Error>>>,
+    records_written: usize,
+    finished: bool,
+}
+
+impl MoleculeWriter {
+    /// Writes records in `format` to `sink`. Fails with
+    /// [Error::UnknownFormat](crate::Error::UnknownFormat) if the format is not compiled in.
+    pub fn new<W: std::io::Write + 'static>(sink: W, format: OutputFormat) -> Result<Self> {
+        let rust_writer = ob_rs::RustWriter::new(Box::new(sink));
+        let io_error = rust_writer.error_handle();
+        cxx::let_cxx_string!(output_format_cxx = format.to_string());
+        let _lock = ob_lock();
+        let ob_writer = ob::OBStreamWriter_new(Box::new(rust_writer), &output_format_cxx);
+        if ob_writer.is_null() {
+            return Err(Error::UnknownFormat(format.to_string()));
+        }
+        Ok(Self { ob_writer, format, io_error, records_written: 0, finished: false })
+    }
+
+    /// Creates (or truncates) the file at `path` and writes records in `format` to it.
+    pub fn create<P: AsRef<Path>>(path: P, format: OutputFormat) -> Result<Self> {
+        let file = std::fs::File::create(path)?;
+        Self::new(std::io::BufWriter::new(file), format)
+    }
+
+    /// Sets a format option for all following records, see
+    /// [Conversion::add_option](super::conversion::Conversion::add_option).
+    pub fn with_option(self, opt_type: OptionType, name: &str, value: Option<&str>) -> Self {
+        cxx::let_cxx_string!(name_cxx = name);
+        cxx::let_cxx_string!(value_cxx = value.unwrap_or(""));
+        ob::OBStreamWriter_add_option(&self.ob_writer, &name_cxx, opt_type.ob_type(), &value_cxx);
+        self
+    }
+
+    /// Writes `mol` as the next record, unless a general option filters it out.
+    pub fn write(&mut self, mol: &Molecule) -> Result<()> {
+        let status = {
+            let _lock = ob_lock();
+            ob::OBStreamWriter_write(&self.ob_writer, &mol.ob_mol)
+        };
+        self.take_io_error()?;
+        if status == 0 {
+            return Ok(());
+        }
+        if status < 0 {
+            return Err(Error::Record {
+                index: self.records_written,
+                offset: 0,
+                format: self.format.to_string(),
+                message: String::from("OBConversion::Write failed"),
+            });
+        }
+        self.records_written += 1;
+        Ok(())
+    }
+
+    /// Number of records written so far
+    pub fn records_written(&self) -> usize {
+        self.records_written
+    }
+
+    /// Writes the trailer of the format and flushes the sink.
+    pub fn finish(mut self) -> Result<()> {
+        self.close()
+    }
+
+    fn close(&mut self) -> Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        self.finished = true;
+        let finished = {
+            let _lock = ob_lock();
+            ob::OBStreamWriter_finish(&self.ob_writer)
+        };
+        self.take_io_error()?;
+        if !finished {
+            return Err(Error::Write {
+                format: self.format.to_string(),
+                message: String::from("closing the output failed"),
+            });
+        }
+        Ok(())
+    }
+
+    fn take_io_error(&self) -> Result<()> {
+        match self.io_error.lock().unwrap().take() {
+            Some(err) => Err(Error::Io(err)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for MoleculeWriter {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+#[cfg(test)]
+mod test_mod_writer {
+    use super::*;
+    use crate::io::formats::InputFormat;
+    use crate::io::reader::MoleculeReader;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[derive(Clone, Default)]
+    struct SharedBuf(Rc<RefCell<Vec<u8>>>);
+
+    impl std::io::Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedBuf {
+        fn contents(&self) -> String {
+            String::from_utf8(self.0.borrow().clone()).unwrap()
+        }
+    }
+
+    fn mols() -> Vec<Molecule> {
+        ["CCO", "c1ccccc1", "CC(=O)O"].iter().map(|s| Molecule::from_smiles(s).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_write_smiles() {
+        let buf = SharedBuf::default();
+        let mut writer = MoleculeWriter::new(buf.clone(), OutputFormat::smi).unwrap();
+        for mol in mols().iter() {
+            writer.write(mol).unwrap();
+        }
+        assert_eq!(writer.records_written(), 3);
+        writer.finish().unwrap();
+        assert_eq!(buf.contents().lines().count(), 3);
+    }
+
+    #[test]
+    fn test_write_sdf_on_drop() {
+        let buf = SharedBuf::default();
+        {
+            let mut writer = MoleculeWriter::new(buf.clone(), OutputFormat::sdf).unwrap();
+            for mol in mols().iter() {
+                writer.write(mol).unwrap();
+            }
+        }
+        assert_eq!(buf.contents().matches("$$$$").count(), 3);
+    }
+
+    #[test]
+    fn test_write_multi_model_pdb() {
+        let buf = SharedBuf::default();
+        let mut writer = MoleculeWriter::new(buf.clone(), OutputFormat::pdb).unwrap();
+        for mol in mols().iter() {
+            writer.write(mol).unwrap();
+        }
+        writer.finish().unwrap();
+        let output = buf.contents();
+        assert_eq!(output.lines().filter(|l| l.starts_with("MODEL")).count(), 3);
+        assert_eq!(output.lines().filter(|l| l.starts_with("ENDMDL")).count(), 3);
+        assert_eq!(output.lines().last(), Some("END"));
+
+        let reader = MoleculeReader::new(std::io::Cursor::new(output.into_bytes()), InputFormat::pdb).unwrap();
+        let atom_counts: Vec<u32> = reader.map(|mol| mol.unwrap().num_atoms()).collect();
+        assert_eq!(atom_counts, mols().iter().map(|mol| mol.num_atoms()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_write_with_option() {
+        let buf = SharedBuf::default();
+        let mut writer = MoleculeWriter::new(buf.clone(), OutputFormat::smi).unwrap()
+            .with_option(OptionType::Output, "n", None);
+        for mol in mols().iter() {
+            writer.write(mol).unwrap();
+        }
+        writer.finish().unwrap();
+        assert_eq!(buf.contents(), "CCO\nc1ccccc1\nCC(=O)O\n");
+    }
+
+    #[test]
+    fn test_write_error() {
+        struct FailingWrite;
+        impl std::io::Write for FailingWrite {
+            fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::new(std::io::ErrorKind::BrokenPipe, "disk full"))
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        let mut writer = MoleculeWriter::new(FailingWrite, OutputFormat::smi).unwrap();
+        writer.write(&mols()[0]).unwrap();
+        assert!(matches!(writer.finish(), Err(Error::Io(_))));
+    }
+}
@@ -1,6 +1,22 @@
+pub mod atom;
+pub mod bond;
+pub mod error;
+pub mod errorlog;
 pub mod fingerprint;
+pub mod fastsearch;
+pub mod fingerprintindex;
 pub mod forcefields;
+#[cfg(feature = "inchi")]
+pub mod inchi;
 pub mod molecule;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+pub mod patternset;
 pub mod smartspattern;
 pub mod utils;
-mod io;
+pub mod io;
+
+pub use error::{Error, Result};
+pub use io::formats::{InputFormat, OutputFormat};
+pub use io::reader::MoleculeReader;
+pub use io::writer::MoleculeWriter;
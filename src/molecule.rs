@@ -5,23 +5,104 @@
 //! ```
 //! use openbabel::molecule;
 //!
-//! let mol = molecule::Molecule::new_from_smiles("c1ccccc1");
+//! let mol = molecule::Molecule::from_smiles("c1ccccc1").unwrap();
 //! assert_eq!(mol.num_atoms(), 6);
 //! assert_eq!(mol.num_bonds(), 6);
 //! assert_eq!(mol.num_hvy_atoms(), 6);
+//! assert!(mol.atoms().all(|a| a.atomic_num() == 6 && a.is_aromatic()));
+//! ```
+//!
+//! # Build a Molecule atom by atom
+//! ```
+//! use openbabel::molecule;
+//!
+//! let mut mol = molecule::Molecule::new_empty();
+//! let c = mol.add_atom(6).unwrap();
+//! let o = mol.add_atom(8).unwrap();
+//! mol.add_bond(c, o, 1).unwrap();
+//! mol.set_implicit_h_count(c, 3);
+//! mol.set_implicit_h_count(o, 1);
+//! assert!((mol.get_mol_wt() - 32.042).abs() < 1e-3);
+//! ```
+//!
+//! # Deduplicate by canonical SMILES
+//! ```
+//! use openbabel::molecule::Molecule;
+//!
+//! let a = Molecule::from_smiles("OCC").unwrap();
+//! let b = Molecule::from_smiles("C(C)O").unwrap();
+//! assert_eq!(a.canonical_smiles().unwrap(), b.canonical_smiles().unwrap());
 //! ```
 
 use ob_rs::ob;
-use crate::io::conversion::Conversion;
-use crate::io::formats::OutputFormat;
+use crate::atom::Atom;
+use crate::bond::Bond;
+use crate::error::{Error, Result};
+use crate::errorlog;
+use std::path::Path;
+use crate::io::conversion::{Conversion, OptionType};
+use crate::io::formats::{InputFormat, OutputFormat};
+use crate::io::{FileForMol, StringForMol};
+use crate::utils::ob_lock;
 
+/// `Molecule` is `Send` but not `Sync`: OpenBabel perceives rings, aromaticity and stereo
+/// lazily, so even read-only queries may modify the underlying OBMol.
 pub struct Molecule {
     pub ob_mol: cxx::UniquePtr<ob::OBMol>,
 }
 
+// SAFETY: the OBMol is owned by this Molecule alone, and calls which touch the global state
+// of OpenBabel take the lock of `utils::ob_lock`.
+unsafe impl Send for Molecule {}
+
+/// Options of [Molecule::canonical_smiles_with]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SmilesOptions {
+    /// Keep isotopes and stereochemistry (default), off is `-xi`
+    pub isomeric: bool,
+    /// Write hydrogens which are explicit atoms of the molecule as such, `-xh`
+    pub explicit_hydrogens: bool,
+}
+
+impl Default for SmilesOptions {
+    fn default() -> Self {
+        Self { isomeric: true, explicit_hydrogens: false }
+    }
+}
+
 impl Molecule {
+    /// Creates a new `Molecule` from a SMILES string, failing with
+    /// [Error::Parse](crate::Error::Parse) if OpenBabel cannot read it.
+    pub fn from_smiles(smiles: &str) -> Result<Self> {
+        cxx::let_cxx_string!(smiles_cxx = smiles);
+        let _lock = ob_lock();
+        let error_count = errorlog::error_count();
+        let ob_mol = ob::OBMol_from_smiles(&smiles_cxx);
+        if ob_mol.is_null() {
+            let message = match errorlog::errors_since(error_count) {
+                Some(ob_message) => format!("invalid SMILES '{}': {}", smiles, ob_message),
+                None => format!("invalid SMILES '{}'", smiles),
+            };
+            return Err(Error::Parse { format: String::from("smi"), message });
+        }
+        Ok(Self { ob_mol })
+    }
+
+    /// Creates a new `Molecule` from `input` in the given [InputFormat].
+    pub fn from_str(input: &str, format: InputFormat) -> Result<Self> {
+        StringForMol::new(String::from(input), format).to_mol(&Conversion::new())
+    }
+
+    /// Creates a new `Molecule` from the first record of the file at `path`.
+    /// Use [InputFormat::from_path] to guess the format from the extension.
+    pub fn from_file<P: AsRef<Path>>(path: P, format: InputFormat) -> Result<Self> {
+        FileForMol::new(path.as_ref(), format).to_mol(&Conversion::new())
+    }
+
+    #[deprecated(since = "0.5.5", note = "use `Molecule::from_smiles`, which reports parse errors")]
     pub fn new_from_smiles(smiles: &str) -> Self {
         cxx::let_cxx_string!(smiles_cxx = smiles);
+        let _lock = ob_lock();
         Self {
             ob_mol: ob::OBMol_from_smiles(&smiles_cxx),
         }
@@ -29,9 +110,59 @@ impl Molecule {
 
     /// Returns `ob_mol` as a [String](std::String) in the specified
     /// [OutputFormat](crate::io::formats::OutputFormat) 
-    pub fn to_string(&self, conv: &Conversion, format: &OutputFormat) -> String {
-        conv.set_output_format(format);
-        ob::OBConversion_write_string(&conv.ob_conv, &self.ob_mol)
+    pub fn to_string(&self, conv: &Conversion, format: &OutputFormat) -> Result<String> {
+        let _lock = ob_lock();
+        conv.set_output_format(format)?;
+        Ok(ob::OBConversion_write_string(&conv.ob_conv, &self.ob_mol))
+    }
+
+    /// Canonical isomeric SMILES without the title, see [canonical_smiles_with](Molecule::canonical_smiles_with)
+    pub fn canonical_smiles(&self) -> Result<String> {
+        self.canonical_smiles_with(&SmilesOptions::default())
+    }
+
+    /// Canonical SMILES (OpenBabel `can` format) without the title
+    pub fn canonical_smiles_with(&self, options: &SmilesOptions) -> Result<String> {
+        let conv = Conversion::new().with_option(OptionType::Output, "n", None);
+        if !options.isomeric {
+            conv.add_option(OptionType::Output, "i", None);
+        }
+        if options.explicit_hydrogens {
+            conv.add_option(OptionType::Output, "h", None);
+        }
+        let smiles = self.to_string(&conv, &OutputFormat::can)?;
+        Ok(smiles.trim_end().to_string())
+    }
+
+    /// Symmetry class of each atom (OBGraphSym), equal for symmetry-equivalent atoms
+    pub fn symmetry_classes(&self) -> Vec<u32> {
+        let _lock = ob_lock();
+        ob::OBMol_get_symmetry_classes(&self.ob_mol)
+    }
+
+    /// 1-based canonical rank of each atom, in atom index order (CanonicalLabels). Equal
+    /// molecules get the same rank for corresponding atoms; this is not the atom order of the
+    /// canonical SMILES, which also depends on how the SMILES writer walks the graph.
+    pub fn canonical_labels(&self) -> Vec<u32> {
+        let _lock = ob_lock();
+        ob::OBMol_get_canonical_labels(&self.ob_mol)
+    }
+
+    /// Runs the lazy perceptions of OpenBabel (rings, aromaticity, hybridization, stereo)
+    /// under the lock, so that later queries neither log nor use the global typers.
+    /// The lock is only taken until the perceptions are done, edits make them run again.
+    pub(crate) fn perceive(&self) {
+        if ob::OBMol_is_perceived(&self.ob_mol) {
+            return;
+        }
+        let _lock = ob_lock();
+        ob::OBMol_perceive(&self.ob_mol);
+    }
+
+    /// Creates a `Molecule` without atoms, to be built with [add_atom](Molecule::add_atom)
+    /// and [add_bond](Molecule::add_bond).
+    pub fn new_empty() -> Self {
+        Self { ob_mol: ob::OBMol_new() }
     }
 
     pub fn is_valid(&self) -> bool {
@@ -49,10 +180,209 @@ impl Molecule {
     pub fn get_mol_wt(&self) -> f64 {
         ob::OBMol_get_mol_wt(&self.ob_mol)
     }
+
+    /// Returns the atom at the 0-based `idx`, or `None` if out of range.
+    pub fn atom(&self, idx: usize) -> Option<Atom<'_>> {
+        if idx < self.num_atoms() as usize { Some(Atom::new(self, idx)) } else { None }
+    }
+
+    /// Returns the bond at the 0-based `idx`, or `None` if out of range.
+    pub fn bond(&self, idx: usize) -> Option<Bond<'_>> {
+        if idx < self.num_bonds() as usize { Some(Bond::new(self, idx)) } else { None }
+    }
+
+    /// Returns 0 for no coordinates (e.g. read from SMILES), 2 or 3.
+    pub fn dimension(&self) -> u32 {
+        ob::OBMol_get_dimension(&self.ob_mol)
+    }
+
+    /// Marks the coordinates as 0D, 2D or 3D, which output formats such as SDF rely on.
+    pub fn set_dimension(&mut self, dim: u32) {
+        ob::OBMol_set_dimension(&self.ob_mol, dim);
+    }
+
+    /// Returns the position of every atom, in atom index order.
+    pub fn coordinates(&self) -> Vec<[f64; 3]> {
+        ob::OBMol_get_coordinates(&self.ob_mol)
+            .chunks_exact(3)
+            .map(|xyz| [xyz[0], xyz[1], xyz[2]])
+            .collect()
+    }
+
+    /// Cell vectors, one per row, of a structure read from a crystal format (CIF, VASP, CASTEP, ...)
+    pub fn unit_cell(&self) -> Option<[[f64; 3]; 3]> {
+        let flat = ob::OBMol_get_cell_vectors(&self.ob_mol);
+        if flat.len() != 9 {
+            return None;
+        }
+        Some([[flat[0], flat[1], flat[2]], [flat[3], flat[4], flat[5]], [flat[6], flat[7], flat[8]]])
+    }
+
+    /// Replaces the position of every atom. [dimension](Molecule::dimension) is left unchanged.
+    ///
+    /// # Panics
+    /// If `coords` does not hold one position per atom.
+    pub fn set_coordinates(&mut self, coords: &[[f64; 3]]) {
+        assert_eq!(coords.len(), self.num_atoms() as usize, "one position per atom is required");
+        let flat: Vec<f64> = coords.iter().flatten().cloned().collect();
+        ob::OBMol_set_coordinates(&self.ob_mol, &flat);
+    }
+
+    /// Moves the geometric center of the molecule to the origin.
+    pub fn center(&mut self) {
+        ob::OBMol_center(&self.ob_mol);
+    }
+
+    pub fn translate(&mut self, v: [f64; 3]) {
+        ob::OBMol_translate(&self.ob_mol, v[0], v[1], v[2]);
+    }
+
+    /// Applies the rotation `matrix` (row-major) to every atom position.
+    pub fn rotate(&mut self, matrix: [[f64; 3]; 3]) {
+        let flat: Vec<f64> = matrix.iter().flatten().cloned().collect();
+        ob::OBMol_rotate(&self.ob_mol, &flat);
+    }
+
+    /// Number of sets of coordinates, 1 unless conformers were stored, e.g. by
+    /// [ForceField::search_conformers](crate::forcefields::ForceField::search_conformers).
+    pub fn num_conformers(&self) -> u32 {
+        ob::OBMol_num_conformers(&self.ob_mol)
+    }
+
+    /// Returns the positions of conformer `idx`, or `None` if out of range.
+    pub fn conformer(&self, idx: usize) -> Option<Vec<[f64; 3]>> {
+        if idx >= self.num_conformers() as usize {
+            return None;
+        }
+        let coords = ob::OBMol_get_conformer(&self.ob_mol, idx as u32)
+            .chunks_exact(3)
+            .map(|xyz| [xyz[0], xyz[1], xyz[2]])
+            .collect();
+        Some(coords)
+    }
+
+    /// Makes conformer `idx` the current one, the one [coordinates](Molecule::coordinates)
+    /// returns and output formats write.
+    ///
+    /// # Panics
+    /// If `idx` is out of range.
+    pub fn set_conformer(&mut self, idx: usize) {
+        assert!(idx < self.num_conformers() as usize, "conformer index {} out of range", idx);
+        ob::OBMol_set_conformer(&self.ob_mol, idx as u32);
+    }
+
+    /// Replaces all conformers, the first one becoming the current one. Energies stored with
+    /// the previous conformers are dropped.
+    ///
+    /// # Panics
+    /// If `conformers` is empty or a conformer does not hold one position per atom.
+    pub fn set_conformers(&mut self, conformers: &[Vec<[f64; 3]>]) {
+        assert!(!conformers.is_empty(), "at least one conformer is required");
+        assert!(conformers.iter().all(|coords| coords.len() == self.num_atoms() as usize), "one position per atom is required");
+        let flat: Vec<f64> = conformers.iter().flatten().flatten().cloned().collect();
+        ob::OBMol_set_conformers(&self.ob_mol, &flat);
+        ob::OBMol_set_energies(&self.ob_mol, &[]);
+    }
+
+    /// Energy of each conformer as stored by a conformer search, empty if there are none
+    pub fn conformer_energies(&self) -> Vec<f64> {
+        ob::OBMol_get_energies(&self.ob_mol)
+    }
+
+    fn check_atom_idx(&self, idx: usize) {
+        assert!(idx < self.num_atoms() as usize, "atom index {} out of range", idx);
+    }
+
+    /// Appends an atom of element `atomic_num` and returns its 0-based index. Fails with
+    /// [Error::InvalidEdit](crate::Error::InvalidEdit) unless `atomic_num` is in 1..=118.
+    ///
+    /// The new atom carries no implicit hydrogens, see
+    /// [set_implicit_h_count](Molecule::set_implicit_h_count), and sits at the origin in every
+    /// conformer. Edits keep the conformers of the molecule.
+    pub fn add_atom(&mut self, atomic_num: u32) -> Result<usize> {
+        if !(1..=118).contains(&atomic_num) {
+            return Err(Error::InvalidEdit(format!("atomic number {} is not in 1..=118", atomic_num)));
+        }
+        Ok(ob::OBMol_add_atom(&self.ob_mol, atomic_num) as usize - 1)
+    }
+
+    /// Bonds the atoms at the 0-based indices `begin` and `end` with `order` 1, 2, 3 or 5
+    /// (aromatic) and returns the 0-based index of the new bond. Fails with
+    /// [Error::InvalidEdit](crate::Error::InvalidEdit) for any other order, or if the atoms are
+    /// identical or already bonded.
+    ///
+    /// # Panics
+    /// If either index is out of range.
+    pub fn add_bond(&mut self, begin: usize, end: usize, order: u32) -> Result<usize> {
+        self.check_atom_idx(begin);
+        self.check_atom_idx(end);
+        if !matches!(order, 1 | 2 | 3 | 5) {
+            return Err(Error::InvalidEdit(format!("bond order {} is not 1, 2, 3 or 5 (aromatic)", order)));
+        }
+        if !ob::OBMol_add_bond(&self.ob_mol, begin as u32 + 1, end as u32 + 1, order) {
+            return Err(Error::InvalidEdit(format!("atoms {} and {} are identical or already bonded", begin, end)));
+        }
+        Ok(self.num_bonds() as usize - 1)
+    }
+
+    /// Removes the atom and its bonds. Atoms after `idx` shift down by one.
+    ///
+    /// # Panics
+    /// If `idx` is out of range.
+    pub fn delete_atom(&mut self, idx: usize) {
+        self.check_atom_idx(idx);
+        ob::OBMol_delete_atom(&self.ob_mol, idx as u32 + 1);
+    }
+
+    /// Removes the bond. Bonds after `idx` shift down by one.
+    ///
+    /// # Panics
+    /// If `idx` is out of range.
+    pub fn delete_bond(&mut self, idx: usize) {
+        assert!(idx < self.num_bonds() as usize, "bond index {} out of range", idx);
+        ob::OBMol_delete_bond(&self.ob_mol, idx as u32);
+    }
+
+    /// # Panics
+    /// If `idx` is out of range.
+    pub fn set_formal_charge(&mut self, idx: usize, charge: i32) {
+        self.check_atom_idx(idx);
+        ob::OBAtom_set_formal_charge(&self.ob_mol, idx as u32 + 1, charge);
+    }
+
+    /// Sets the mass number, 0 clears it.
+    ///
+    /// # Panics
+    /// If `idx` is out of range.
+    pub fn set_isotope(&mut self, idx: usize, isotope: u32) {
+        self.check_atom_idx(idx);
+        ob::OBAtom_set_isotope(&self.ob_mol, idx as u32 + 1, isotope);
+    }
+
+    /// # Panics
+    /// If `idx` is out of range.
+    pub fn set_implicit_h_count(&mut self, idx: usize, count: u32) {
+        self.check_atom_idx(idx);
+        ob::OBAtom_set_implicit_h_count(&self.ob_mol, idx as u32 + 1, count);
+    }
+
+    pub fn atoms(&self) -> impl Iterator<Item = Atom<'_>> {
+        (0..self.num_atoms() as usize).map(move |idx| Atom::new(self, idx))
+    }
+
+    pub fn bonds(&self) -> impl Iterator<Item = Bond<'_>> {
+        (0..self.num_bonds() as usize).map(move |idx| Bond::new(self, idx))
+    }
+}
+
+impl Clone for Molecule {
+    fn clone(&self) -> Self {
+        Self { ob_mol: ob::OBMol_clone(&self.ob_mol) }
+    }
 }
 
 pub trait ToMol {
-    fn to_mol(&self, conv: &Conversion) -> Result<Molecule, ()>;
+    fn to_mol(&self, conv: &Conversion) -> Result<Molecule>;
 }
 
 #[cfg(test)]
@@ -61,7 +391,7 @@ mod test_mod_molecule {
 
     #[test]
     fn test_molecule() {
-        let mol = Molecule::new_from_smiles("CCNCC");
+        let mol = Molecule::from_smiles("CCNCC").unwrap();
         assert!(mol.is_valid());
         assert_eq!(mol.num_atoms(), 5);
         assert_eq!(mol.num_bonds(), 4);
@@ -72,4 +402,175 @@ mod test_mod_molecule {
             mol.get_mol_wt()
         );
     }
+
+    #[test]
+    fn test_edit() {
+        let mut mol = Molecule::new_empty();
+        assert_eq!(mol.num_atoms(), 0);
+        let c1 = mol.add_atom(6).unwrap();
+        let c2 = mol.add_atom(6).unwrap();
+        let n = mol.add_atom(7).unwrap();
+        assert_eq!((c1, c2, n), (0, 1, 2));
+        assert!(matches!(mol.add_atom(0), Err(Error::InvalidEdit(_))));
+        assert!(matches!(mol.add_atom(119), Err(Error::InvalidEdit(_))));
+        assert_eq!(mol.num_atoms(), 3);
+        assert_eq!(mol.add_bond(c1, c2, 1).unwrap(), 0);
+        assert_eq!(mol.add_bond(c2, n, 3).unwrap(), 1);
+        assert!(matches!(mol.add_bond(c2, n, 1), Err(Error::InvalidEdit(_))));
+        assert!(matches!(mol.add_bond(n, n, 1), Err(Error::InvalidEdit(_))));
+        assert!(matches!(mol.add_bond(c1, n, 4), Err(Error::InvalidEdit(_))));
+        assert!(matches!(mol.add_bond(c1, n, 0), Err(Error::InvalidEdit(_))));
+        assert_eq!(mol.num_bonds(), 2);
+        mol.set_implicit_h_count(c1, 3);
+        assert!((mol.get_mol_wt() - 41.053).abs() < 1e-3, "mol wt is {}", mol.get_mol_wt());
+
+        mol.set_isotope(c1, 13);
+        assert_eq!(mol.atom(c1).unwrap().isotope(), 13);
+        mol.set_formal_charge(n, -1);
+        assert_eq!(mol.atom(n).unwrap().formal_charge(), -1);
+
+        mol.delete_bond(1);
+        assert_eq!(mol.num_bonds(), 1);
+        mol.delete_atom(c1);
+        assert_eq!(mol.num_atoms(), 2);
+        assert_eq!(mol.num_bonds(), 0);
+        assert_eq!(mol.atom(1).unwrap().atomic_num(), 7);
+    }
+
+    #[test]
+    fn test_coordinates() {
+        let mut mol = Molecule::new_empty();
+        let a = mol.add_atom(8).unwrap();
+        let b = mol.add_atom(8).unwrap();
+        mol.add_bond(a, b, 2).unwrap();
+        mol.set_coordinates(&[[1.0, 1.0, 1.0], [2.2, 1.0, 1.0]]);
+        mol.set_dimension(3);
+        assert_eq!(mol.dimension(), 3);
+        assert_eq!(mol.coordinates(), vec![[1.0, 1.0, 1.0], [2.2, 1.0, 1.0]]);
+
+        mol.center();
+        let coords = mol.coordinates();
+        assert!((coords[0][0] + 0.6).abs() < 1e-9 && coords[0][1].abs() < 1e-9);
+
+        mol.translate([0.6, 0.0, 0.0]);
+        mol.rotate([[0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]]);
+        let position = mol.atom(b).unwrap().position();
+        assert!(position[0].abs() < 1e-9 && (position[1] - 1.2).abs() < 1e-9, "position is {:?}", position);
+    }
+
+    #[test]
+    fn test_conformers() {
+        let mut mol = Molecule::from_smiles("O=O").unwrap();
+        assert_eq!(mol.num_conformers(), 1);
+        mol.set_conformers(&[vec![[0.0, 0.0, 0.0], [1.2, 0.0, 0.0]], vec![[0.0, 0.0, 0.0], [0.0, 1.2, 0.0]]]);
+        assert_eq!(mol.num_conformers(), 2);
+        assert_eq!(mol.coordinates(), vec![[0.0, 0.0, 0.0], [1.2, 0.0, 0.0]]);
+        mol.set_conformer(1);
+        assert_eq!(mol.coordinates(), vec![[0.0, 0.0, 0.0], [0.0, 1.2, 0.0]]);
+        assert_eq!(mol.conformer(0), Some(vec![[0.0, 0.0, 0.0], [1.2, 0.0, 0.0]]));
+        assert_eq!(mol.conformer(2), None);
+        assert!(mol.conformer_energies().is_empty());
+
+        // edits keep the conformers, a new atom sits at the origin in each
+        let h = mol.add_atom(1).unwrap();
+        mol.add_bond(1, h, 1).unwrap();
+        mol.set_formal_charge(0, 1);
+        assert_eq!(mol.num_conformers(), 2);
+        assert_eq!(mol.coordinates(), vec![[0.0, 0.0, 0.0], [0.0, 1.2, 0.0], [0.0, 0.0, 0.0]]);
+        mol.delete_atom(0);
+        assert_eq!(mol.conformer(0), Some(vec![[1.2, 0.0, 0.0], [0.0, 0.0, 0.0]]));
+    }
+
+    #[test]
+    #[should_panic(expected = "one position per atom")]
+    fn test_set_coordinates_len() {
+        let mut mol = Molecule::from_smiles("CC").unwrap();
+        mol.set_coordinates(&[[0.0, 0.0, 0.0]]);
+    }
+
+    #[test]
+    fn test_from_str_and_file() {
+        let mol = Molecule::from_str("C1CCCCC1 cyclohexane", InputFormat::smi).unwrap();
+        assert_eq!(mol.num_atoms(), 6);
+        let mol = Molecule::from_file("./openbabel-sys/openbabel/test/files/3o8g_uff.sdf", InputFormat::sdf).unwrap();
+        assert_eq!(mol.num_atoms(), 36);
+        assert_eq!(mol.dimension(), 3);
+    }
+
+    #[test]
+    fn test_clone() {
+        let mol = Molecule::from_smiles("CCO").unwrap();
+        let mut copy = mol.clone();
+        copy.add_atom(6).unwrap();
+        assert_eq!(mol.num_atoms(), 3);
+        assert_eq!(copy.num_atoms(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range")]
+    fn test_edit_out_of_range() {
+        let mut mol = Molecule::new_empty();
+        mol.set_formal_charge(0, 1);
+    }
+
+    #[test]
+    fn test_invalid_smiles() {
+        match Molecule::from_smiles("C1CC(") {
+            Err(Error::Parse { format, message }) => {
+                assert_eq!(format, "smi");
+                assert!(message.contains("C1CC("));
+            }
+            _ => panic!("expected a parse error"),
+        }
+    }
+
+    #[test]
+    fn test_canonical_smiles() {
+        let a = Molecule::from_smiles("OCC").unwrap();
+        let b = Molecule::from_smiles("C(O)C").unwrap();
+        assert_eq!(a.canonical_smiles().unwrap(), "CCO");
+        assert_eq!(b.canonical_smiles().unwrap(), "CCO");
+
+        let chiral = Molecule::from_smiles("N[C@@H](C)O").unwrap();
+        assert!(chiral.canonical_smiles().unwrap().contains('@'));
+        let options = SmilesOptions { isomeric: false, ..SmilesOptions::default() };
+        assert!(!chiral.canonical_smiles_with(&options).unwrap().contains('@'));
+
+        let explicit = Molecule::from_smiles("[H]OC").unwrap();
+        assert_eq!(explicit.canonical_smiles().unwrap(), "CO");
+        let options = SmilesOptions { explicit_hydrogens: true, ..SmilesOptions::default() };
+        assert!(explicit.canonical_smiles_with(&options).unwrap().contains("[H]"));
+    }
+
+    #[test]
+    fn test_canonical_labels() {
+        let a = Molecule::from_smiles("OCC").unwrap();
+        let b = Molecule::from_smiles("CCO").unwrap();
+        assert_eq!(a.canonical_labels()[0], b.canonical_labels()[2]);
+        let mut labels = a.canonical_labels();
+        labels.sort();
+        assert_eq!(labels, vec![1, 2, 3]);
+
+        let benzene = Molecule::from_smiles("c1ccccc1").unwrap();
+        let classes = benzene.symmetry_classes();
+        assert_eq!(classes.len(), 6);
+        assert!(classes.iter().all(|c| *c == classes[0]));
+    }
+
+    #[test]
+    fn test_send_between_threads() {
+        let handles: Vec<_> = (0..8)
+            .map(|i| std::thread::spawn(move || {
+                let mol = Molecule::from_smiles(&format!("c1ccccc1{}", "C".repeat(i))).unwrap();
+                let smiles = mol.canonical_smiles().unwrap();
+                (mol, smiles)
+            }))
+            .collect();
+        for (i, handle) in handles.into_iter().enumerate() {
+            let (mol, smiles) = handle.join().unwrap();
+            assert_eq!(mol.num_atoms(), 6 + i as u32);
+            assert!(mol.atom(0).unwrap().is_aromatic());
+            assert_eq!(mol.canonical_smiles().unwrap(), smiles);
+        }
+    }
 }
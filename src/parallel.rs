@@ -0,0 +1,118 @@
+//! Openbabel Parallel Batches
+//!
+//! Batch substructure search on the rayon thread pool (`rayon` feature). Results come back in
+//! input order, with the error of each item that fails. Run the calls inside
+//! `rayon::ThreadPool::install` to use a pool other than the global one.
+//!
+//! ```
+//! use openbabel::molecule::Molecule;
+//! use openbabel::parallel;
+//! use openbabel::smartspattern::SmartsPattern;
+//!
+//! let mols = vec![Molecule::from_smiles("CCO").unwrap(), Molecule::from_smiles("c1ccccc1O").unwrap()];
+//! let patterns = vec![SmartsPattern::from_smarts("[OX2H]").unwrap(), SmartsPattern::from_smarts("c").unwrap()];
+//! let matches = parallel::find_matches(&patterns, &mols);
+//! let ethanol = matches[0].as_ref().unwrap();
+//! assert_eq!(ethanol[0].len(), 1);  // one hydroxyl
+//! assert_eq!(ethanol[1].len(), 0);  // no aromatic carbon
+//! assert_eq!(matches[1].as_ref().unwrap()[1].len(), 6);
+//!
+//! let matches = parallel::find_matches_for_smiles(&patterns, &["CCO", "C1CC("]);
+//! assert!(matches[1].is_err());
+//! ```
+//!
+//! Matching of SMARTS without recursion runs in parallel. Calls which touch the global state
+//! of OpenBabel are serialized: parsing, the first perception of each molecule and matching of
+//! recursive SMARTS (`$(...)`), see the thread notes of [Molecule] and [SmartsPattern].
+//! Parsing and fingerprints therefore have no batch version here: they stay serial, use
+//! [Molecule::from_smiles] and [FingerprintGenerator::get_fingerprint_for_smiles_vec](crate::fingerprint::FingerprintGenerator::get_fingerprint_for_smiles_vec).
+
+use rayon::prelude::*;
+use crate::error::{Error, Result};
+use crate::molecule::Molecule;
+use crate::smartspattern::SmartsPattern;
+
+/// Borrowed molecule handed to exactly one rayon task
+struct MolRef<'a>(&'a Molecule);
+
+// SAFETY: `Molecule` is not `Sync`, so all other references to the molecules stay on the calling
+// thread, which is blocked in the batch call; each molecule is then used by a single task.
+unsafe impl Send for MolRef<'_> {}
+
+fn mol_refs(mols: &[Molecule]) -> Vec<MolRef<'_>> {
+    mols.iter().map(MolRef).collect()
+}
+
+fn match_all(patterns: &[SmartsPattern], mol: &Molecule) -> Result<Vec<Vec<Vec<usize>>>> {
+    patterns.iter()
+        .map(|sp| {
+            if !sp.is_valid() {
+                let message = format!("invalid SMARTS '{}'", sp.smarts());
+                return Err(Error::Parse { format: String::from("smarts"), message });
+            }
+            Ok(sp.find_match(mol))
+        })
+        .collect()
+}
+
+/// Runs every pattern over every molecule: `result[i][j]` holds the matches of
+/// `patterns[j]` in `mols[i]`, as returned by [SmartsPattern::find_match]. Each item fails
+/// with [Error::Parse] if one of the patterns is invalid, which can only happen with
+/// patterns built by the deprecated [SmartsPattern::new_from_smarts].
+pub fn find_matches(patterns: &[SmartsPattern], mols: &[Molecule]) -> Vec<Result<Vec<Vec<Vec<usize>>>>> {
+    mol_refs(mols).into_par_iter()
+        .map(|mol| match_all(patterns, mol.0))
+        .collect()
+}
+
+/// [find_matches] on molecules parsed from SMILES, with the parse error of each invalid SMILES
+pub fn find_matches_for_smiles<S: AsRef<str> + Sync>(patterns: &[SmartsPattern], smiles_vec: &[S]) -> Vec<Result<Vec<Vec<Vec<usize>>>>> {
+    smiles_vec.par_iter()
+        .map(|smiles| {
+            let mol = Molecule::from_smiles(smiles.as_ref())?;
+            match_all(patterns, &mol)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test_mod_parallel {
+    use super::*;
+    use crate::error::Error;
+
+    fn smiles_vec() -> Vec<String> {
+        let mut smiles_vec: Vec<String> = (1..50).map(|n| format!("c1ccccc1{}O", "C".repeat(n % 7))).collect();
+        smiles_vec.insert(10, String::from("C1CC("));
+        smiles_vec
+    }
+
+    #[test]
+    fn test_find_matches() {
+        let smiles_vec = smiles_vec();
+        let patterns: Vec<SmartsPattern> = ["[OX2H]", "c1ccccc1", "CC", "[$(cO)]"].iter()
+            .map(|smarts| SmartsPattern::from_smarts(smarts).unwrap())
+            .collect();
+        let results = find_matches_for_smiles(&patterns, &smiles_vec);
+        assert_eq!(results.len(), smiles_vec.len());
+        assert!(results[10].is_err());
+
+        let mols: Vec<Molecule> = smiles_vec.iter().filter_map(|smiles| Molecule::from_smiles(smiles).ok()).collect();
+        let matches: Vec<_> = find_matches(&patterns, &mols).into_iter().map(|result| result.unwrap()).collect();
+        let valid_results: Vec<_> = results.into_iter().filter_map(|result| result.ok()).collect();
+        assert_eq!(matches, valid_results);
+        for (mol_matches, mol) in matches.iter().zip(mols.iter()) {
+            for (pattern_matches, sp) in mol_matches.iter().zip(patterns.iter()) {
+                assert_eq!(pattern_matches, &sp.find_match(mol));
+            }
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_find_matches_invalid_pattern() {
+        let patterns = vec![SmartsPattern::from_smarts("c").unwrap(), SmartsPattern::new_from_smarts("O=C(N")];
+        let mols: Vec<Molecule> = ["CCO", "c1ccccc1"].iter().map(|smiles| Molecule::from_smiles(smiles).unwrap()).collect();
+        assert!(find_matches(&patterns, &mols).iter().all(|result| matches!(result, Err(Error::Parse { .. }))));
+        assert!(find_matches_for_smiles(&patterns, &["CCO"]).iter().all(|result| matches!(result, Err(Error::Parse { .. }))));
+    }
+}
@@ -0,0 +1,242 @@
+//! Openbabel Pattern Sets
+//!
+//! Named SMARTS patterns compiled once and run together against each molecule, e.g. substructure
+//! filters (PAINS, Brenk, reactive groups) or the functional groups of OpenBabel's
+//! `SMARTS_InteLigand.txt`, the pattern file of the FP4 fingerprint.
+//!
+//! ```
+//! use openbabel::molecule::Molecule;
+//! use openbabel::patternset::PatternSet;
+//!
+//! let patterns = PatternSet::parse("
+//! # SMARTS name
+//! [OX2H][CX4] alcohol
+//! [OX2H]c1ccccc1 phenol
+//! C(=O)[Cl,Br,I] acyl halide
+//! ").unwrap();
+//! assert_eq!(patterns.len(), 3);
+//!
+//! let mol = Molecule::from_smiles("OCc1ccccc1O").unwrap();
+//! let hits = patterns.find_matches(&mol);
+//! assert_eq!(hits.iter().map(|hit| hit.name).collect::<Vec<_>>(), vec!["alcohol", "phenol"]);
+//! assert_eq!(hits[0].matches, vec![vec![0, 1]]);
+//! assert_eq!(patterns.hits(&mol), vec![0, 1]);
+//! ```
+//!
+//! Two text formats are read, both skipping blank lines and lines starting with `#`:
+//! - [parse](PatternSet::parse): `SMARTS name` per line, the name defaulting to the SMARTS
+//! - [parse_inteligand](PatternSet::parse_inteligand): `name: SMARTS` per line, as in
+//!   `SMARTS_InteLigand.txt`
+
+use std::path::Path;
+use crate::error::{Error, Result};
+use crate::molecule::Molecule;
+use crate::smartspattern::SmartsPattern;
+use crate::utils::ob_lock;
+use ob_rs::ob;
+
+const INTELIGAND_FILE: &str = "SMARTS_InteLigand.txt";
+
+struct Entry {
+    name: String,
+    pattern: SmartsPattern,
+}
+
+/// The matches of one pattern in a molecule
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatternHit<'a> {
+    /// Position of the pattern in the set
+    pub index: usize,
+    pub name: &'a str,
+    /// Unique mappings, see [SmartsPattern::find_unique_matches]
+    pub matches: Vec<Vec<usize>>,
+}
+
+/// `PatternSet` is `Send + Sync`, one set can screen molecules on several threads. Patterns
+/// with recursive SMARTS (`$(...)`), as many of `SMARTS_InteLigand.txt`, are matched one at a
+/// time across threads, see [SmartsPattern].
+#[derive(Default)]
+pub struct PatternSet {
+    entries: Vec<Entry>,
+}
+
+impl PatternSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compiles `smarts` and appends it under `name`, returning its index in the set
+    pub fn add(&mut self, name: &str, smarts: &str) -> Result<usize> {
+        let pattern = SmartsPattern::from_smarts(smarts)?;
+        self.entries.push(Entry { name: String::from(name), pattern });
+        Ok(self.entries.len() - 1)
+    }
+
+    fn parse_lines(text: &str, split: impl Fn(&str) -> Option<(&str, &str)>) -> Result<Self> {
+        let mut set = Self::new();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, smarts) = split(line).ok_or_else(|| Error::Parse {
+                format: String::from("smarts"),
+                message: format!("line {}: expected a named SMARTS, found '{}'", n + 1, line),
+            })?;
+            set.add(name, smarts).map_err(|err| match err {
+                Error::Parse { format, message } => Error::Parse { format, message: format!("line {}: {}", n + 1, message) },
+                err => err,
+            })?;
+        }
+        Ok(set)
+    }
+
+    /// Reads `SMARTS name` lines; the name is the rest of the line, or the SMARTS if there is none.
+    pub fn parse(text: &str) -> Result<Self> {
+        Self::parse_lines(text, |line| {
+            let (smarts, name) = line.split_once(char::is_whitespace).unwrap_or((line, line));
+            Some((name.trim(), smarts))
+        })
+    }
+
+    /// Reads `name: SMARTS` lines. As OpenBabel does for FP4, the SMARTS is the first word after
+    /// the colon, anything after it is a comment.
+    pub fn parse_inteligand(text: &str) -> Result<Self> {
+        Self::parse_lines(text, |line| {
+            let (name, rest) = line.split_once(':')?;
+            let smarts = rest.split_whitespace().next()?;
+            Some((name.trim(), smarts))
+        })
+    }
+
+    /// [parse](PatternSet::parse) on the content of a file
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// [parse_inteligand](PatternSet::parse_inteligand) on the content of a file
+    pub fn from_inteligand_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::parse_inteligand(&std::fs::read_to_string(path)?)
+    }
+
+    /// The functional groups of the `SMARTS_InteLigand.txt` shipped with OpenBabel, failing with
+    /// [Error::MissingDataFile](crate::Error::MissingDataFile) if it cannot be located.
+    pub fn inteligand() -> Result<Self> {
+        cxx::let_cxx_string!(file_name_cxx = INTELIGAND_FILE);
+        let path = {
+            let _lock = ob_lock();
+            ob::OBDatafile_locate(&file_name_cxx)
+        };
+        if path.is_empty() {
+            return Err(Error::MissingDataFile(String::from(INTELIGAND_FILE)));
+        }
+        Self::from_inteligand_path(path)
+    }
+
+    pub fn len(&self) -> usize { self.entries.len() }
+    pub fn is_empty(&self) -> bool { self.entries.is_empty() }
+
+    pub fn name(&self, index: usize) -> Option<&str> {
+        self.entries.get(index).map(|entry| entry.name.as_str())
+    }
+
+    pub fn pattern(&self, index: usize) -> Option<&SmartsPattern> {
+        self.entries.get(index).map(|entry| &entry.pattern)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.entries.iter().map(|entry| entry.name.as_str())
+    }
+
+    /// Indices of the patterns found in `mol`
+    pub fn hits(&self, mol: &Molecule) -> Vec<usize> {
+        self.entries.iter().enumerate()
+            .filter(|(_, entry)| entry.pattern.has_match(mol))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// The patterns found in `mol` with their atom mappings, in the order of the set
+    pub fn find_matches(&self, mol: &Molecule) -> Vec<PatternHit<'_>> {
+        self.entries.iter().enumerate()
+            .filter_map(|(index, entry)| {
+                let matches = entry.pattern.find_unique_matches(mol);
+                if matches.is_empty() {
+                    return None;
+                }
+                Some(PatternHit { index, name: entry.name.as_str(), matches })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test_mod_patternset {
+    use super::*;
+
+    const INTELIGAND: &str = "# name: SMARTS
+Carboxylic_acid: [CX3](=O)[OX2H1] comment
+Hydroxy: [OX2H]
+
+Primary_amine: [NX3H2]
+Benzene: c1ccccc1
+";
+
+    #[test]
+    fn test_parse() {
+        let set = PatternSet::parse("CC(=O)O acetyl ester\n  # comment\n[NX3H2]\n").unwrap();
+        assert_eq!(set.names().collect::<Vec<_>>(), vec!["acetyl ester", "[NX3H2]"]);
+        assert_eq!(set.pattern(0).unwrap().smarts(), "CC(=O)O");
+        let set = PatternSet::parse_inteligand(INTELIGAND).unwrap();
+        assert_eq!(set.len(), 4);
+        assert_eq!(set.name(0), Some("Carboxylic_acid"));
+        assert_eq!(set.pattern(0).unwrap().smarts(), "[CX3](=O)[OX2H1]");
+        assert_eq!(set.name(4), None);
+
+        match PatternSet::parse("CC ok\nC(C bad\n") {
+            Err(Error::Parse { message, .. }) => assert!(message.starts_with("line 2:")),
+            _ => panic!("invalid SMARTS accepted"),
+        }
+        assert!(matches!(PatternSet::parse_inteligand("no colon here"), Err(Error::Parse { .. })));
+    }
+
+    #[test]
+    fn test_find_matches() {
+        let set = PatternSet::parse_inteligand(INTELIGAND).unwrap();
+        let mol = Molecule::from_smiles("NCC(=O)O").unwrap();
+        let hits = set.find_matches(&mol);
+        assert_eq!(hits.len(), 3);
+        assert_eq!(hits[0], PatternHit { index: 0, name: "Carboxylic_acid", matches: vec![vec![2, 3, 4]] });
+        assert_eq!(hits[1].name, "Hydroxy");
+        assert_eq!(hits[2].matches, vec![vec![0]]);
+        assert_eq!(set.hits(&mol), vec![0, 1, 2]);
+        assert!(set.hits(&Molecule::from_smiles("CCCC").unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_recursive_in_threads() {
+        let set = PatternSet::parse("[OX2H][$(C=O)] carboxylic acid\n[$([NX3H2]),$([NX3H1])]C amine\n").unwrap();
+        let smiles = ["NCC(=O)O", "CCNC", "OC(=O)c1ccccc1", "CCCC"];
+        let expected: Vec<Vec<usize>> = smiles.iter().map(|s| set.hits(&Molecule::from_smiles(s).unwrap())).collect();
+        assert_eq!(expected, vec![vec![0, 1], vec![1], vec![0], vec![]]);
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..20 {
+                        for (s, hits) in smiles.iter().zip(expected.iter()) {
+                            assert_eq!(&set.hits(&Molecule::from_smiles(s).unwrap()), hits);
+                        }
+                    }
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn test_inteligand() {
+        let set = PatternSet::inteligand().unwrap();
+        assert!(set.len() > 200);
+        let mol = Molecule::from_smiles("OC(=O)c1ccccc1").unwrap();
+        assert!(set.find_matches(&mol).iter().any(|hit| hit.name.contains("Carboxylic_acid")));
+    }
+}
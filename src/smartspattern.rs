@@ -6,39 +6,153 @@
 //! use openbabel::molecule;
 //! use openbabel::smartspattern;
 //! 
-//! let mol = molecule::Molecule::new_from_smiles("NCC(=O)NCC");
-//! let sp = smartspattern::SmartsPattern::new_from_smarts("O=CN*");
+//! let mol = molecule::Molecule::from_smiles("NCC(=O)NCC").unwrap();
+//! let sp = smartspattern::SmartsPattern::from_smarts("O=CN*").unwrap();
 //! assert_eq!(sp.num_atoms(), 4);
 //! assert_eq!(sp.num_bonds(), 3);
 //! let match_result = sp.find_match(&mol);
-//! assert_eq!(sp.num_matches(), 1);
-//! assert_eq!(vec![vec![4, 3, 5, 6]], match_result.as_slice());
+//! assert_eq!(match_result.len(), 1);
+//! assert_eq!(vec![vec![3, 2, 4, 5]], match_result.as_slice());
+//! assert!(sp.has_match(&mol));
+//! ```
+//!
+//! # Matching modes
+//! ```
+//! use openbabel::molecule::Molecule;
+//! use openbabel::smartspattern::SmartsPattern;
+//!
+//! let mol = Molecule::from_smiles("OCCO").unwrap();
+//! let sp = SmartsPattern::from_smarts("OC").unwrap();
+//! assert_eq!(sp.find_match(&mol).len(), 2);
+//! assert_eq!(sp.find_first_match(&mol), Some(vec![0, 1]));
+//! assert_eq!(sp.find_match_limited(&mol, 1), vec![vec![0, 1]]);
+//!
+//! // symmetric pattern: all mappings, or one mapping per set of atoms
+//! let sp = SmartsPattern::from_smarts("CC").unwrap();
+//! assert_eq!(sp.find_match(&mol), vec![vec![1, 2], vec![2, 1]]);
+//! assert_eq!(sp.find_unique_matches(&mol), vec![vec![1, 2]]);
 //! ```
 
 use ob_rs::ob;
 use super::molecule;
+use crate::error::{Error, Result};
+use crate::errorlog;
+use crate::utils::ob_lock;
+
+/// Matching modes of OBSmartsPattern::MatchType
+#[derive(Clone, Copy)]
+enum MatchType {
+    All = 0,
+    Single = 1,
+    AllUnique = 2,
+}
 
+/// `SmartsPattern` is `Send + Sync`: matches are returned by each call and never stored
+/// in the pattern, so one pattern can search molecules on several threads. Patterns with
+/// recursive SMARTS (`$(...)`) are matched one at a time, as OpenBabel caches their atom
+/// matches in a global.
 pub struct SmartsPattern {
-    ob_sp: cxx::UniquePtr<ob::OBSmartsPattern>
+    ob_sp: cxx::UniquePtr<ob::OBSmartsPattern>,
+    smarts: String,
+    recursive: bool,
 }
 
+// SAFETY: the OBSmartsPattern is owned by this SmartsPattern alone; the SMARTS parser
+// runs under the lock of `utils::ob_lock`, and molecules are perceived before matching.
+unsafe impl Send for SmartsPattern {}
+// SAFETY: after parsing, only the const members of OBSmartsPattern are called (HasMatch and
+// the Match overload which fills a caller-owned list), which leave the pattern unchanged.
+// They share no state between calls except the file-static cache of recursive SMARTS in
+// parsmart.cpp, so recursive patterns are matched under the lock of `utils::ob_lock`.
+unsafe impl Sync for SmartsPattern {}
+
 impl SmartsPattern {
+    /// Compiles a SMARTS pattern, failing with [Error::Parse](crate::Error::Parse)
+    /// if OpenBabel rejects it.
+    pub fn from_smarts(smarts: &str) -> Result<Self> {
+        cxx::let_cxx_string!(smarts_cxx = smarts);
+        let _lock = ob_lock();
+        let error_count = errorlog::error_count();
+        let ob_sp = ob::OBSmartsPattern_from_smarts(&smarts_cxx);
+        if !ob::OBSmartsPattern_is_valid(&ob_sp) {
+            let message = match errorlog::errors_since(error_count) {
+                Some(ob_message) => format!("invalid SMARTS '{}': {}", smarts, ob_message),
+                None => format!("invalid SMARTS '{}'", smarts),
+            };
+            return Err(Error::Parse { format: String::from("smarts"), message });
+        }
+        Ok(Self::new(ob_sp, smarts))
+    }
+
+    fn new(ob_sp: cxx::UniquePtr<ob::OBSmartsPattern>, smarts: &str) -> Self {
+        Self { ob_sp, smarts: String::from(smarts), recursive: smarts.contains("$(") }
+    }
+
+    #[deprecated(since = "0.5.5", note = "use `SmartsPattern::from_smarts`, which reports parse errors")]
     pub fn new_from_smarts(smarts: &str) -> Self {
         cxx::let_cxx_string!(smarts_cxx = smarts);
-        Self { ob_sp: ob::OBSmartsPattern_from_smarts(&smarts_cxx) }
+        let _lock = ob_lock();
+        Self::new(ob::OBSmartsPattern_from_smarts(&smarts_cxx), smarts)
     }
 
+    /// False for a pattern which OpenBabel could not parse, see
+    /// [new_from_smarts](SmartsPattern::new_from_smarts)
+    pub fn is_valid(&self) -> bool { ob::OBSmartsPattern_is_valid(&self.ob_sp) }
+
+    /// The SMARTS string this pattern was compiled from
+    pub fn smarts(&self) -> &str { self.smarts.as_str() }
+
     pub fn num_atoms(&self) -> u32 { ob::OBSmartsPattern_num_atoms(&self.ob_sp) }
     pub fn num_bonds(&self) -> u32 { ob::OBSmartsPattern_num_bonds(&self.ob_sp) }
-    pub fn num_matches(&self) -> u32 { ob::OBSmartsPattern_num_matches(&self.ob_sp) }
-
-    pub fn find_match(&self, mol: &molecule::Molecule) -> Vec<Vec<i32>> { // 'match' is keyword in rust, use 'find_match' instead
-        ob::OBSmartsPattern_match(&self.ob_sp, &mol.ob_mol)
-            .as_slice()
-            .split(|&i| i == -1)
-            .filter(|v| v.len() > 0)
-            .map(|v| v.to_vec())
-            .collect::<Vec<Vec<i32>>>()
+
+    /// Always 0: a pattern shared by several threads has no last search. Kept for compatibility.
+    #[deprecated(since = "0.5.5", note = "use the length of the result of `find_match`")]
+    pub fn num_matches(&self) -> u32 { 0 }
+
+    /// Whether `mol` contains the pattern; stops at the first match
+    pub fn has_match(&self, mol: &molecule::Molecule) -> bool {
+        mol.perceive();
+        let _lock = self.recursive.then(ob_lock);
+        ob::OBSmartsPattern_has_match(&self.ob_sp, &mol.ob_mol)
+    }
+
+    /// Every mapping of the pattern atoms onto atoms of `mol`, including those which only differ
+    /// by a symmetry of the pattern. Atom indices are 0-based, as in [Molecule::atom](molecule::Molecule::atom),
+    /// in the order of the pattern atoms.
+    pub fn find_match(&self, mol: &molecule::Molecule) -> Vec<Vec<usize>> { // 'match' is keyword in rust, use 'find_match' instead
+        self.match_with(mol, MatchType::All, 0)
+    }
+
+    /// Like [find_match](SmartsPattern::find_match), with one mapping per distinct set of
+    /// matched atoms (OBSmartsPattern::GetUMapList)
+    pub fn find_unique_matches(&self, mol: &molecule::Molecule) -> Vec<Vec<usize>> {
+        self.match_with(mol, MatchType::AllUnique, 0)
+    }
+
+    /// The first mapping found, the search stops there
+    pub fn find_first_match(&self, mol: &molecule::Molecule) -> Option<Vec<usize>> {
+        self.match_with(mol, MatchType::Single, 0).pop()
+    }
+
+    /// The first `max_matches` mappings of [find_match](SmartsPattern::find_match). OpenBabel
+    /// still enumerates every mapping; the limit bounds the result size.
+    pub fn find_match_limited(&self, mol: &molecule::Molecule, max_matches: usize) -> Vec<Vec<usize>> {
+        if max_matches == 0 {
+            return vec![];
+        }
+        self.match_with(mol, MatchType::All, max_matches.min(u32::MAX as usize) as u32)
+    }
+
+    fn match_with(&self, mol: &molecule::Molecule, match_type: MatchType, max_matches: u32) -> Vec<Vec<usize>> {
+        mol.perceive();
+        let matches = {
+            let _lock = self.recursive.then(ob_lock);
+            ob::OBSmartsPattern_match(&self.ob_sp, &mol.ob_mol, match_type as u32, max_matches)
+        };
+        matches.offsets
+            .windows(2)
+            .map(|bounds| matches.atoms[bounds[0] as usize..bounds[1] as usize].iter().map(|&idx| idx as usize).collect())
+            .collect()
     }
 }
 
@@ -48,26 +162,99 @@ mod test_mod_smartspattern {
 
     #[test]
     fn test_match() {
-        let sp = SmartsPattern::new_from_smarts("O=CN*");
+        let sp = SmartsPattern::from_smarts("O=CN*").unwrap();
         assert_eq!(sp.num_atoms(), 4);
         assert_eq!(sp.num_bonds(), 3);
-        let mol_1 = molecule::Molecule::new_from_smiles("NCC(=O)NCC");
+        let mol_1 = molecule::Molecule::from_smiles("NCC(=O)NCC").unwrap();
         let match_result_1 = sp.find_match(&mol_1);
-        assert_eq!(sp.num_matches(), 1);
-        assert_eq!(vec![vec![4, 3, 5, 6]], match_result_1);
-        let mol_2 = molecule::Molecule::new_from_smiles("NCCNCC");
+        assert_eq!(match_result_1.len(), 1);
+        assert_eq!(vec![vec![3, 2, 4, 5]], match_result_1);
+        let mol_2 = molecule::Molecule::from_smiles("NCCNCC").unwrap();
         let match_result_2 = sp.find_match(&mol_2);
-        assert_eq!(sp.num_matches(), 0);
+        assert!(!sp.has_match(&mol_2));
         assert_eq!(0, match_result_2.as_slice().len());
-        let mol_3 = molecule::Molecule::new_from_smiles("CNC(=O)C(=O)NCC");
+        let mol_3 = molecule::Molecule::from_smiles("CNC(=O)C(=O)NCC").unwrap();
         let match_result_3 = sp.find_match(&mol_3);
-        assert_eq!(sp.num_matches(), 2);
-        assert_eq!(vec![vec![4, 3, 2, 1], vec![6, 5, 7, 8]], match_result_3.as_slice());
+        assert!(sp.has_match(&mol_3));
+        assert_eq!(vec![vec![3, 2, 1, 0], vec![5, 4, 6, 7]], match_result_3.as_slice());
         // symmetric query smarts 
-        let sp_4 = SmartsPattern::new_from_smarts("c1ccccc1N=O");
-        let mol_4 = molecule::Molecule::new_from_smiles("COc1cc([N+](=O)[O-])c(OC)cc1CC(C)N");
+        let sp_4 = SmartsPattern::from_smarts("c1ccccc1N=O").unwrap();
+        let mol_4 = molecule::Molecule::from_smiles("COc1cc([N+](=O)[O-])c(OC)cc1CC(C)N").unwrap();
         let match_result_4 = sp_4.find_match(&mol_4);
-        assert_eq!(sp_4.num_matches(), 2);
-        assert_eq!(vec![vec![4, 3, 13, 12, 9, 5, 6, 7], vec![9, 12, 13, 3, 4, 5, 6, 7]], match_result_4.as_slice());
+        assert_eq!(vec![vec![3, 2, 12, 11, 8, 4, 5, 6], vec![8, 11, 12, 2, 3, 4, 5, 6]], match_result_4.as_slice());
+        assert_eq!(vec![vec![3, 2, 12, 11, 8, 4, 5, 6]], sp_4.find_unique_matches(&mol_4));
+        assert_eq!(Some(vec![3, 2, 12, 11, 8, 4, 5, 6]), sp_4.find_first_match(&mol_4));
+        assert_eq!(None, sp.find_first_match(&mol_2));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_match_limited() {
+        let sp = SmartsPattern::from_smarts("[#6]").unwrap();
+        let mol = molecule::Molecule::from_smiles("CCCCCC").unwrap();
+        assert_eq!(sp.find_match(&mol).len(), 6);
+        assert_eq!(sp.find_match_limited(&mol, 4), vec![vec![0], vec![1], vec![2], vec![3]]);
+        assert_eq!(sp.find_match_limited(&mol, 10).len(), 6);
+        assert!(sp.find_match_limited(&mol, 0).is_empty());
+        // results do not depend on earlier calls
+        let mol_2 = molecule::Molecule::from_smiles("CC").unwrap();
+        assert_eq!(sp.find_match(&mol_2).len(), 2);
+        assert_eq!(sp.find_match_limited(&mol, 4).len(), 4);
+    }
+
+    #[test]
+    fn test_match_in_threads() {
+        let sp = std::sync::Arc::new(SmartsPattern::from_smarts("O=CN*").unwrap());
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let sp = sp.clone();
+                std::thread::spawn(move || {
+                    let mol = molecule::Molecule::from_smiles("CNC(=O)C(=O)NCC").unwrap();
+                    (0..20).map(|_| sp.find_match(&mol)).collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        for handle in handles {
+            for match_result in handle.join().unwrap() {
+                assert_eq!(vec![vec![3, 2, 1, 0], vec![5, 4, 6, 7]], match_result);
+            }
+        }
+    }
+
+    #[test]
+    fn test_recursive_match_in_threads() {
+        let sp = std::sync::Arc::new(SmartsPattern::from_smarts("[$(C=O)][$([NX3;H1,H2])]").unwrap());
+        let mol = molecule::Molecule::from_smiles("CNC(=O)C(=O)NCC").unwrap();
+        let expected = sp.find_match(&mol);
+        assert_eq!(expected, vec![vec![2, 1], vec![4, 6]]);
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let sp = sp.clone();
+                std::thread::spawn(move || {
+                    // molecules of different sizes, so that the cached recursive matches differ
+                    let other = molecule::Molecule::from_smiles(&format!("{}C(=O)N", "C".repeat(i + 1))).unwrap();
+                    let mol = molecule::Molecule::from_smiles("CNC(=O)C(=O)NCC").unwrap();
+                    (0..20).map(|_| (sp.has_match(&other), sp.find_match(&mol))).collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        for handle in handles {
+            for (other_has_match, match_result) in handle.join().unwrap() {
+                assert!(other_has_match);
+                assert_eq!(expected, match_result);
+            }
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_num_matches() {
+        let sp = SmartsPattern::from_smarts("O=CN*").unwrap();
+        assert_eq!(sp.find_match(&molecule::Molecule::from_smiles("CNC(=O)C(=O)NCC").unwrap()).len(), 2);
+        assert_eq!(sp.num_matches(), 0);
+    }
+
+    #[test]
+    fn test_invalid_smarts() {
+        assert!(matches!(SmartsPattern::from_smarts("O=C(N"), Err(Error::Parse { .. })));
+    }
+}
@@ -1,3 +1,77 @@
+use std::cell::{Cell, RefCell};
+use std::marker::PhantomData;
+use std::sync::{Mutex, MutexGuard};
+
 pub fn cxx_vector_into_vector<T: cxx::vector::VectorElement + Clone>(vec_cxx: &cxx::Vector<T>) -> Vec<T> {
     vec_cxx.iter().cloned().collect()
-}
\ No newline at end of file
+}
+
+/// Serializes calls into OpenBabel code which uses its global state: obErrorLog, the atom and
+/// aromatic typers, and the format, fingerprint and force field plugin instances.
+static OB_LOCK: Mutex<()> = Mutex::new(());
+
+thread_local! {
+    static OB_LOCK_DEPTH: Cell<usize> = const { Cell::new(0) };
+    static OB_LOCK_GUARD: RefCell<Option<MutexGuard<'static, ()>>> = const { RefCell::new(None) };
+}
+
+/// Guard of [ob_lock]. The thread holds the lock until the last of its guards is dropped,
+/// in whatever order they are dropped.
+pub(crate) struct ObLock {
+    _not_send: PhantomData<*const ()>,
+}
+
+/// Takes the OpenBabel lock. The lock is reentrant, so wrappers holding it can call each other.
+pub(crate) fn ob_lock() -> ObLock {
+    OB_LOCK_DEPTH.with(|depth| {
+        if depth.get() == 0 {
+            let guard = OB_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            OB_LOCK_GUARD.with(|g| *g.borrow_mut() = Some(guard));
+        }
+        depth.set(depth.get() + 1);
+    });
+    ObLock { _not_send: PhantomData }
+}
+
+impl Drop for ObLock {
+    fn drop(&mut self) {
+        OB_LOCK_DEPTH.with(|depth| {
+            depth.set(depth.get() - 1);
+            if depth.get() == 0 {
+                OB_LOCK_GUARD.with(|g| g.borrow_mut().take());
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod test_mod_utils {
+    use super::*;
+
+    #[test]
+    fn test_ob_lock_reentrant() {
+        let outer = ob_lock();
+        let inner = ob_lock();
+        drop(inner);
+        drop(outer);
+        let handle = std::thread::spawn(|| {
+            let _lock = ob_lock();
+        });
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_ob_lock_out_of_order() {
+        let first = ob_lock();
+        let second = ob_lock();
+        drop(first);
+        // still held by `second`
+        let handle = std::thread::spawn(|| OB_LOCK.try_lock().is_err());
+        assert!(handle.join().unwrap());
+        drop(second);
+        let handle = std::thread::spawn(|| {
+            let _lock = ob_lock();
+        });
+        handle.join().unwrap();
+    }
+}